@@ -1,9 +1,15 @@
 use std::{
     fs::File,
-    io::{BufReader, Cursor, Read, Result, Seek, SeekFrom},
+    io::{BufRead, BufReader, Cursor, Error, ErrorKind, Read, Result, Seek, SeekFrom},
     path::Path,
 };
 
+mod mmap;
+mod stream;
+
+pub use mmap::MmapFile;
+pub use stream::{Compression, Stream};
+
 pub struct Buffer {
     cursor: Cursor<Vec<u8>>,
 }
@@ -32,23 +38,34 @@ impl Seek for Buffer {
     }
 }
 
-impl Peek for Buffer {}
+impl Peek for Buffer {
+    fn peek(&mut self, ahead: usize, size: usize) -> Result<&[u8]> {
+        self.cursor.peek(ahead, size)
+    }
+}
 
 pub struct BufFile {
     reader: BufReader<File>,
+    window: Vec<u8>, // Holds peeks that do not fit in the remainder of the read buffer
 }
 
 impl BufFile {
     pub fn new<P: AsRef<Path>>(filepath: P) -> Result<Self> {
         let file = File::open(filepath)?;
         let reader = BufReader::new(file);
-        Ok(Self { reader })
+        Ok(Self {
+            reader,
+            window: Vec::new(),
+        })
     }
 
     pub fn with_capacity<P: AsRef<Path>>(capacity: usize, filepath: P) -> Result<Self> {
         let file = File::open(filepath)?;
         let reader = BufReader::with_capacity(capacity, file);
-        Ok(Self { reader })
+        Ok(Self {
+            reader,
+            window: Vec::new(),
+        })
     }
 
     pub fn position(&mut self) -> Result<u64> {
@@ -69,27 +86,60 @@ impl Seek for BufFile {
     }
 }
 
-impl Peek for BufFile {}
+impl Peek for BufFile {
+    fn peek(&mut self, ahead: usize, size: usize) -> Result<&[u8]> {
+        // Most peeks land in the bytes already buffered by the reader
+        if self.reader.fill_buf()?.len() >= ahead + size {
+            return Ok(&self.reader.buffer()[ahead..ahead + size]);
+        }
+
+        // Otherwise read them into the window and restore the position (dropping the buffer)
+        let original_pos = self.reader.stream_position()?;
+        self.window.resize(size, 0);
+        let result = (|| {
+            self.reader.seek(SeekFrom::Current(ahead as i64))?;
+            self.reader.read_exact(&mut self.window)
+        })();
+        self.reader.seek(SeekFrom::Start(original_pos))?;
+        result?;
+
+        Ok(&self.window)
+    }
+}
+
+// Records of the text versions are parsed from memory
+impl Peek for Cursor<Vec<u8>> {
+    fn peek(&mut self, ahead: usize, size: usize) -> Result<&[u8]> {
+        let from = self.position() as usize + ahead;
+        slice_at(self.get_ref(), from, size)
+    }
+}
+
+// Lets callers pick a source at runtime (e.g. `Box<dyn Peek>`) without losing its own `peek`
+impl<P: Peek + ?Sized> Peek for Box<P> {
+    fn peek(&mut self, ahead: usize, size: usize) -> Result<&[u8]> {
+        (**self).peek(ahead, size)
+    }
+}
 
 /// A trait for peeking ahead in a readable stream without consuming the data.
 ///
 /// This trait extends `Read` and `Seek` to provide the ability to look ahead
 /// at data that will be read in the future, without advancing the current
-/// position in the stream.
+/// position in the stream. Peeked bytes are borrowed from the source (its
+/// data or an internal window), so peeking neither allocates nor, in most
+/// cases, seeks.
 pub trait Peek: Read + Seek {
-    fn peek(&mut self, ahead: usize, size: usize) -> Result<Vec<u8>> {
-        let original_pos = self.stream_position()?;
-        let result = (|| {
-            self.seek(SeekFrom::Current(ahead as i64))?;
-            let mut buf = vec![0; size];
-            self.read_exact(&mut buf)?;
-
-            Ok(buf)
-        })();
-        self.seek(SeekFrom::Start(original_pos))?;
+    /// Returns the `size` bytes found `ahead` bytes past the current position.
+    ///
+    /// Fails with `ErrorKind::UnexpectedEof` if the source ends before them.
+    fn peek(&mut self, ahead: usize, size: usize) -> Result<&[u8]>;
+}
 
-        result
-    }
+// Returns `data[from..from + size]` or an end-of-file error if `data` is too short
+fn slice_at(data: &[u8], from: usize, size: usize) -> Result<&[u8]> {
+    data.get(from..from + size)
+        .ok_or_else(|| Error::new(ErrorKind::UnexpectedEof, "failed to fill whole buffer"))
 }
 
 #[cfg(test)]
@@ -132,4 +182,32 @@ mod tests {
         assert_eq!(res, b"ghijk");
         assert_eq!(buffile.reader.stream_position().unwrap(), 0);
     }
+
+    #[test]
+    fn peek_after_read() {
+        let file = assert_fs::NamedTempFile::new("test.txt").unwrap();
+        file.write_str("abcdefghijkl").unwrap(); // 12 bytes
+        let mut buffile = BufFile::with_capacity(4, file.path()).unwrap();
+
+        let mut buf = [0; 3];
+        buffile.read_exact(&mut buf).unwrap();
+        assert_eq!(buffile.peek(0, 1).unwrap(), b"d"); // Within the read buffer
+        assert_eq!(buffile.peek(1, 4).unwrap(), b"efgh"); // Beyond the read buffer
+        assert_eq!(buffile.position().unwrap(), 3);
+
+        buffile.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"def");
+    }
+
+    #[test]
+    fn peek_cursor() {
+        let mut cursor = Cursor::new(b"abcdefghijkl".to_vec());
+        cursor.set_position(2);
+        assert_eq!(cursor.peek(1, 3).unwrap(), b"def");
+        assert_eq!(cursor.position(), 2);
+        assert_eq!(
+            cursor.peek(8, 4).unwrap_err().kind(),
+            ErrorKind::UnexpectedEof
+        );
+    }
 }
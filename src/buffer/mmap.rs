@@ -0,0 +1,110 @@
+use std::{
+    fs::File,
+    io::{Cursor, Read, Result, Seek, SeekFrom},
+    path::Path,
+};
+
+use memmap2::Mmap;
+
+use super::{slice_at, Peek};
+
+/// A source backed by a read-only memory map of a file.
+///
+/// Unlike `Buffer`, the file is not loaded into memory upfront: pages are brought in by the
+/// operating system as they are read (and can be evicted again), so files larger than the
+/// available memory can be processed. Peeking is a plain view into the map.
+pub struct MmapFile {
+    cursor: Cursor<Mmap>,
+}
+
+impl MmapFile {
+    pub fn new<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let file = File::open(path)?;
+        // SAFETY: The map is read-only. As with any memory map, the file must not be truncated
+        // or modified by another process while it is mapped.
+        let mmap = unsafe { Mmap::map(&file)? };
+        #[cfg(unix)]
+        mmap.advise(memmap2::Advice::Sequential)?;
+        Ok(Self {
+            cursor: Cursor::new(mmap),
+        })
+    }
+
+    pub fn position(&self) -> u64 {
+        self.cursor.position()
+    }
+
+    pub fn len(&self) -> u64 {
+        self.cursor.get_ref().len() as u64
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Read for MmapFile {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        self.cursor.read(buf)
+    }
+}
+
+impl Seek for MmapFile {
+    fn seek(&mut self, pos: SeekFrom) -> Result<u64> {
+        self.cursor.seek(pos)
+    }
+}
+
+impl Peek for MmapFile {
+    fn peek(&mut self, ahead: usize, size: usize) -> Result<&[u8]> {
+        let from = self.cursor.position() as usize + ahead;
+        slice_at(self.cursor.get_ref(), from, size)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::ErrorKind;
+
+    use assert_fs::prelude::*;
+
+    use super::*;
+
+    #[test]
+    fn peek_a_bit() {
+        let file = assert_fs::NamedTempFile::new("test.txt").unwrap();
+        file.write_str("abcdefghijkl").unwrap(); // 12 bytes
+        let mut mmap = MmapFile::new(file.path()).unwrap();
+        assert_eq!(mmap.peek(4, 4).unwrap(), b"efgh");
+        assert_eq!(mmap.position(), 0);
+
+        mmap.seek(SeekFrom::Current(2)).unwrap();
+        assert_eq!(mmap.peek(0, 3).unwrap(), b"cde");
+        let mut buf = [0; 4];
+        mmap.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"cdef");
+    }
+
+    #[test]
+    fn peek_too_far() {
+        let file = assert_fs::NamedTempFile::new("test.txt").unwrap();
+        file.write_str("abcd").unwrap(); // 4 bytes
+        let mut mmap = MmapFile::new(file.path()).unwrap();
+        let err = mmap.peek(0, 8).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+        assert_eq!(mmap.position(), 0);
+
+        // Seeking past the end is allowed, peeking there is not
+        mmap.seek(SeekFrom::Start(10)).unwrap();
+        assert!(mmap.peek(0, 1).is_err());
+    }
+
+    #[test]
+    fn maps_empty_file() {
+        let file = assert_fs::NamedTempFile::new("test.txt").unwrap();
+        file.touch().unwrap();
+        let mut mmap = MmapFile::new(file.path()).unwrap();
+        assert!(mmap.is_empty());
+        assert_eq!(mmap.read(&mut [0; 4]).unwrap(), 0);
+    }
+}
@@ -0,0 +1,282 @@
+use std::{
+    fs::File,
+    io::{self, BufReader, Error, ErrorKind, Read, Result, Seek, SeekFrom},
+    path::Path,
+};
+
+use flate2::read::MultiGzDecoder;
+
+use super::Peek;
+
+// Number of bytes requested from the underlying reader at a time
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// A forward-only source that reads its input on the fly, e.g. while decompressing it.
+///
+/// Unlike `Buffer` and `BufFile`, the underlying reader cannot seek. Instead, `Stream` keeps a
+/// window of the bytes that have been read ahead: peeking fills the window without consuming it,
+/// and seeking forward consumes it (and discards anything beyond). Seeking backward is not
+/// supported.
+pub struct Stream<R> {
+    reader: R,
+    window: Vec<u8>,
+    start: usize,  // Index of the next unread byte in `window`
+    position: u64, // Stream position of the next unread byte
+}
+
+impl<R: Read> Stream<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            window: Vec::with_capacity(CHUNK_SIZE),
+            start: 0,
+            position: 0,
+        }
+    }
+
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+
+    fn available(&self) -> usize {
+        self.window.len() - self.start
+    }
+
+    // Reads from the underlying reader until at least `size` unread bytes are in the window or
+    // the input is exhausted. Returns the number of unread bytes.
+    fn fill(&mut self, size: usize) -> Result<usize> {
+        if self.available() >= size {
+            return Ok(self.available());
+        }
+
+        // Drop consumed bytes before growing the window
+        self.window.drain(..self.start);
+        self.start = 0;
+
+        while self.window.len() < size {
+            let len = self.window.len();
+            self.window.resize(len + CHUNK_SIZE.max(size - len), 0);
+            match self.reader.read(&mut self.window[len..]) {
+                Ok(n) => {
+                    self.window.truncate(len + n);
+                    if n == 0 {
+                        break;
+                    }
+                }
+                Err(e) => {
+                    self.window.truncate(len);
+                    if e.kind() != ErrorKind::Interrupted {
+                        return Err(e);
+                    }
+                }
+            }
+        }
+
+        Ok(self.available())
+    }
+
+    fn consume(&mut self, amount: usize) {
+        self.start += amount;
+        self.position += amount as u64;
+    }
+
+    fn skip(&mut self, amount: u64) -> Result<()> {
+        let buffered = (self.available() as u64).min(amount);
+        self.consume(buffered as usize);
+
+        let remaining = amount - buffered;
+        if remaining > 0 {
+            let skipped = io::copy(&mut (&mut self.reader).take(remaining), &mut io::sink())?;
+            self.position += skipped;
+        }
+
+        Ok(())
+    }
+}
+
+impl Stream<Box<dyn Read>> {
+    /// Opens a gzip-compressed file (including multi-member files).
+    pub fn gzip<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let file = BufReader::new(File::open(path)?);
+        Ok(Self::new(Box::new(MultiGzDecoder::new(file))))
+    }
+
+    /// Opens a zstd-compressed file.
+    pub fn zstd<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let file = File::open(path)?;
+        Ok(Self::new(Box::new(zstd::Decoder::new(file)?)))
+    }
+
+    /// Opens a file, decompressing it if its extension is `.gz` or `.zst`.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        match Compression::from_path(&path) {
+            Some(Compression::Gzip) => Self::gzip(path),
+            Some(Compression::Zstd) => Self::zstd(path),
+            None => Ok(Self::new(Box::new(File::open(path)?))),
+        }
+    }
+}
+
+impl<R: Read> Read for Stream<R> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        let available = self.fill(1)?;
+        let n = available.min(buf.len());
+        buf[..n].copy_from_slice(&self.window[self.start..self.start + n]);
+        self.consume(n);
+        Ok(n)
+    }
+}
+
+impl<R: Read> Seek for Stream<R> {
+    fn seek(&mut self, pos: SeekFrom) -> Result<u64> {
+        let target = match pos {
+            SeekFrom::Start(offset) => offset as i128,
+            SeekFrom::Current(offset) => self.position as i128 + offset as i128,
+            SeekFrom::End(_) => {
+                return Err(Error::new(
+                    ErrorKind::Unsupported,
+                    "Streams cannot seek from the end",
+                ))
+            }
+        };
+        if target < self.position as i128 {
+            return Err(Error::new(
+                ErrorKind::Unsupported,
+                "Streams cannot seek backward",
+            ));
+        }
+
+        self.skip((target - self.position as i128) as u64)?;
+        Ok(self.position)
+    }
+}
+
+impl<R: Read> Peek for Stream<R> {
+    fn peek(&mut self, ahead: usize, size: usize) -> Result<&[u8]> {
+        if self.fill(ahead + size)? < ahead + size {
+            return Err(Error::new(
+                ErrorKind::UnexpectedEof,
+                "failed to fill whole buffer",
+            ));
+        }
+        let from = self.start + ahead;
+        Ok(&self.window[from..from + size])
+    }
+}
+
+/// Compression formats recognized by `Stream::open`.
+#[derive(Debug, PartialEq)]
+pub enum Compression {
+    Gzip,
+    Zstd,
+}
+
+impl Compression {
+    /// Returns the compression format implied by the extension of `path`, if any.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Option<Self> {
+        match path.as_ref().extension()?.to_str()? {
+            "gz" | "gzip" => Some(Compression::Gzip),
+            "zst" | "zstd" => Some(Compression::Zstd),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Cursor, Write};
+
+    use assert_fs::{prelude::*, NamedTempFile};
+    use flate2::{write::GzEncoder, Compression as Level};
+
+    use super::*;
+
+    #[test]
+    fn peek_without_consuming() {
+        let mut stream = Stream::new(Cursor::new(b"abcdefghijkl".to_vec()));
+        assert_eq!(stream.peek(4, 4).unwrap(), b"efgh");
+        assert_eq!(stream.position(), 0);
+
+        let mut buf = [0; 3];
+        stream.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"abc");
+        assert_eq!(stream.peek(0, 2).unwrap(), b"de");
+    }
+
+    #[test]
+    fn peek_too_far() {
+        let mut stream = Stream::new(Cursor::new(b"abcd".to_vec()));
+        let err = stream.peek(0, 8).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+        assert_eq!(stream.position(), 0);
+    }
+
+    #[test]
+    fn seeks_forward_only() {
+        let mut stream = Stream::new(Cursor::new(b"abcdefghijkl".to_vec()));
+        stream.peek(0, 2).unwrap();
+
+        assert_eq!(stream.seek(SeekFrom::Current(5)).unwrap(), 5);
+        assert_eq!(stream.stream_position().unwrap(), 5);
+        assert_eq!(stream.peek(0, 1).unwrap(), b"f");
+        assert_eq!(stream.seek(SeekFrom::Start(10)).unwrap(), 10);
+        assert_eq!(stream.peek(0, 2).unwrap(), b"kl");
+
+        assert!(stream.seek(SeekFrom::Current(-1)).is_err());
+        assert!(stream.seek(SeekFrom::End(0)).is_err());
+    }
+
+    #[test]
+    fn peek_beyond_chunk() {
+        let data: Vec<u8> = (0..3 * CHUNK_SIZE).map(|i| i as u8).collect();
+        let mut stream = Stream::new(Cursor::new(data.clone()));
+
+        stream
+            .seek(SeekFrom::Current(CHUNK_SIZE as i64 - 2))
+            .unwrap();
+        let res = stream.peek(0, CHUNK_SIZE + 4).unwrap();
+        assert_eq!(res, &data[CHUNK_SIZE - 2..2 * CHUNK_SIZE + 2]);
+
+        let mut rest = vec![];
+        stream.read_to_end(&mut rest).unwrap();
+        assert_eq!(rest, &data[CHUNK_SIZE - 2..]);
+    }
+
+    #[test]
+    fn reads_gzip_file() {
+        let file = NamedTempFile::new("test.bin.gz").unwrap();
+        let mut encoder = GzEncoder::new(vec![], Level::default());
+        encoder.write_all(b"abcdefghijkl").unwrap();
+        file.write_binary(&encoder.finish().unwrap()).unwrap();
+
+        let mut stream = Stream::open(file.path()).unwrap();
+        assert_eq!(stream.peek(2, 3).unwrap(), b"cde");
+        let mut contents = String::new();
+        stream.read_to_string(&mut contents).unwrap();
+        assert_eq!(contents, "abcdefghijkl");
+    }
+
+    #[test]
+    fn reads_zstd_file() {
+        let file = NamedTempFile::new("test.bin.zst").unwrap();
+        file.write_binary(&zstd::encode_all(&b"abcdefghijkl"[..], 0).unwrap())
+            .unwrap();
+
+        let mut stream = Stream::open(file.path()).unwrap();
+        stream.seek(SeekFrom::Current(6)).unwrap();
+        assert_eq!(stream.peek(0, 6).unwrap(), b"ghijkl");
+    }
+
+    #[test]
+    fn detects_compression_from_extension() {
+        assert_eq!(
+            Compression::from_path("01302019.NASDAQ_ITCH50.gz"),
+            Some(Compression::Gzip)
+        );
+        assert_eq!(
+            Compression::from_path("S022717-v50.zst"),
+            Some(Compression::Zstd)
+        );
+        assert_eq!(Compression::from_path("S022717-v50.txt"), None);
+    }
+}
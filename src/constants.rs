@@ -1 +1,3 @@
-pub const EVERY_TICKER: &str = "*";
+use crate::message::Ticker;
+
+pub const EVERY_TICKER: Ticker = Ticker::new(*b"*       ");
@@ -0,0 +1,230 @@
+//! Errors raised while reading messages.
+//!
+//! Errors raised by a `Reader` are wrapped in `Error::At`, which records where in the input the
+//! offending message starts, so that a bad record can be found (and reported) without aborting
+//! on the first inconsistency.
+
+use std::{fmt, io};
+
+use crate::message::Version;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Debug)]
+pub enum Error {
+    /// Reading the input failed, or it ended between messages (`io::ErrorKind::UnexpectedEof`).
+    Io(io::Error),
+    /// An order message refers to an order that was never added or is already gone.
+    UnknownRefno(u64),
+    /// An order message executes or cancels more shares than its order has left.
+    OrderUnderflow {
+        refno: u64,
+        shares: u32,
+        remaining: u32,
+    },
+    /// A Version 3.0 to 4.1 timestamp came before any seconds ('T') message.
+    MissingClock,
+    /// A side other than 'B' (buy) or 'S' (sell).
+    InvalidSide(u8),
+    /// A system event code that is not defined.
+    InvalidEventCode(u8),
+    /// Any other field holding a value it cannot take.
+    InvalidField { field: &'static str, value: String },
+    /// A message type that is not defined for the version.
+    UnsupportedMessage { kind: char, version: Version },
+    /// A text version (2.0 or 3.0), whose messages are not length-prefixed binary frames, where
+    /// only frames can be handled (e.g. when indexing or sharding a file).
+    Unframed(Version),
+    /// An order book update removes `shares` from a price level at which only `resting` shares
+    /// are left (possibly none).
+    LevelUnderflow {
+        price: u32,
+        shares: u32,
+        resting: u32,
+    },
+    /// The input ended within a message of `size` bytes.
+    TruncatedMessage { size: usize },
+    /// A message of type `kind` is `found` bytes long instead of the `expected` length of its type
+    /// (if fixed), or too short for its fields.
+    SizeMismatch {
+        kind: char,
+        expected: Option<usize>,
+        found: usize,
+    },
+    /// Writing a row into a `Sink` failed.
+    Sink(Box<dyn std::error::Error + Send + Sync>),
+    /// An error raised by the message starting at byte `offset` of the input, which is preceded
+    /// by `sequence` messages (including 'T' messages).
+    At {
+        offset: u64,
+        sequence: u64,
+        source: Box<Error>,
+    },
+}
+
+impl Error {
+    /// Returns the byte offset of the message that raised the error, if known.
+    pub fn offset(&self) -> Option<u64> {
+        match self {
+            Error::At { offset, .. } => Some(*offset),
+            _ => None,
+        }
+    }
+
+    /// Returns the number of messages preceding the one that raised the error, if known.
+    pub fn sequence(&self) -> Option<u64> {
+        match self {
+            Error::At { sequence, .. } => Some(*sequence),
+            _ => None,
+        }
+    }
+
+    /// Returns the error without its position.
+    pub fn cause(&self) -> &Error {
+        match self {
+            Error::At { source, .. } => source.cause(),
+            error => error,
+        }
+    }
+
+    /// Returns the kind of the underlying I/O error, or `InvalidData` for malformed messages, so
+    /// that the end of the input can be told apart as with `io::Error`.
+    pub fn kind(&self) -> io::ErrorKind {
+        match self.cause() {
+            Error::Io(e) => e.kind(),
+            Error::Unframed(_) => io::ErrorKind::Unsupported,
+            Error::Sink(e) => e
+                .downcast_ref::<io::Error>()
+                .map_or(io::ErrorKind::Other, |e| e.kind()),
+            _ => io::ErrorKind::InvalidData,
+        }
+    }
+
+    /// Records where the message that raised the error starts, unless already known.
+    pub fn at(self, offset: u64, sequence: u64) -> Self {
+        match self {
+            Error::At { .. } => self,
+            error => Error::At {
+                offset,
+                sequence,
+                source: Box::new(error),
+            },
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(e) => write!(f, "{}", e),
+            Error::UnknownRefno(refno) => write!(f, "Order not found: {}", refno),
+            Error::OrderUnderflow {
+                refno,
+                shares,
+                remaining,
+            } => write!(
+                f,
+                "Cannot remove {} shares from order {} ({} remaining)",
+                shares, refno, remaining
+            ),
+            Error::MissingClock => write!(f, "Timestamp precedes every seconds message"),
+            Error::InvalidSide(code) => write!(f, "Invalid side: {}", char::from(*code)),
+            Error::InvalidEventCode(code) => {
+                write!(f, "Invalid event code: {}", char::from(*code))
+            }
+            Error::InvalidField { field, value } => write!(f, "Invalid {}: {}", field, value),
+            Error::UnsupportedMessage { kind, version } => {
+                write!(f, "Message type {} is not defined for {}", kind, version)
+            }
+            Error::Unframed(version) => {
+                write!(f, "{} messages are not length-prefixed frames", version)
+            }
+            Error::LevelUnderflow {
+                price,
+                shares,
+                resting,
+            } => write!(
+                f,
+                "Cannot remove {} shares from price level {} ({} resting)",
+                shares, price, resting
+            ),
+            Error::TruncatedMessage { size } => {
+                write!(f, "Truncated message: expected {} bytes", size)
+            }
+            Error::SizeMismatch {
+                kind,
+                expected: Some(expected),
+                found,
+            } => write!(
+                f,
+                "Message of type {} is {} bytes long instead of {}",
+                kind, found, expected
+            ),
+            Error::SizeMismatch {
+                kind,
+                expected: None,
+                found,
+            } => write!(f, "Message of type {} is too short: {} bytes", kind, found),
+            Error::Sink(e) => write!(f, "Failed to write rows: {}", e),
+            Error::At {
+                offset,
+                sequence,
+                source,
+            } => write!(f, "{} (message {} at byte {})", source, sequence, offset),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(e) => Some(e),
+            Error::Sink(e) => Some(e.as_ref()),
+            Error::At { source, .. } => Some(source.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+impl From<Error> for io::Error {
+    fn from(e: Error) -> Self {
+        match e {
+            Error::Io(e) => e,
+            e => io::Error::new(e.kind(), e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records_position_once() {
+        let err = Error::UnknownRefno(42).at(128, 3).at(0, 0);
+        assert_eq!(err.offset(), Some(128));
+        assert_eq!(err.sequence(), Some(3));
+        assert!(matches!(err.cause(), Error::UnknownRefno(42)));
+        assert_eq!(
+            err.to_string(),
+            "Order not found: 42 (message 3 at byte 128)"
+        );
+    }
+
+    #[test]
+    fn keeps_io_error_kinds() {
+        let eof = Error::from(io::Error::from(io::ErrorKind::UnexpectedEof)).at(7, 1);
+        assert_eq!(eof.kind(), io::ErrorKind::UnexpectedEof);
+        assert_eq!(io::Error::from(eof).kind(), io::ErrorKind::UnexpectedEof);
+
+        let err = io::Error::from(Error::MissingClock);
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(Error::InvalidSide(b'Z').kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,363 @@
+//! Records where messages start in a binary file, so that a `Reader` can jump to a time of day
+//! without parsing everything before it.
+//!
+//! An index is built by a single pass over the length prefixes of a file, which is much cheaper
+//! than parsing it, and is saved next to the file as a sidecar (e.g. `S031413-v41.txt.idx`).
+//! Every `interval`-th message gets an entry holding its byte offset, its timestamp and, for
+//! Versions 4.0 and 4.1, the seconds clock in effect before it, which the messages that follow
+//! need to compute their timestamps.
+//!
+//! Sidecars also record the length and modification time of the file they were built from, so
+//! that an index left over from an earlier version of the file can be told apart.
+
+use std::{
+    fs::{self, File},
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::{Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+
+use byteorder::{NetworkEndian, ReadBytesExt, WriteBytesExt};
+use getset::Getters;
+
+use crate::{
+    error::{Error, Result},
+    message::{frame_field, frame_u32, read_frame, Version},
+};
+
+mod tickers;
+
+pub use tickers::{Selection, TickerIndex};
+
+/// Number of messages between index entries unless specified otherwise.
+pub const DEFAULT_INTERVAL: u64 = 10_000;
+
+// Leading bytes of a sidecar file, followed by the format version
+const MAGIC: &[u8; 6] = b"TVIIDX";
+const FORMAT: u8 = 2;
+
+// Stands in for a missing clock in sidecar files (clocks never exceed 86,400 seconds)
+const NO_CLOCK: u32 = u32::MAX;
+
+#[derive(Debug, Clone, Copy, PartialEq, Getters)]
+#[getset(get = "pub")]
+pub struct IndexEntry {
+    message: u64,       // Number of messages (including 'T' messages) preceding the entry
+    offset: u64,        // Byte offset of the length prefix of the message
+    nanoseconds: u64,   // Timestamp of the message
+    clock: Option<u32>, // Seconds clock in effect before the message (Versions 4.0 and 4.1)
+}
+
+/// The length and modification time of an indexed file, which tell whether its sidecar indexes
+/// are still up to date.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Getters)]
+#[getset(get = "pub")]
+pub struct Source {
+    len: u64,
+    modified: u64, // Nanoseconds since the Unix epoch, or 0 if unknown
+}
+
+impl Source {
+    /// Reads the length and modification time of the file at `path`.
+    pub fn of<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let metadata = fs::metadata(path)?;
+        let modified = metadata
+            .modified()
+            .ok()
+            .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+            .map_or(0, |since| since.as_nanos() as u64);
+        Ok(Self {
+            len: metadata.len(),
+            modified,
+        })
+    }
+
+    fn read<R: Read>(reader: &mut R) -> io::Result<Self> {
+        Ok(Self {
+            len: reader.read_u64::<NetworkEndian>()?,
+            modified: reader.read_u64::<NetworkEndian>()?,
+        })
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_u64::<NetworkEndian>(self.len)?;
+        writer.write_u64::<NetworkEndian>(self.modified)
+    }
+}
+
+#[derive(Debug, PartialEq, Getters)]
+#[getset(get = "pub")]
+pub struct Index {
+    version: Version,
+    interval: u64,
+    source: Source, // File the index was built from, if known
+    entries: Vec<IndexEntry>,
+}
+
+impl Index {
+    /// Scans the length-prefixed messages of `reader` and records every `interval`-th of them.
+    ///
+    /// Only binary versions can be indexed.
+    pub fn build<R: Read>(reader: &mut R, version: Version, interval: u64) -> Result<Self> {
+        if version.is_text() {
+            return Err(Error::Unframed(version));
+        }
+        if interval == 0 {
+            return Err(Error::InvalidField {
+                field: "index interval",
+                value: interval.to_string(),
+            });
+        }
+
+        let mut entries = Vec::new();
+        let mut clock = None;
+        let mut offset = 0;
+        let mut frame = Vec::new();
+        for message in 0.. {
+            let at = |e: Error| e.at(offset, message);
+            if !read_frame(reader, &mut frame).map_err(at)? {
+                break;
+            }
+
+            let is_seconds = version != Version::V50 && frame.first() == Some(&b'T');
+            if message % interval == 0 {
+                entries.push(IndexEntry {
+                    message,
+                    offset,
+                    nanoseconds: timestamp(&frame, version, clock).map_err(at)?,
+                    clock,
+                });
+            }
+            if is_seconds {
+                clock = Some(frame_u32(&frame, 1).map_err(at)?);
+            }
+            offset += 2 + frame.len() as u64;
+        }
+
+        Ok(Self {
+            version,
+            interval,
+            source: Source::default(),
+            entries,
+        })
+    }
+
+    /// Records the file the index was built from, to be saved along with it.
+    pub fn with_source(mut self, source: Source) -> Self {
+        self.source = source;
+        self
+    }
+
+    /// Returns the path of the sidecar index of `path`.
+    pub fn sidecar<P: AsRef<Path>>(path: P) -> PathBuf {
+        let mut sidecar = path.as_ref().as_os_str().to_owned();
+        sidecar.push(".idx");
+        PathBuf::from(sidecar)
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Self::read(&mut BufReader::new(File::open(path)?))
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write(&mut writer)?;
+        writer.flush()
+    }
+
+    pub fn read<R: Read>(reader: &mut R) -> io::Result<Self> {
+        let mut magic = [0; 6];
+        reader.read_exact(&mut magic)?;
+        let format = reader.read_u8()?;
+        if &magic != MAGIC || format != FORMAT {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Not a message index",
+            ));
+        }
+
+        let version = reader.read_u8()?.to_string().parse()?;
+        let interval = reader.read_u64::<NetworkEndian>()?;
+        let source = Source::read(reader)?;
+        let count = reader.read_u64::<NetworkEndian>()?;
+        let mut entries = Vec::new();
+        for _ in 0..count {
+            let message = reader.read_u64::<NetworkEndian>()?;
+            let offset = reader.read_u64::<NetworkEndian>()?;
+            let nanoseconds = reader.read_u64::<NetworkEndian>()?;
+            let clock = match reader.read_u32::<NetworkEndian>()? {
+                NO_CLOCK => None,
+                seconds => Some(seconds),
+            };
+            entries.push(IndexEntry {
+                message,
+                offset,
+                nanoseconds,
+                clock,
+            });
+        }
+
+        Ok(Self {
+            version,
+            interval,
+            source,
+            entries,
+        })
+    }
+
+    pub fn write<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(MAGIC)?;
+        writer.write_u8(FORMAT)?;
+        writer.write_u8(version_number(&self.version))?;
+        writer.write_u64::<NetworkEndian>(self.interval)?;
+        self.source.write(writer)?;
+        writer.write_u64::<NetworkEndian>(self.entries.len() as u64)?;
+        for entry in &self.entries {
+            writer.write_u64::<NetworkEndian>(entry.message)?;
+            writer.write_u64::<NetworkEndian>(entry.offset)?;
+            writer.write_u64::<NetworkEndian>(entry.nanoseconds)?;
+            writer.write_u32::<NetworkEndian>(entry.clock.unwrap_or(NO_CLOCK))?;
+        }
+        Ok(())
+    }
+
+    /// Returns the entry to start reading from to see every message at or after `nanoseconds`,
+    /// i.e. the last entry before that time (or the first entry).
+    pub fn start(&self, nanoseconds: u64) -> Option<&IndexEntry> {
+        let after = self
+            .entries
+            .partition_point(|entry| entry.nanoseconds < nanoseconds);
+        self.entries.get(after.saturating_sub(1))
+    }
+
+    /// Returns the offset up to which to read to see every message at or before `nanoseconds`,
+    /// i.e. that of the first entry after that time, if any.
+    pub fn end(&self, nanoseconds: u64) -> Option<u64> {
+        let after = self
+            .entries
+            .partition_point(|entry| entry.nanoseconds <= nanoseconds);
+        self.entries.get(after).map(|entry| entry.offset)
+    }
+}
+
+// Timestamp of a message, given without its length prefix
+fn timestamp(frame: &[u8], version: Version, clock: Option<u32>) -> Result<u64> {
+    match version {
+        Version::V50 => {
+            let bytes = frame_field(frame, 5, 6)?;
+            Ok(bytes.iter().fold(0, |acc, &b| (acc << 8) | b as u64))
+        }
+        // 'T' messages carry the seconds themselves
+        _ if frame.first() == Some(&b'T') => Ok(frame_u32(frame, 1)? as u64 * 1_000_000_000),
+        _ => {
+            let seconds = clock.unwrap_or_default() as u64;
+            Ok(seconds * 1_000_000_000 + frame_u32(frame, 1)? as u64)
+        }
+    }
+}
+
+fn version_number(version: &Version) -> u8 {
+    match version {
+        Version::V20 => 20,
+        Version::V30 => 30,
+        Version::V40 => 40,
+        Version::V41 => 41,
+        Version::V50 => 50,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::message::{test_helpers::message_builders::*, Side};
+
+    fn messages_v41() -> Cursor<Vec<u8>> {
+        create_message_sequence(vec![
+            timestamp_v41(36000),
+            add_order_v41(100, 1, Side::Buy, 100, "AAPL", 1),
+            add_order_v41(200, 2, Side::Buy, 100, "AAPL", 1),
+            timestamp_v41(36001),
+            delete_order_v41(300, 1),
+            delete_order_v41(400, 2),
+        ])
+    }
+
+    #[test]
+    fn records_every_nth_message() {
+        let index = Index::build(&mut messages_v41(), Version::V41, 2).unwrap();
+        let entries = index.entries();
+        assert_eq!(entries.len(), 3);
+
+        // Messages are 5, 30 and 13 bytes long, plus their length prefixes
+        assert_eq!(*entries[1].message(), 2);
+        assert_eq!(*entries[1].offset(), 7 + 32);
+        assert_eq!(*entries[1].nanoseconds(), 36_000_000_000_200);
+        assert_eq!(*entries[1].clock(), Some(36000));
+        assert_eq!(*entries[2].offset(), 7 + 32 + 32 + 7);
+        assert_eq!(*entries[2].clock(), Some(36001));
+        assert_eq!(*entries[0].clock(), None);
+    }
+
+    #[test]
+    fn finds_entries_around_times() {
+        let index = Index::build(&mut messages_v41(), Version::V41, 2).unwrap();
+        let start = index.start(36_000_000_000_300).unwrap();
+        assert_eq!(*start.message(), 2);
+        assert_eq!(*index.start(0).unwrap().message(), 0);
+        assert_eq!(index.end(36_000_000_000_200), Some(7 + 32 + 32 + 7));
+        assert_eq!(index.end(36_001_000_000_300), None);
+    }
+
+    #[test]
+    fn indexes_version_50_timestamps() {
+        let mut data = create_message_sequence(vec![
+            add_order_v50(1_000, 1, Side::Buy, 100, "AAPL", 1),
+            delete_order_v50(2_000, 1),
+        ]);
+        let index = Index::build(&mut data, Version::V50, 1).unwrap();
+        assert_eq!(*index.entries()[1].nanoseconds(), 2_000);
+        assert_eq!(*index.entries()[1].clock(), None);
+    }
+
+    #[test]
+    fn roundtrips_through_sidecar() {
+        let source = Source {
+            len: 108,
+            modified: 1_700_000_000_000_000_000,
+        };
+        let index = Index::build(&mut messages_v41(), Version::V41, 2)
+            .unwrap()
+            .with_source(source);
+        let mut data = vec![];
+        index.write(&mut data).unwrap();
+        assert_eq!(Index::read(&mut Cursor::new(data)).unwrap(), index);
+
+        assert_eq!(
+            Index::sidecar("data/S031413-v41.txt"),
+            PathBuf::from("data/S031413-v41.txt.idx")
+        );
+        assert!(Index::read(&mut Cursor::new(b"not an index".to_vec())).is_err());
+    }
+
+    #[test]
+    fn describes_the_indexed_file() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(&messages_v41().into_inner()).unwrap();
+        let source = Source::of(file.path()).unwrap();
+        assert_eq!(*source.len(), 108);
+
+        file.write_all(&[0, 0]).unwrap();
+        assert_ne!(Source::of(file.path()).unwrap(), source);
+    }
+
+    #[test]
+    fn rejects_text_versions_and_truncated_files() {
+        assert!(Index::build(&mut Cursor::new(vec![]), Version::V30, 10).is_err());
+        let mut data = Cursor::new(vec![0, 5, b'T', 0]);
+        let err = Index::build(&mut data, Version::V41, 10).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(err.offset(), Some(0));
+    }
+}
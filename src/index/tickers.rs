@@ -0,0 +1,441 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs::File,
+    io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write},
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
+use byteorder::{NetworkEndian, ReadBytesExt, WriteBytesExt};
+
+use super::{version_number, Source};
+use crate::{
+    constants::EVERY_TICKER,
+    error::{Error, Result},
+    message::{frame_field, frame_u32, read_frame, Ticker, Version},
+    reader::ticker_offset,
+    shard::Orders,
+};
+
+// Leading bytes of a ticker sidecar file, followed by the format version
+const MAGIC: &[u8; 6] = b"TVITIX";
+const FORMAT: u8 = 2;
+
+/// Records the offsets of the messages of every ticker in a binary file, so that the messages of
+/// a few tickers can be read without walking the whole file.
+///
+/// Version 5.0 messages are attributed to a ticker by their stock locate code. For earlier
+/// versions, order executions, cancellations, deletions and replacements are attributed through
+/// the reference number of the order they update. Messages about no stock in particular (e.g.
+/// system events) are kept for every ticker, while Version 4.x broken trades, which only identify
+/// a trade by its match number, are not indexed.
+#[derive(Debug, PartialEq)]
+pub struct TickerIndex {
+    version: Version,
+    source: Source,                     // File the index was built from, if known
+    common: Vec<u64>,                   // Offsets of messages about no stock in particular
+    tickers: HashMap<Ticker, Vec<u64>>, // Offsets of the messages of each ticker
+    clocks: Vec<(u64, u32)>,            // Offset and seconds of 'T' messages (Versions 4.0 and 4.1)
+}
+
+impl TickerIndex {
+    /// Scans the length-prefixed messages of `reader` and records the offset of each.
+    ///
+    /// Only binary versions can be indexed.
+    pub fn build<R: Read>(reader: &mut R, version: Version) -> Result<Self> {
+        if version.is_text() {
+            return Err(Error::Unframed(version));
+        }
+
+        let mut index = Self {
+            version,
+            source: Source::default(),
+            common: Vec::new(),
+            tickers: HashMap::new(),
+            clocks: Vec::new(),
+        };
+        let mut locates: HashMap<u16, Ticker> = HashMap::new();
+        let mut orders: Orders<Ticker> = Orders::new();
+        let mut offset = 0;
+        let mut frame = Vec::new();
+        for message in 0.. {
+            let at = |e: Error| e.at(offset, message);
+            if !read_frame(reader, &mut frame).map_err(at)? {
+                break;
+            }
+            index
+                .record(&frame, offset, &mut locates, &mut orders)
+                .map_err(at)?;
+            offset += 2 + frame.len() as u64;
+        }
+
+        Ok(index)
+    }
+
+    // Records the offset of a message, given without its length prefix, under its ticker
+    fn record(
+        &mut self,
+        frame: &[u8],
+        offset: u64,
+        locates: &mut HashMap<u16, Ticker>,
+        orders: &mut Orders<Ticker>,
+    ) -> Result<()> {
+        let version = self.version;
+        let kind = frame_field(frame, 0, 1)?[0];
+        let ticker = if version == Version::V50 {
+            let locate = frame_field(frame, 1, 2)?;
+            let locate = u16::from_be_bytes([locate[0], locate[1]]);
+            if kind == b'R' {
+                let ticker = read_ticker(frame, &version, version.header_size())?;
+                locates.insert(locate, ticker);
+            }
+            locates.get(&locate).copied()
+        } else if kind == b'T' {
+            self.clocks.push((offset, frame_u32(frame, 1)?));
+            None
+        } else if let Some(at) = ticker_offset(&version, kind) {
+            let ticker = read_ticker(frame, &version, at)?;
+            if matches!(kind, b'A' | b'F') {
+                orders.add(frame, &version, ticker)?;
+            }
+            Some(ticker)
+        } else {
+            orders.update(frame, &version)?
+        };
+
+        match ticker {
+            Some(ticker) => self.tickers.entry(ticker).or_default().push(offset),
+            None if matches!(kind, b'S' | b'V' | b'W') => self.common.push(offset),
+            None => {}
+        }
+        Ok(())
+    }
+
+    /// Returns the path of the ticker sidecar index of `path`.
+    pub fn sidecar<P: AsRef<Path>>(path: P) -> PathBuf {
+        let mut sidecar = path.as_ref().as_os_str().to_owned();
+        sidecar.push(".tickers.idx");
+        PathBuf::from(sidecar)
+    }
+
+    pub fn version(&self) -> &Version {
+        &self.version
+    }
+
+    pub fn source(&self) -> &Source {
+        &self.source
+    }
+
+    /// Records the file the index was built from, to be saved along with it.
+    pub fn with_source(mut self, source: Source) -> Self {
+        self.source = source;
+        self
+    }
+
+    /// Returns the tickers found in the file.
+    pub fn tickers(&self) -> impl Iterator<Item = &Ticker> {
+        self.tickers.keys()
+    }
+
+    /// Returns the offsets of the messages of `tickers` and of those about no stock in
+    /// particular, in file order.
+    pub fn offsets(&self, tickers: &HashSet<Ticker>) -> Vec<u64> {
+        let mut offsets = self.common.clone();
+        for (ticker, ticker_offsets) in &self.tickers {
+            if tickers.contains(ticker) || tickers.contains(&EVERY_TICKER) {
+                offsets.extend_from_slice(ticker_offsets);
+            }
+        }
+        offsets.sort_unstable();
+        offsets
+    }
+
+    /// Returns a source over `buffer` that only yields the messages of `tickers`.
+    pub fn select<B: Read + Seek>(&self, buffer: B, tickers: &HashSet<Ticker>) -> Selection<B> {
+        Selection {
+            buffer,
+            offsets: self.offsets(tickers),
+            next: 0,
+            clocks: self.clocks.clone(),
+            clock: None,
+            position: 0,
+            pending: Vec::new(),
+            added: 0,
+            start: 0,
+        }
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Self::read(&mut BufReader::new(File::open(path)?))
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write(&mut writer)?;
+        writer.flush()
+    }
+
+    pub fn read<R: Read>(reader: &mut R) -> io::Result<Self> {
+        let mut magic = [0; 6];
+        reader.read_exact(&mut magic)?;
+        let format = reader.read_u8()?;
+        if &magic != MAGIC || format != FORMAT {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Not a ticker index",
+            ));
+        }
+
+        let version = reader.read_u8()?.to_string().parse()?;
+        let source = Source::read(reader)?;
+        let mut clocks = Vec::new();
+        for _ in 0..reader.read_u64::<NetworkEndian>()? {
+            let offset = reader.read_u64::<NetworkEndian>()?;
+            clocks.push((offset, reader.read_u32::<NetworkEndian>()?));
+        }
+        let common = read_offsets(reader)?;
+        let mut tickers = HashMap::new();
+        for _ in 0..reader.read_u64::<NetworkEndian>()? {
+            let mut ticker = [0; 8];
+            reader.read_exact(&mut ticker)?;
+            tickers.insert(Ticker::from_bytes(&ticker)?, read_offsets(reader)?);
+        }
+
+        Ok(Self {
+            version,
+            source,
+            common,
+            tickers,
+            clocks,
+        })
+    }
+
+    pub fn write<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(MAGIC)?;
+        writer.write_u8(FORMAT)?;
+        writer.write_u8(version_number(&self.version))?;
+        self.source.write(writer)?;
+        writer.write_u64::<NetworkEndian>(self.clocks.len() as u64)?;
+        for &(offset, seconds) in &self.clocks {
+            writer.write_u64::<NetworkEndian>(offset)?;
+            writer.write_u32::<NetworkEndian>(seconds)?;
+        }
+        write_offsets(writer, &self.common)?;
+        writer.write_u64::<NetworkEndian>(self.tickers.len() as u64)?;
+        for (ticker, offsets) in &self.tickers {
+            writer.write_all(ticker.as_bytes())?;
+            write_offsets(writer, offsets)?;
+        }
+        Ok(())
+    }
+}
+
+/// A source that yields the messages found at a list of offsets, seeking from one to the next.
+///
+/// Versions 4.0 and 4.1 messages are preceded by a 'T' message whenever the seconds clock in
+/// effect differs from that of the previous message, so a `Reader` computes the same timestamps
+/// as when reading the whole file. Since the offsets are visited in order, compressed streams
+/// (which only seek forward) can be read as well.
+pub struct Selection<B> {
+    buffer: B,
+    offsets: Vec<u64>,
+    next: usize,             // Index of the next offset to read
+    clocks: Vec<(u64, u32)>, // Offset and seconds of 'T' messages
+    clock: Option<u32>,      // Seconds clock of the last message yielded
+    position: u64,           // Offset of the last message yielded
+    pending: Vec<u8>,        // Bytes of the last message yielded (and its 'T' message)
+    added: usize,            // Length of the 'T' message added in front of it (0 if none)
+    start: usize,            // Index of the next unread byte in `pending`
+}
+
+impl<B: Read + Seek> Selection<B> {
+    /// Restricts the selection to the messages within a range of offsets.
+    pub fn within(mut self, range: Range<u64>) -> Self {
+        self.offsets.retain(|offset| range.contains(offset));
+        self
+    }
+
+    /// Returns the offset of the last message read.
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+
+    /// Returns the number of messages selected.
+    pub fn len(&self) -> usize {
+        self.offsets.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.offsets.is_empty()
+    }
+
+    // Whether the bytes read last are those of a 'T' message added in front of a selected message
+    pub(crate) fn in_added_clock(&self) -> bool {
+        self.added > 0 && self.start == self.added
+    }
+
+    // Loads the next message into `pending`. Returns `false` once every message has been read.
+    fn fill(&mut self) -> io::Result<bool> {
+        let Some(&offset) = self.offsets.get(self.next) else {
+            return Ok(false);
+        };
+        self.next += 1;
+        self.pending.clear();
+        self.start = 0;
+
+        let after = self.clocks.partition_point(|&(at, _)| at < offset);
+        let clock = after.checked_sub(1).map(|i| self.clocks[i].1);
+        if clock != self.clock {
+            if let Some(seconds) = clock {
+                self.pending.extend_from_slice(&[0, 5, b'T']);
+                self.pending.extend_from_slice(&seconds.to_be_bytes());
+            }
+            self.clock = clock;
+        }
+        self.added = self.pending.len();
+
+        self.position = offset;
+        self.buffer.seek(SeekFrom::Start(offset))?;
+        let size = self.buffer.read_u16::<NetworkEndian>()?;
+        let from = self.pending.len() + 2;
+        self.pending.extend_from_slice(&size.to_be_bytes());
+        self.pending.resize(from + size as usize, 0);
+        self.buffer.read_exact(&mut self.pending[from..])?;
+        Ok(true)
+    }
+}
+
+impl<B: Read + Seek> Read for Selection<B> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.start == self.pending.len() && !self.fill()? {
+            return Ok(0);
+        }
+        let n = (self.pending.len() - self.start).min(buf.len());
+        buf[..n].copy_from_slice(&self.pending[self.start..self.start + n]);
+        self.start += n;
+        Ok(n)
+    }
+}
+
+fn read_ticker(frame: &[u8], version: &Version, at: usize) -> Result<Ticker> {
+    Ok(Ticker::from_bytes(frame_field(
+        frame,
+        at,
+        version.ticker_size(),
+    )?)?)
+}
+
+fn read_offsets<R: Read>(reader: &mut R) -> io::Result<Vec<u64>> {
+    let count = reader.read_u64::<NetworkEndian>()?;
+    (0..count)
+        .map(|_| reader.read_u64::<NetworkEndian>())
+        .collect()
+}
+
+fn write_offsets<W: Write>(writer: &mut W, offsets: &[u64]) -> io::Result<()> {
+    writer.write_u64::<NetworkEndian>(offsets.len() as u64)?;
+    for &offset in offsets {
+        writer.write_u64::<NetworkEndian>(offset)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::{
+        error,
+        message::{test_helpers::message_builders::*, Message, Side},
+        Reader,
+    };
+
+    fn messages_v41() -> Cursor<Vec<u8>> {
+        create_message_sequence(vec![
+            timestamp_v41(34200),
+            system_event_v41(0, 'O'),
+            add_order_v41(1, 1, Side::Buy, 100, "A", 1000),
+            add_order_v41(2, 2, Side::Sell, 200, "B", 2000),
+            timestamp_v41(34201),
+            execute_order_v41(3, 1, 50),
+            replace_order_v41(4, 2, 3, 100, 2100),
+            delete_order_v41(5, 3),
+            delete_order_v41(6, 1),
+        ])
+    }
+
+    #[test]
+    fn attributes_order_updates_through_reference_numbers() {
+        let index = TickerIndex::build(&mut messages_v41(), Version::V41).unwrap();
+        // Messages are 5, 6, 30, 30, 5, 25, 29, 13 and 13 bytes long
+        assert_eq!(index.common, vec![7]);
+        assert_eq!(index.tickers[&ticker("A")], vec![15, 86, 159]);
+        assert_eq!(index.tickers[&ticker("B")], vec![47, 113, 144]);
+        assert_eq!(index.clocks, vec![(0, 34200), (79, 34201)]);
+    }
+
+    #[test]
+    fn attributes_version_50_messages_through_stock_locates() {
+        let mut data = create_message_sequence(vec![
+            with_locate(
+                stock_directory_v50(0, "AAPL", 'Q', 'N', 100, 'C', "Z", 'N', 'N'),
+                7,
+                0,
+            ),
+            with_locate(add_order_v50(1, 1, Side::Buy, 100, "AAPL", 1000), 7, 0),
+            with_locate(delete_order_v50(2, 1), 7, 0),
+            with_locate(delete_order_v50(3, 2), 8, 0),
+        ]);
+        let index = TickerIndex::build(&mut data, Version::V50).unwrap();
+        assert_eq!(index.tickers[&ticker("AAPL")].len(), 3);
+        assert!(index.common.is_empty());
+    }
+
+    #[test]
+    fn selects_the_messages_of_some_tickers() {
+        let index = TickerIndex::build(&mut messages_v41(), Version::V41).unwrap();
+        let tickers = HashSet::from([ticker("B")]);
+        let mut selection = index.select(messages_v41(), &tickers);
+        assert_eq!(selection.len(), 4);
+
+        let reader = Reader::new(Version::V41, tickers);
+        let messages = reader
+            .streamed_messages(&mut selection)
+            .collect::<error::Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(messages.len(), 5); // Replace orders are read as a delete and an add
+        assert!(matches!(&messages[0], Message::SystemEvent(_)));
+        assert!(matches!(&messages[1], Message::AddOrder(m)
+            if m.nanoseconds() == &34_200_000_000_002));
+        assert!(matches!(&messages[4], Message::DeleteOrder(m)
+            if m.refno() == &3 && m.nanoseconds() == &34_201_000_000_005));
+        assert_eq!(selection.position(), 144);
+    }
+
+    #[test]
+    fn restricts_selection_to_range() {
+        let index = TickerIndex::build(&mut messages_v41(), Version::V41).unwrap();
+        let tickers = HashSet::from([ticker("A")]);
+        let selection = index.select(messages_v41(), &tickers).within(50..200);
+        assert_eq!(selection.offsets, vec![86, 159]);
+    }
+
+    #[test]
+    fn roundtrips_through_sidecar() {
+        let source = Source {
+            len: 512,
+            modified: 1_700_000_000_000_000_000,
+        };
+        let index = TickerIndex::build(&mut messages_v41(), Version::V41)
+            .unwrap()
+            .with_source(source);
+        let mut data = vec![];
+        index.write(&mut data).unwrap();
+        assert_eq!(TickerIndex::read(&mut Cursor::new(data)).unwrap(), index);
+        assert_eq!(
+            TickerIndex::sidecar("S031413-v41.txt"),
+            PathBuf::from("S031413-v41.txt.tickers.idx")
+        );
+    }
+}
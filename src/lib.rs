@@ -1,12 +1,22 @@
 pub mod buffer;
 pub mod constants;
+pub mod error;
+pub mod index;
 pub mod message;
 pub mod orderbook;
+pub mod pipeline;
 pub mod reader;
+pub mod shard;
+pub mod sniff;
 pub mod writer;
 
-pub use buffer::{BufFile, Buffer};
-pub use message::{Message, Version};
+pub use buffer::{BufFile, Buffer, MmapFile, Stream};
+pub use error::Error;
+pub use index::Index;
+pub use message::{Message, Ticker, Version};
 pub use orderbook::{OrderBook, OrderBookSnapshot};
-pub use reader::Reader;
+pub use pipeline::{Pipeline, Processor, Sink};
+pub use reader::{Messages, Policy, Reader};
+pub use shard::{Route, Router, Shards};
+pub use sniff::sniff_version;
 pub use writer::{Writer, CSV};
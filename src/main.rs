@@ -1,21 +1,29 @@
 use std::{
-    collections::{HashMap, HashSet},
+    collections::HashSet,
+    error::Error,
     fs,
-    io::{ErrorKind, Seek},
+    io::{self, BufRead, BufReader, ErrorKind, Read, Seek, SeekFrom, StdinLock},
     path::Path,
+    process::ExitCode,
     time::{Duration, Instant},
 };
 
-use clap::Parser;
+use clap::{
+    error::ErrorKind::{ArgumentConflict, MissingRequiredArgument},
+    CommandFactory, Parser,
+};
 use indicatif::{ProgressBar, ProgressStyle};
 use tvi::{
-    message::{IntoNOIIMessage, IntoOrderMessage, IntoTradeMessage},
-    Buffer, Message, OrderBook, Reader, Version, Writer, CSV,
+    buffer::{Compression, Peek},
+    error,
+    index::{IndexEntry, Selection, Source, TickerIndex, DEFAULT_INTERVAL},
+    pipeline::{self, OrderBooks, Output},
+    shard::{Frames, Sequential},
+    sniff::{date_from_filename, detect_version, is_date, version_from_filename},
+    sniff_version, Index, Message, MmapFile, Pipeline, Policy, Processor, Reader, Shards, Sink,
+    Stream, Ticker, Version, Writer, CSV,
 };
 
-// TODO: Print error to std:err
-// TODO: handle panics
-
 struct PerformanceMetrics {
     file_size: u64,
     duration: DurationMetrics,
@@ -33,6 +41,16 @@ impl PerformanceMetrics {
         }
     }
 
+    // Adds the messages counted by a pipeline and the time it spent on order books
+    fn add(&mut self, pipeline: &Pipeline<Chain>) {
+        let chain = pipeline.processor();
+        self.duration.orderbook += chain.order_books.duration;
+        self.messages.total += chain.messages.total;
+        self.messages.orders += chain.messages.orders;
+        self.messages.trades += chain.messages.trades;
+        self.messages.noii += chain.messages.noii;
+    }
+
     fn summarize(&self) {
         println!("📊 Performance Report");
         println!("━━━━━━━━━━━━━━━━━━━━━━━━");
@@ -42,6 +60,9 @@ impl PerformanceMetrics {
             "Throughput:     {:.1} MB/s",
             (self.file_size as f64) / 1_000_000.0 / self.duration.total.as_secs_f64()
         );
+        if self.messages.skipped > 0 {
+            println!("Skipped:        {} messages", self.messages.skipped);
+        }
 
         println!("\n🔍 Time Breakdown:");
         let total_ms = self.duration.total.as_millis() as f64;
@@ -87,6 +108,7 @@ struct MessageMetrics {
     // system: u64,
     trades: u64,
     noii: u64,
+    skipped: u64, // Messages that could not be parsed (under a lenient policy)
 }
 
 impl MessageMetrics {
@@ -97,6 +119,23 @@ impl MessageMetrics {
             // system: 0,
             trades: 0,
             noii: 0,
+            skipped: 0,
+        }
+    }
+
+    // Counts a message, and by type
+    fn count(&mut self, msg: &Message) {
+        self.total += 1;
+        match msg {
+            Message::AddOrder(_)
+            | Message::CancelOrder(_)
+            | Message::DeleteOrder(_)
+            | Message::ExecuteOrder(_) => self.orders += 1,
+            Message::Trade(_) | Message::CrossTrade(_) | Message::BrokenTrade(_) => {
+                self.trades += 1
+            }
+            Message::NetOrderImbalanceIndicator(_) => self.noii += 1,
+            _ => {}
         }
     }
 }
@@ -112,16 +151,224 @@ impl MessageMetrics {
 //     }
 // }
 
+// The processors run on every message: order books (timed for the performance report), then the
+// rows to write, which are dropped for messages outside the time window. Messages are counted
+// along the way.
+struct Chain {
+    messages: MessageMetrics,
+    order_books: Timed<OrderBooks>,
+    rows: pipeline::Rows,
+    window: Window,
+}
+
+impl Processor for Chain {
+    fn process(&mut self, message: &Message, reader: &Reader, outputs: &mut Vec<Output>) {
+        self.messages.count(message);
+        let from = outputs.len();
+        self.order_books.process(message, reader, outputs);
+        self.rows.process(message, reader, outputs);
+        if !self.window.contains(message.nanoseconds()) {
+            outputs.truncate(from);
+        }
+    }
+}
+
+fn create_pipeline(
+    tickers: &HashSet<Ticker>,
+    date: &str,
+    depth: usize,
+    window: Window,
+) -> Pipeline<Chain> {
+    Pipeline::new(Chain {
+        messages: MessageMetrics::new(),
+        order_books: Timed::new(OrderBooks::new(tickers, date, depth)),
+        rows: pipeline::Rows::new(date),
+        window,
+    })
+}
+
+// Prints the updates that could not be applied to the order books
+fn report_book_failures(pipeline: &Pipeline<Chain>) {
+    for e in pipeline.processor().order_books.inner.diagnostics() {
+        eprintln!("Warning: {}", e);
+    }
+}
+
+// Prints the messages skipped under `--on-error warn`
+fn report_skipped(diagnostics: &[tvi::Error], policy: Policy) {
+    if policy == Policy::Warn {
+        for e in diagnostics {
+            eprintln!("Warning: skipped a message: {}", e);
+        }
+    }
+}
+
+// Measures the time spent in a processor or sink
+struct Timed<T> {
+    inner: T,
+    duration: Duration,
+}
+
+impl<T> Timed<T> {
+    fn new(inner: T) -> Self {
+        Self {
+            inner,
+            duration: Duration::new(0, 0),
+        }
+    }
+}
+
+impl<P: Processor> Processor for Timed<P> {
+    fn process(&mut self, message: &Message, reader: &Reader, outputs: &mut Vec<Output>) {
+        let start = Instant::now();
+        self.inner.process(message, reader, outputs);
+        self.duration += start.elapsed();
+    }
+}
+
+impl<S: Sink> Sink for Timed<S> {
+    fn write(&mut self, output: Output) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let start = Instant::now();
+        let result = self.inner.write(output);
+        self.duration += start.elapsed();
+        result
+    }
+}
+
+// Number of messages routed to shards between updates of the progress bar
+const PROGRESS_INTERVAL: u64 = 1 << 14;
+
+// The messages routed to shards, which show progress and end with the time window
+struct Tracked<'a, F> {
+    frames: &'a mut F,
+    pb: &'a ProgressBar,
+    window: &'a Window,
+    read: u64,
+}
+
+impl<'a, F> Tracked<'a, F> {
+    fn new(frames: &'a mut F, pb: &'a ProgressBar, window: &'a Window) -> Self {
+        Self {
+            frames,
+            pb,
+            window,
+            read: 0,
+        }
+    }
+}
+
+impl<F: Frames> Frames for Tracked<'_, F> {
+    fn read_frame(&mut self, frame: &mut Vec<u8>) -> tvi::error::Result<bool> {
+        if !self.frames.read_frame(frame)? {
+            return Ok(false);
+        }
+        let offset = self.frames.offset();
+        if self.window.is_done(offset) {
+            return Ok(false);
+        }
+        self.read += 1;
+        if let Some(offset) = offset.filter(|_| self.read % PROGRESS_INTERVAL == 0) {
+            self.pb.set_position(offset);
+        }
+        Ok(true)
+    }
+
+    fn offset(&self) -> Option<u64> {
+        self.frames.offset()
+    }
+}
+
+// The messages to write when reading a time window: rows are written for messages within
+// `start..=end` only, but every message from the index entry the input was moved to (if any) is
+// processed, so that order books are as complete as possible.
+#[derive(Clone, Copy)]
+struct Window {
+    start: u64,
+    end: u64,
+    resume: Option<IndexEntry>, // Entry the input was moved to
+    end_offset: Option<u64>,    // Offset of the first indexed message after `end`
+}
+
+impl Window {
+    fn all() -> Self {
+        Self {
+            start: 0,
+            end: u64::MAX,
+            resume: None,
+            end_offset: None,
+        }
+    }
+
+    fn contains(&self, nanoseconds: u64) -> bool {
+        (self.start..=self.end).contains(&nanoseconds)
+    }
+
+    // Whether every message of the window has been read
+    fn is_done(&self, position: Option<u64>) -> bool {
+        matches!((position, self.end_offset), (Some(position), Some(end)) if position >= end)
+    }
+}
+
+// Files can be peeked into, while standard input and selections of a file's messages are read
+// one message at a time
+enum Input {
+    File(Box<dyn Peek>),
+    Stdin(BufReader<StdinLock<'static>>),
+    Selection(Selection<Box<dyn Peek>>),
+}
+
+impl Input {
+    fn extract_message(&mut self, reader: &mut Reader) -> error::Result<Message> {
+        match self {
+            Input::File(buffer) => reader.extract_message(buffer),
+            Input::Stdin(buffer) => reader.extract_streamed_message(buffer),
+            Input::Selection(buffer) => reader.extract_streamed_message(buffer),
+        }
+    }
+
+    fn position(&mut self) -> Option<u64> {
+        match self {
+            Input::File(buffer) => buffer.stream_position().ok(),
+            Input::Stdin(_) => None,
+            Input::Selection(selection) => Some(selection.position()),
+        }
+    }
+}
+
+impl Seek for Input {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        match self {
+            Input::File(buffer) => buffer.seek(pos),
+            Input::Stdin(_) | Input::Selection(_) => Err(io::Error::new(
+                ErrorKind::Unsupported,
+                "Only whole files can seek",
+            )),
+        }
+    }
+}
+
+impl Read for Input {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Input::File(buffer) => buffer.read(buf),
+            Input::Stdin(buffer) => buffer.read(buf),
+            Input::Selection(selection) => selection.read(buf),
+        }
+    }
+}
+
 #[derive(Parser)]
 struct Cli {
+    #[arg(help = "The ITCH file to parse, or '-' to read from standard input.")]
     path: std::path::PathBuf,
 
     #[arg(
         short,
         long,
-        default_value_t = String::from("*"),
+        default_value = "*",
+        value_parser = parse_tickers,
         help = "A comma-delimited list of tickers to read read, or '*' to read all messages.")]
-    tickers: String, // a comma- or space-delimited list
+    tickers: HashSet<Ticker>,
 
     #[arg(
         short,
@@ -138,251 +385,440 @@ struct Cli {
         help = "The size of internal buffer used for writing data."
     )]
     capacity: usize,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Write the stock locate and tracking number of v5.0 messages."
+    )]
+    locate: bool,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Filter v5.0 messages by stock locate code once the stock directory has been read."
+    )]
+    locate_filter: bool,
+
+    #[arg(
+        long,
+        value_parser = parse_date,
+        help = "The trading date (YYYY-MM-DD). Inferred from the filename by default."
+    )]
+    date: Option<String>,
+
+    #[arg(
+        long,
+        help = "The ITCH version (e.g., '50' or '4.1'). Detected from the file contents by default."
+    )]
+    version: Option<Version>,
+
+    #[arg(
+        long,
+        default_value_t = 1,
+        help = "The number of threads to parse binary files with. Messages are split across threads by stock."
+    )]
+    threads: usize,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Build (or rebuild) the sidecar indexes of the file, which record where messages start."
+    )]
+    index: bool,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Jump between the messages of the requested tickers with a sidecar index of the offsets of each ticker's messages."
+    )]
+    ticker_index: bool,
+
+    #[arg(
+        long,
+        default_value_t = DEFAULT_INTERVAL,
+        help = "The number of messages between entries of a new index."
+    )]
+    index_interval: u64,
+
+    #[arg(
+        long,
+        value_parser = parse_time,
+        help = "Only write messages from this time of day (HH:MM[:SS]) on, using the sidecar index to skip ahead."
+    )]
+    start: Option<u64>,
+
+    #[arg(
+        long,
+        value_parser = parse_time,
+        help = "Only write messages up to this time of day (HH:MM[:SS]), using the sidecar index to stop early."
+    )]
+    end: Option<u64>,
+
+    #[arg(
+        long,
+        default_value_t = Policy::Strict,
+        help = "What to do with messages that cannot be parsed: 'strict' stops, 'warn' skips them with a warning, and 'skip' skips them silently."
+    )]
+    on_error: Policy,
 }
 
-fn parse_filename<P: AsRef<Path>>(path: P) -> Option<(String, Version)> {
-    let filename = path.as_ref().file_stem()?.to_str()?;
-
-    // Expected format: SMMDDYY-vXX (e.g., S022717-v50)
-    if filename.len() == 11 && filename.chars().next()? == 'S' {
-        let parts: Vec<&str> = filename.split('-').collect();
-        if parts.len() == 2 {
-            let date_part = &parts[0][1..]; // Skip 'S', take MMDDYY
-            let version_part = parts[1];
-
-            if date_part.len() == 6 && date_part.chars().all(|c| c.is_ascii_digit()) {
-                let mm = &date_part[0..2];
-                let dd = &date_part[2..4];
-                let yy = &date_part[4..6];
-                let yyyy = format!("20{}", yy);
-                let date = format!("{}-{}-{}", yyyy, mm, dd);
-
-                if version_part.starts_with('v') && version_part.len() == 3 {
-                    let version = match &version_part[1..] {
-                        "41" => Version::V41,
-                        "50" => Version::V50,
-                        _ => return None,
-                    };
-
-                    return Some((date, version));
-                }
+fn parse_tickers(s: &str) -> Result<HashSet<Ticker>, String> {
+    s.split(',')
+        .map(|ticker| {
+            ticker.parse().map_err(|_| {
+                format!(
+                    "'{}' is not a ticker of at most eight ASCII characters",
+                    ticker
+                )
+            })
+        })
+        .collect()
+}
+
+fn parse_date(s: &str) -> Result<String, String> {
+    let parts: Vec<&str> = s.split('-').collect();
+    let valid = parts.len() == 3
+        && parts
+            .iter()
+            .zip([4, 2, 2])
+            .all(|(part, len)| part.len() == len && part.chars().all(|c| c.is_ascii_digit()))
+        && is_date(
+            parts[0].parse().unwrap(),
+            parts[1].parse().unwrap(),
+            parts[2].parse().unwrap(),
+        );
+    if valid {
+        Ok(s.to_string())
+    } else {
+        Err(format!("'{}' is not a date of the form YYYY-MM-DD", s))
+    }
+}
+
+// Parses a time of day into nanoseconds past midnight
+fn parse_time(s: &str) -> Result<u64, String> {
+    let parts: Vec<&str> = s.split(':').collect();
+    let fields: Option<Vec<u64>> = parts
+        .iter()
+        .map(|part| {
+            let valid = part.len() == 2 && part.chars().all(|c| c.is_ascii_digit());
+            valid.then(|| part.parse().unwrap())
+        })
+        .collect();
+    let error = || format!("'{}' is not a time of the form HH:MM[:SS]", s);
+    let (hours, minutes, seconds) = match fields.as_deref() {
+        Some(&[hours, minutes]) => (hours, minutes, 0),
+        Some(&[hours, minutes, seconds]) => (hours, minutes, seconds),
+        _ => return Err(error()),
+    };
+    if hours < 24 && minutes < 60 && seconds < 60 {
+        Ok((hours * 3600 + minutes * 60 + seconds) * 1_000_000_000)
+    } else {
+        Err(error())
+    }
+}
+
+// Explains why the input cannot be indexed, if it cannot
+fn unindexable(from_stdin: bool, version: Version) -> Option<String> {
+    if from_stdin {
+        Some("cannot be used with standard input".to_string())
+    } else if version.is_text() {
+        Some(format!(
+            "cannot be used with {} files, which are text",
+            version
+        ))
+    } else {
+        None
+    }
+}
+
+// Loads the sidecar index of a file, building and saving it if it is missing, stale or asked for
+fn open_index(path: &Path, version: Version, interval: u64, rebuild: bool) -> error::Result<Index> {
+    let sidecar = Index::sidecar(path);
+    let source = Source::of(path)?;
+    if !rebuild {
+        if let Ok(index) = Index::load(&sidecar) {
+            let fresh = *index.version() == version
+                && *index.interval() == interval
+                && *index.source() == source;
+            if fresh {
+                return Ok(index);
+            }
+        }
+    }
+
+    let index = Index::build(&mut Stream::open(path)?, version, interval)?.with_source(source);
+    index.save(&sidecar)?;
+    Ok(index)
+}
+
+// Loads the ticker sidecar index of a file, building and saving it if it is missing, stale or
+// asked for
+fn open_ticker_index(path: &Path, version: Version, rebuild: bool) -> error::Result<TickerIndex> {
+    let sidecar = TickerIndex::sidecar(path);
+    let source = Source::of(path)?;
+    if !rebuild {
+        if let Ok(index) = TickerIndex::load(&sidecar) {
+            if *index.version() == version && *index.source() == source {
+                return Ok(index);
             }
         }
     }
 
-    None
+    let index = TickerIndex::build(&mut Stream::open(path)?, version)?.with_source(source);
+    index.save(&sidecar)?;
+    Ok(index)
 }
 
-fn main() {
+fn main() -> ExitCode {
     //  Start timer
     let start = Instant::now();
 
     // Parse args and environment variables
     let args = Cli::parse();
-    let tickers: HashSet<String> = args.tickers.split(',').map(|s| s.to_string()).collect();
-    let (date, version) = parse_filename(&args.path).expect(
-        "The filename should match the format 'SMMDDYY-vNN' where 'NN' is one of '41' or '50'.",
-    );
-
-    // Set up reader and writer
-    let mut buffer = Buffer::new(&args.path).unwrap();
-    let mut reader = Reader::new(version, tickers.clone());
-    let backend = CSV::new("data").unwrap();
-    let mut writer = Writer::new(backend, args.capacity);
-
-    // Set up progress bar
-    let filesize = fs::metadata(&args.path).unwrap().len();
-    let pb = ProgressBar::new(filesize);
-    pb.set_style(
-        ProgressStyle::default_bar()
-            .template(
-                "{spinner:.green} [{elapsed_precise}] [{wide_bar:.cyan/blue}] {bytes}/{total_bytes} ({bytes_per_sec}, {msg}, {eta})",
-            ).unwrap()
-            .progress_chars("#>-"),
-    );
+    match parse_file(&args, start) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("An error occurred: {}.", e);
+            ExitCode::FAILURE
+        }
+    }
+}
 
-    // Set up metrics
-    let mut metrics = PerformanceMetrics::new(filesize);
+// Parses the file (or standard input) given on the command line and writes its rows
+fn parse_file(args: &Cli, start: Instant) -> Result<(), Box<dyn Error>> {
+    let tickers = &args.tickers;
+    let from_stdin = args.path.as_os_str() == "-";
+
+    // A missing file is reported as such rather than as one whose version cannot be detected
+    let open_error = |e| format!("Unable to open {}: {}", args.path.display(), e);
+    let filesize = if from_stdin {
+        0
+    } else {
+        fs::metadata(&args.path).map_err(open_error)?.len()
+    };
+
+    // Standard input is sniffed through its buffer, which leaves the sniffed bytes unread
+    let mut stdin = from_stdin.then(|| BufReader::with_capacity(1 << 16, io::stdin().lock()));
+    let version = args
+        .version
+        .or_else(|| match stdin.as_mut() {
+            Some(stdin) => detect_version(stdin.fill_buf().ok()?),
+            None => {
+                let mut stream = Stream::open(&args.path).ok()?;
+                sniff_version(&mut stream).ok()?
+            }
+        })
+        .or_else(|| version_from_filename(&args.path))
+        .unwrap_or_else(|| {
+            let message =
+                "unable to detect the ITCH version of the file; specify it with '--version'";
+            Cli::command()
+                .error(MissingRequiredArgument, message)
+                .exit()
+        });
+    let date = args
+        .date
+        .clone()
+        .or_else(|| date_from_filename(&args.path))
+        .unwrap_or_else(|| {
+            let message =
+                "unable to infer the trading date from the filename; specify it with '--date'";
+            Cli::command()
+                .error(MissingRequiredArgument, message)
+                .exit()
+        });
+
+    // Only binary files can be indexed
+    if args.index || args.start.is_some() || args.end.is_some() {
+        if let Some(reason) = unindexable(from_stdin, version) {
+            let message = format!("'--index', '--start' and '--end' {}", reason);
+            Cli::command().error(ArgumentConflict, message).exit();
+        }
+    }
+    if args.ticker_index {
+        if let Some(reason) = unindexable(from_stdin, version) {
+            let message = format!("'--ticker-index' {}", reason);
+            Cli::command().error(ArgumentConflict, message).exit();
+        }
+    }
 
-    // Create order books for each ticker
-    let mut order_books: HashMap<String, OrderBook> = HashMap::new();
-    for ticker in &tickers {
-        if ticker != "*" {
-            // Skip wildcard
-            order_books.insert(
-                ticker.clone(),
-                OrderBook::new(date.clone(), ticker.clone(), args.depth),
-            );
+    // Set up reader and writer (compressed files are decompressed on the fly)
+    let compression = Compression::from_path(&args.path);
+    let mut input = match (stdin, &compression) {
+        (Some(stdin), _) => Input::Stdin(stdin),
+        (None, Some(_)) => Input::File(Box::new(Stream::open(&args.path).map_err(open_error)?)),
+        (None, None) => Input::File(Box::new(MmapFile::new(&args.path).map_err(open_error)?)),
+    };
+    let mut reader = Reader::new(version, tickers.clone())
+        .with_locate_filter(args.locate_filter)
+        .with_policy(args.on_error);
+
+    // Skip ahead to the requested time window with the sidecar index
+    let mut window = Window::all();
+    if args.index || args.start.is_some() || args.end.is_some() {
+        let index = open_index(&args.path, version, args.index_interval, args.index)
+            .map_err(|e| format!("Unable to index the file: {}", e))?;
+        window.start = args.start.unwrap_or(window.start);
+        window.end = args.end.unwrap_or(window.end);
+        window.end_offset = args.end.and_then(|end| index.end(end));
+        if let Some(entry) = args.start.and_then(|start| index.start(start)) {
+            reader.seek(&mut input, entry)?;
+            window.resume = Some(*entry);
         }
     }
 
-    // Begin main loop...
-    loop {
-        let current_pos = buffer.stream_position().unwrap();
-        pb.set_position(current_pos);
+    // Only read the messages of the requested tickers (within the time window)
+    if args.ticker_index {
+        let Input::File(buffer) = input else {
+            unreachable!("Standard input cannot be indexed.");
+        };
+        let index = open_ticker_index(&args.path, version, args.index)
+            .map_err(|e| format!("Unable to index the tickers of the file: {}", e))?;
+        let from = window.resume.map_or(0, |entry| *entry.offset());
+        let to = window.end_offset.unwrap_or(u64::MAX);
+        input = Input::Selection(index.select(buffer, tickers).within(from..to));
+    }
 
-        let parse_start = Instant::now();
-        match reader.extract_message(&mut buffer) {
-            Ok(msg) => {
-                metrics.messages.total += 1;
-                metrics.duration.parsing += parse_start.elapsed();
-                pb.set_message(format!("{} messages", &metrics.messages.total));
-
-                match msg {
-                    Message::AddOrder(data) => {
-                        metrics.messages.orders += 1;
-                        // Update order book
-                        if let Some(order_book) = order_books.get_mut(data.ticker()) {
-                            let order_book_start = Instant::now();
-                            order_book.add_order(
-                                *data.side(),
-                                *data.price(),
-                                *data.shares(),
-                                *data.nanoseconds(),
-                            );
-                            metrics.duration.orderbook += order_book_start.elapsed();
-
-                            // Create and write snapshot
-                            let write_start = Instant::now();
-                            let snapshot = order_book.snapshot();
-                            writer.write_snapshot(snapshot).unwrap();
-                            metrics.duration.orderbook += write_start.elapsed();
-                        }
-
-                        let write_start = Instant::now();
-                        let order_message = data.into_order_message(date.clone());
-                        writer.write_order_message(order_message).unwrap();
-                        metrics.duration.serialization += write_start.elapsed();
-                    }
-                    Message::CancelOrder(data) => {
-                        metrics.messages.orders += 1;
-                        // Update order book
-                        if let Some(order_book) = order_books.get_mut(data.ticker()) {
-                            let order_book_start = Instant::now();
-                            if let Err(e) = order_book.remove_order(
-                                *data.side(),
-                                *data.price(),
-                                *data.shares(),
-                                *data.nanoseconds(),
-                            ) {
-                                metrics.duration.orderbook += order_book_start.elapsed();
-                                eprintln!("Warning: Failed to cancel order: {}", e);
-                            } else {
-                                metrics.duration.orderbook += order_book_start.elapsed();
-                                // Create and write snapshot only if update succeeded
-                                let write_start = Instant::now();
-                                let snapshot = order_book.snapshot();
-                                writer.write_snapshot(snapshot).unwrap();
-                                metrics.duration.serialization += write_start.elapsed();
-                            }
-                        }
-
-                        let write_start = Instant::now();
-                        let order_message = data.into_order_message(date.clone());
-                        writer.write_order_message(order_message).unwrap();
-                        metrics.duration.serialization += write_start.elapsed();
-                    }
-                    Message::DeleteOrder(data) => {
-                        metrics.messages.orders += 1;
-                        // Update order book
-                        if let Some(order_book) = order_books.get_mut(data.ticker()) {
-                            let order_book_start = Instant::now();
-                            if let Err(e) = order_book.remove_order(
-                                *data.side(),
-                                *data.price(),
-                                *data.shares(),
-                                *data.nanoseconds(),
-                            ) {
-                                metrics.duration.orderbook += order_book_start.elapsed();
-                                eprintln!("Warning: Failed to delete order: {}", e);
-                            } else {
-                                metrics.duration.orderbook += order_book_start.elapsed();
-                                // Create and write snapshot only if update succeeded
-                                let write_start = Instant::now();
-                                let snapshot = order_book.snapshot();
-                                writer.write_snapshot(snapshot).unwrap();
-                                metrics.duration.serialization += write_start.elapsed();
-                            }
-                        }
-
-                        let write_start = Instant::now();
-                        let order_message = data.into_order_message(date.clone());
-                        writer.write_order_message(order_message).unwrap();
-                        metrics.duration.serialization += write_start.elapsed();
-                    }
-                    Message::ExecuteOrder(data) => {
-                        metrics.messages.orders += 1;
-                        // Update order book
-                        if let Some(order_book) = order_books.get_mut(data.ticker()) {
-                            let order_book_start = Instant::now();
-                            if let Err(e) = order_book.execute_order(
-                                *data.side(),
-                                *data.price(),
-                                *data.shares(),
-                                *data.nanoseconds(),
-                            ) {
-                                metrics.duration.orderbook += order_book_start.elapsed();
-                                eprintln!("Warning: Failed to execute order: {}", e);
-                            } else {
-                                metrics.duration.orderbook += order_book_start.elapsed();
-                                // Create and write snapshot only if update succeeded
-                                let write_start = Instant::now();
-                                let snapshot = order_book.snapshot();
-                                writer.write_snapshot(snapshot).unwrap();
-                                metrics.duration.serialization += write_start.elapsed();
-                            }
-                        }
-
-                        let write_start = Instant::now();
-                        let order_message = data.into_order_message(date.clone());
-                        writer.write_order_message(order_message).unwrap();
-                        metrics.duration.serialization += write_start.elapsed();
-                    }
-                    Message::Trade(data) => {
-                        metrics.messages.trades += 1;
-                        let write_start = Instant::now();
-                        let trade_message = data.into_trade_message(date.clone());
-                        writer.write_trade_message(trade_message).unwrap();
-                        metrics.duration.serialization += write_start.elapsed();
-                    }
-                    Message::CrossTrade(data) => {
-                        metrics.messages.trades += 1;
-                        let write_start = Instant::now();
-                        let trade_message = data.into_trade_message(date.clone());
-                        writer.write_trade_message(trade_message).unwrap();
-                        metrics.duration.serialization += write_start.elapsed();
-                    }
-                    Message::BrokenTrade(data) => {
-                        metrics.messages.trades += 1;
-                        let write_start = Instant::now();
-                        let trade_message = data.into_trade_message(date.clone());
-                        writer.write_trade_message(trade_message).unwrap();
-                        metrics.duration.serialization += write_start.elapsed();
-                    }
-                    Message::NetOrderImbalanceIndicator(data) => {
-                        metrics.messages.noii += 1;
-                        let write_start = Instant::now();
-                        let noii_message = data.into_noii_message(date.clone());
-                        writer.write_noii_message(noii_message).unwrap();
-                        metrics.duration.serialization += write_start.elapsed();
-                    }
-                    _ => {}
-                }
+    let backend =
+        CSV::new("data").map_err(|e| format!("Unable to create the output directory: {}", e))?;
+    let mut writer = Timed::new(Writer::new(backend, args.capacity).with_locate(args.locate));
+
+    // Set up progress bar (the decompressed size of a compressed file is unknown upfront)
+    let pb = match compression {
+        _ if from_stdin => {
+            let pb = ProgressBar::new_spinner();
+            pb.set_style(
+                ProgressStyle::default_spinner()
+                    .template("{spinner:.green} [{elapsed_precise}] {msg}")
+                    .unwrap(),
+            );
+            pb
+        }
+        Some(_) => {
+            let pb = ProgressBar::no_length();
+            pb.set_style(
+                ProgressStyle::default_spinner()
+                    .template(
+                        "{spinner:.green} [{elapsed_precise}] {bytes} decompressed ({bytes_per_sec}, {msg})",
+                    )
+                    .unwrap(),
+            );
+            pb
+        }
+        None => {
+            let pb = ProgressBar::new(filesize);
+            pb.set_style(
+                ProgressStyle::default_bar()
+                    .template(
+                        "{spinner:.green} [{elapsed_precise}] [{wide_bar:.cyan/blue}] {bytes}/{total_bytes} ({bytes_per_sec}, {msg}, {eta})",
+                    ).unwrap()
+                    .progress_chars("#>-"),
+            );
+            pb
+        }
+    };
+
+    // Set up metrics
+    let mut metrics = PerformanceMetrics::new(filesize);
+
+    // Begin main loop (binary files can be split across threads by stock)...
+    let result = if args.threads > 1 && !version.is_text() {
+        let mut shards =
+            Shards::new(version, args.threads, tickers.clone()).with_policy(args.on_error);
+        if let Some(entry) = window.resume {
+            shards = shards.with_resume(entry);
+        }
+        // Each shard has its own reader and order books
+        let worker = || {
+            let reader = Reader::new(version, tickers.clone())
+                .with_locate_filter(args.locate_filter)
+                .with_policy(args.on_error);
+            (reader, create_pipeline(tickers, &date, args.depth, window))
+        };
+        let from = window.resume.map_or(0, |entry| *entry.offset());
+        let result = match &mut input {
+            Input::Selection(selection) => shards.run(
+                &mut Tracked::new(selection, &pb, &window),
+                &mut writer,
+                worker,
+            ),
+            input => {
+                let frames = &mut Sequential::new(input, from);
+                shards.run(&mut Tracked::new(frames, &pb, &window), &mut writer, worker)
             }
-            Err(e) => {
-                if e.kind() == ErrorKind::UnexpectedEof {
-                    break; // End of file reached
-                } else {
-                    eprintln!("An error occurred: {}.", e);
-                    return; // Err(e); // Actual error
-                }
+        };
+        result.map(|shards| {
+            for shard in &shards {
+                metrics.add(shard.pipeline());
+                metrics.duration.parsing += *shard.parsing();
+                metrics.messages.skipped += shard.reader().diagnostics().len() as u64;
+                report_skipped(shard.reader().diagnostics(), args.on_error);
+                report_book_failures(shard.pipeline());
             }
-        }
+        })
+    } else {
+        // Create order books for each ticker
+        let mut pipeline = create_pipeline(tickers, &date, args.depth, window);
+        let result = run(
+            &mut input,
+            &mut reader,
+            &mut writer,
+            &mut pipeline,
+            &pb,
+            &mut metrics,
+        );
+        metrics.add(&pipeline);
+        metrics.messages.skipped += reader.diagnostics().len() as u64;
+        report_skipped(reader.diagnostics(), args.on_error);
+        report_book_failures(&pipeline);
+        result
+    };
+    metrics.duration.serialization += writer.duration;
+    result?;
+
+    if compression.is_some() {
+        // Report throughput over the decompressed data
+        metrics.file_size = input.position().unwrap();
     }
-
     metrics.duration.total += start.elapsed();
     pb.finish_with_message(format!("✅ Processed {} messages", &metrics.messages.total));
     metrics.summarize();
+    Ok(())
+}
+
+// Parses and writes every message of the input on the current thread
+fn run<S: Sink>(
+    input: &mut Input,
+    reader: &mut Reader,
+    sink: &mut S,
+    pipeline: &mut Pipeline<Chain>,
+    pb: &ProgressBar,
+    metrics: &mut PerformanceMetrics,
+) -> error::Result<()> {
+    let window = pipeline.processor().window;
+    loop {
+        let position = input.position();
+        if let Some(current_pos) = position {
+            pb.set_position(current_pos);
+        }
+        if window.is_done(position) {
+            return Ok(());
+        }
+
+        let parse_start = Instant::now();
+        let msg = match input.extract_message(reader) {
+            Ok(msg) => msg,
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(()), // End of file reached
+            Err(e) => return Err(e),
+        };
+        metrics.duration.parsing += parse_start.elapsed();
+
+        for output in pipeline.process(&msg, reader) {
+            sink.write(output).map_err(tvi::Error::Sink)?;
+        }
+        pb.set_message(format!("{} messages", pipeline.processor().messages.total));
+    }
 }
 
 #[cfg(test)]
@@ -390,31 +826,34 @@ mod tests {
     use super::*;
 
     #[test]
-    fn parses_properly_formatted_filename() {
-        let path = std::path::PathBuf::from("data/S022717-v50.txt");
-        let (date, version) = parse_filename(path).unwrap();
-        assert_eq!(date, "2017-02-27");
-        assert_eq!(version, Version::V50);
+    fn parses_tickers_argument() {
+        let tickers = parse_tickers("AAPL,MSFT").unwrap();
+        assert_eq!(
+            tickers,
+            HashSet::from(["AAPL", "MSFT"].map(|t| t.parse().unwrap()))
+        );
+        assert!(parse_tickers("AAPL,TOOLONGTICKER").is_err());
     }
 
     #[test]
-    fn return_none_if_version_is_invalid() {
-        let path = std::path::PathBuf::from("data/S022717-v60.txt");
-        let result = parse_filename(path);
-        assert!(result.is_none());
+    fn parses_date_argument() {
+        assert_eq!(parse_date("2017-02-27").unwrap(), "2017-02-27");
     }
 
     #[test]
-    fn return_none_if_date_is_malformed() {
-        let path = std::path::PathBuf::from("data/S02272017-v50.txt");
-        let result = parse_filename(path);
-        assert!(result.is_none());
+    fn rejects_malformed_date_argument() {
+        assert!(parse_date("02-27-2017").is_err());
+        assert!(parse_date("2017-2-27").is_err());
+        assert!(parse_date("20170227").is_err());
+        assert!(parse_date("2017-02-29").is_err());
     }
 
     #[test]
-    fn return_none_if_filename_is_malformed() {
-        let path = std::path::PathBuf::from("data/S02272017v50.txt");
-        let result = parse_filename(path);
-        assert!(result.is_none());
+    fn parses_time_argument() {
+        assert_eq!(parse_time("10:00").unwrap(), 36_000_000_000_000);
+        assert_eq!(parse_time("10:05:30").unwrap(), 36_330_000_000_000);
+        assert!(parse_time("10").is_err());
+        assert!(parse_time("9:30").is_err());
+        assert!(parse_time("24:00").is_err());
     }
 }
@@ -3,15 +3,29 @@ mod broken_trade;
 mod cancel_order;
 mod cross_trade;
 mod delete_order;
+mod dlcr;
 mod execute_order;
+mod ipo_quoting_period;
+mod luld_auction_collar;
+mod market_participant;
+mod mwcb;
 mod noii;
+mod operational_halt;
+mod reg_sho;
 mod replace_order;
+mod rpii;
+mod stock_directory;
 mod system_event;
+mod ticker;
 mod trade;
+mod trading_action;
+mod view;
 
 use std::{
     collections::HashMap,
-    io::{Error, ErrorKind, Read, Result, Seek},
+    io::{ErrorKind, Read, Seek},
+    str::FromStr,
+    sync::Arc,
 };
 
 pub use add_order::AddOrder;
@@ -20,20 +34,48 @@ use byteorder::{NetworkEndian, ReadBytesExt};
 pub use cancel_order::CancelOrder;
 pub use cross_trade::CrossTrade;
 pub use delete_order::DeleteOrder;
+pub use dlcr::DirectListingCapitalRaise;
 pub use execute_order::ExecuteOrder;
-use getset::Getters;
+use getset::{Getters, Setters};
+pub use ipo_quoting_period::IPOQuotingPeriod;
+pub use luld_auction_collar::LULDAuctionCollar;
+pub use market_participant::MarketParticipantPosition;
+pub use mwcb::{MWCBDeclineLevel, MWCBStatus};
 pub use noii::NetOrderImbalanceIndicator;
+pub use operational_halt::OperationalHalt;
+pub use reg_sho::RegSHORestriction;
 pub(crate) use replace_order::read_replace_order;
+pub use rpii::RetailPriceImprovement;
 use serde::Serialize;
+pub use stock_directory::StockDirectory;
 use strum_macros::Display;
 pub use system_event::SystemEvent;
+pub use ticker::Ticker;
 pub use trade::Trade;
+pub use trading_action::TradingAction;
+pub use view::{
+    AddOrderRef, BrokenTradeRef, CancelOrderRef, CrossTradeRef, DeleteOrderRef, ExecuteOrderRef,
+    Frame, MessageRef, MessageRefs, NetOrderImbalanceIndicatorRef, ReplaceOrderRef,
+    StockDirectoryRef, SystemEventRef, TradeRef, TradingActionRef,
+};
 
-use crate::buffer::Peek;
+use crate::{
+    buffer::Peek,
+    error::{Error, Result},
+};
 
 #[derive(Debug)]
 pub enum Message {
     SystemEvent(SystemEvent),
+    MWCBDeclineLevel(MWCBDeclineLevel),
+    MWCBStatus(MWCBStatus),
+    StockDirectory(StockDirectory),
+    TradingAction(TradingAction),
+    RegSHORestriction(RegSHORestriction),
+    MarketParticipantPosition(MarketParticipantPosition),
+    IPOQuotingPeriod(IPOQuotingPeriod),
+    LULDAuctionCollar(LULDAuctionCollar),
+    OperationalHalt(OperationalHalt),
     AddOrder(AddOrder),
     ExecuteOrder(ExecuteOrder),
     CancelOrder(CancelOrder),
@@ -42,16 +84,170 @@ pub enum Message {
     CrossTrade(CrossTrade),
     BrokenTrade(BrokenTrade),
     NetOrderImbalanceIndicator(NetOrderImbalanceIndicator),
+    RetailPriceImprovement(RetailPriceImprovement),
+    DirectListingCapitalRaise(DirectListingCapitalRaise),
+}
+
+impl Message {
+    /// Returns the timestamp of the message, in nanoseconds past midnight.
+    pub fn nanoseconds(&self) -> u64 {
+        match self {
+            Message::SystemEvent(m) => *m.nanoseconds(),
+            Message::MWCBDeclineLevel(m) => *m.nanoseconds(),
+            Message::MWCBStatus(m) => *m.nanoseconds(),
+            Message::StockDirectory(m) => *m.nanoseconds(),
+            Message::TradingAction(m) => *m.nanoseconds(),
+            Message::RegSHORestriction(m) => *m.nanoseconds(),
+            Message::MarketParticipantPosition(m) => *m.nanoseconds(),
+            Message::IPOQuotingPeriod(m) => *m.nanoseconds(),
+            Message::LULDAuctionCollar(m) => *m.nanoseconds(),
+            Message::OperationalHalt(m) => *m.nanoseconds(),
+            Message::AddOrder(m) => *m.nanoseconds(),
+            Message::ExecuteOrder(m) => *m.nanoseconds(),
+            Message::CancelOrder(m) => *m.nanoseconds(),
+            Message::DeleteOrder(m) => *m.nanoseconds(),
+            Message::Trade(m) => *m.nanoseconds(),
+            Message::CrossTrade(m) => *m.nanoseconds(),
+            Message::BrokenTrade(m) => *m.nanoseconds(),
+            Message::NetOrderImbalanceIndicator(m) => *m.nanoseconds(),
+            Message::RetailPriceImprovement(m) => *m.nanoseconds(),
+            Message::DirectListingCapitalRaise(m) => *m.nanoseconds(),
+        }
+    }
 }
 
-#[derive(Debug, PartialEq, Display)]
+#[derive(Debug, PartialEq, Clone, Copy, Display)]
 pub enum Version {
+    #[strum(serialize = "Version 2.0")]
+    V20,
+    #[strum(serialize = "Version 3.0")]
+    V30,
+    #[strum(serialize = "Version 4.0")]
+    V40,
     #[strum(serialize = "Version 4.1")]
     V41,
     #[strum(serialize = "Version 5.0")]
     V50,
 }
 
+// Parses "50", "5.0", "v50" or "V5.0" (and likewise for the other versions)
+impl FromStr for Version {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let digits: String = s
+            .trim_start_matches(['v', 'V'])
+            .chars()
+            .filter(|c| *c != '.')
+            .collect();
+        match digits.as_str() {
+            "20" => Ok(Version::V20),
+            "30" => Ok(Version::V30),
+            "40" => Ok(Version::V40),
+            "41" => Ok(Version::V41),
+            "50" => Ok(Version::V50),
+            _ => Err(Error::InvalidField {
+                field: "ITCH version",
+                value: s.to_string(),
+            }),
+        }
+    }
+}
+
+impl Version {
+    /// Whether messages are newline-terminated ASCII records (Versions 2.0 and 3.0) rather than
+    /// length-prefixed binary records.
+    pub fn is_text(&self) -> bool {
+        matches!(self, Version::V20 | Version::V30)
+    }
+
+    // Number of bytes preceding the message body: the message type, the stock locate and tracking
+    // number (Version 5.0) and the timestamp. Version 2.0 records are reordered by the reader so
+    // that the message type comes first.
+    pub(crate) fn header_size(&self) -> usize {
+        match self {
+            Version::V20 => 9,                // type + milliseconds (8)
+            Version::V30 => 4,                // type + milliseconds (3)
+            Version::V40 | Version::V41 => 5, // type + nanoseconds (4)
+            Version::V50 => 11,               // type + locate (2) + tracking (2) + nanoseconds (6)
+        }
+    }
+
+    pub(crate) fn ticker_size(&self) -> usize {
+        match self {
+            Version::V20 | Version::V30 | Version::V40 => 6,
+            Version::V41 | Version::V50 => 8,
+        }
+    }
+
+    pub(crate) fn refno_size(&self) -> usize {
+        if self.is_text() {
+            9
+        } else {
+            8
+        }
+    }
+
+    pub(crate) fn shares_size(&self) -> usize {
+        if self.is_text() {
+            6
+        } else {
+            4
+        }
+    }
+
+    // Cross trade and NOII share counts
+    pub(crate) fn long_shares_size(&self) -> usize {
+        if self.is_text() {
+            9
+        } else {
+            8
+        }
+    }
+
+    /// Returns the length of binary messages of type `kind` (without their length prefix), as
+    /// laid out by the specification of the version.
+    ///
+    /// Returns `None` for text versions, whose records are not framed, and for message types that
+    /// are not parsed for the version.
+    pub fn message_size(&self, kind: char) -> Option<usize> {
+        if self.is_text() {
+            return None;
+        }
+        let v50 = *self == Version::V50;
+        let ticker = self.ticker_size();
+        let body = match kind {
+            'T' if !v50 => 0, // Seconds replace the timestamp
+            'S' => 1,
+            'V' if v50 => 24,
+            'W' if v50 => 1,
+            'R' if v50 => ticker + 20,
+            'R' => ticker + 7,
+            'H' => ticker + 6,
+            'Y' => ticker + 1,
+            'L' => ticker + 7,
+            'K' if v50 => ticker + 9,
+            'J' if v50 => ticker + 16,
+            'h' if v50 => ticker + 2,
+            'A' => ticker + 17,
+            'F' => ticker + 21,
+            'E' => 20,
+            'C' => 25,
+            'X' => 12,
+            'D' => 8,
+            'U' => 24,
+            'P' => ticker + 25,
+            'Q' => ticker + 21,
+            'B' => 8,
+            'I' => ticker + 31,
+            'N' if v50 => ticker + 1,
+            'O' if v50 => ticker + 29,
+            _ => return None,
+        };
+        Some(self.header_size() + body)
+    }
+}
+
 #[derive(Debug, PartialEq)]
 pub enum EventCode {
     StartMessages,
@@ -65,6 +261,37 @@ pub enum EventCode {
     EmergencyMarketResumption,
 }
 
+#[derive(Debug, Default, PartialEq, Clone, Copy, Display, Serialize)]
+pub enum TradingState {
+    #[strum(serialize = "H")]
+    #[serde(rename = "H")]
+    Halted,
+    #[strum(serialize = "P")]
+    #[serde(rename = "P")]
+    Paused,
+    #[strum(serialize = "Q")]
+    #[serde(rename = "Q")]
+    QuotationOnly,
+    #[default]
+    #[strum(serialize = "T")]
+    #[serde(rename = "T")]
+    Trading,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum RegSHOAction {
+    NoPriceTest, // No price test in place
+    Intraday,    // Restriction in effect due to an intraday price drop
+    Continued,   // Restriction remains in effect from the previous day
+}
+
+impl RegSHOAction {
+    /// Whether the short sale price test (alternative uptick rule) is in effect
+    pub fn is_restricted(&self) -> bool {
+        !matches!(self, RegSHOAction::NoPriceTest)
+    }
+}
+
 #[derive(Debug, PartialEq, Clone, Copy, Serialize)]
 pub enum Side {
     #[serde(rename = "B")]
@@ -74,16 +301,22 @@ pub enum Side {
 }
 
 pub(crate) struct OrderState {
-    ticker: String,
+    ticker: Ticker,
     side: Side,
     price: u32,
     shares: u32,
 }
 
-pub(crate) struct Context {
+/// The state carried from message to message: the clock of Version 4.x files, the active orders
+/// and the stock-level registries.
+pub struct Context {
     pub(crate) clock: Option<u32>, /* Tracks number of seconds past midnight (applicable for
                                     * Version 4.1) */
     pub(crate) active_orders: HashMap<u64, OrderState>,
+    pub(crate) directory: HashMap<Ticker, StockDirectory>,
+    pub(crate) trading_states: HashMap<Ticker, TradingState>,
+    pub(crate) reg_sho_actions: HashMap<Ticker, RegSHOAction>,
+    pub(crate) participants: HashMap<(String, Ticker), MarketParticipantPosition>, // (mpid, ticker)
 }
 
 impl Context {
@@ -91,6 +324,10 @@ impl Context {
         Self {
             clock: None,
             active_orders: HashMap::new(),
+            directory: HashMap::new(),
+            trading_states: HashMap::new(),
+            reg_sho_actions: HashMap::new(),
+            participants: HashMap::new(),
         }
     }
 
@@ -103,6 +340,12 @@ impl Context {
     }
 }
 
+impl Default for Context {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 pub(crate) trait ReadMessage: Sized {
     fn read<T>(buffer: &mut T, version: &Version, context: &mut Context) -> Result<Self>
     where
@@ -111,29 +354,112 @@ pub(crate) trait ReadMessage: Sized {
 
 fn read_nanoseconds<T: Read>(buffer: &mut T, version: &Version, clock: Option<u32>) -> Result<u64> {
     match version {
-        Version::V41 => {
-            let seconds = clock.expect("Clock info missing");
+        Version::V20 => {
+            let milliseconds = read_numeric(buffer, 8)?;
+            Ok(milliseconds * 1_000_000)
+        }
+        Version::V30 => {
+            let seconds = clock.ok_or(Error::MissingClock)?;
+            let milliseconds = read_numeric(buffer, 3)?;
+            Ok((seconds as u64) * 1_000_000_000 + milliseconds * 1_000_000)
+        }
+        Version::V40 | Version::V41 => {
+            let seconds = clock.ok_or(Error::MissingClock)?;
             let nanoseconds = buffer.read_u32::<NetworkEndian>()?;
             Ok((seconds as u64) * 1_000_000_000 + (nanoseconds as u64))
         }
-        Version::V50 => buffer.read_u48::<NetworkEndian>(),
+        Version::V50 => Ok(buffer.read_u48::<NetworkEndian>()?),
     }
 }
 
-fn read_shares<T: Read>(buffer: &mut T) -> Result<u32> {
-    buffer.read_u32::<NetworkEndian>()
+// Numeric fields of text versions are right-justified ASCII digits
+fn read_numeric<T: Read>(buffer: &mut T, size: usize) -> Result<u64> {
+    let mut buf = vec![0; size];
+    buffer.read_exact(&mut buf)?;
+    parse_numeric(&buf)
+}
+
+fn parse_numeric(buf: &[u8]) -> Result<u64> {
+    let value = std::str::from_utf8(buf)
+        .ok()
+        .and_then(|s| s.trim().parse().ok());
+    value.ok_or_else(|| invalid_field("number", buf))
+}
+
+fn read_numeric_u32<T: Read>(buffer: &mut T, size: usize) -> Result<u32> {
+    let value = read_numeric(buffer, size)?;
+    u32::try_from(value).map_err(|_| invalid_field("number", value.to_string().as_bytes()))
 }
 
-fn read_price<T: Read>(buffer: &mut T) -> Result<u32> {
-    buffer.read_u32::<NetworkEndian>()
+fn invalid_field(field: &'static str, value: &[u8]) -> Error {
+    Error::InvalidField {
+        field,
+        value: String::from_utf8_lossy(value).into_owned(),
+    }
 }
 
-fn read_refno<T: Read>(buffer: &mut T) -> Result<u64> {
-    buffer.read_u64::<NetworkEndian>()
+// Reads the stock locate and tracking number that follow the message type in Version 5.0 (the
+// fields do not exist in earlier versions)
+fn read_locate<T: Read>(buffer: &mut T, version: &Version) -> Result<(Option<u16>, Option<u16>)> {
+    if version == &Version::V50 {
+        let stock_locate = buffer.read_u16::<NetworkEndian>()?;
+        let tracking_number = buffer.read_u16::<NetworkEndian>()?;
+        Ok((Some(stock_locate), Some(tracking_number)))
+    } else {
+        Ok((None, None))
+    }
 }
 
-fn read_matchno<T: Read>(buffer: &mut T) -> Result<u64> {
-    buffer.read_u64::<NetworkEndian>()
+// Message types added in Version 5.0 have no layout in earlier versions
+fn require_v50(version: &Version, kind: char) -> Result<()> {
+    if version != &Version::V50 {
+        return Err(Error::UnsupportedMessage {
+            kind,
+            version: *version,
+        });
+    }
+    Ok(())
+}
+
+fn read_shares<T: Read>(buffer: &mut T, version: &Version) -> Result<u32> {
+    if version.is_text() {
+        read_numeric_u32(buffer, version.shares_size())
+    } else {
+        Ok(buffer.read_u32::<NetworkEndian>()?)
+    }
+}
+
+fn read_long_shares<T: Read>(buffer: &mut T, version: &Version) -> Result<u64> {
+    if version.is_text() {
+        read_numeric(buffer, version.long_shares_size())
+    } else {
+        Ok(buffer.read_u64::<NetworkEndian>()?)
+    }
+}
+
+// Prices carry four implied decimal places in every version (text prices are 6.4 digits)
+fn read_price<T: Read>(buffer: &mut T, version: &Version) -> Result<u32> {
+    if version.is_text() {
+        read_numeric_u32(buffer, 10)
+    } else {
+        Ok(buffer.read_u32::<NetworkEndian>()?)
+    }
+}
+
+fn read_refno<T: Read>(buffer: &mut T, version: &Version) -> Result<u64> {
+    if version.is_text() {
+        read_numeric(buffer, version.refno_size())
+    } else {
+        Ok(buffer.read_u64::<NetworkEndian>()?)
+    }
+}
+
+fn read_matchno<T: Read>(buffer: &mut T, version: &Version) -> Result<u64> {
+    if version.is_text() {
+        read_numeric(buffer, 9)
+    } else {
+        Ok(buffer.read_u64::<NetworkEndian>()?)
+    }
 }
 
 fn read_printable<T: Read>(buffer: &mut T) -> Result<bool> {
@@ -142,68 +468,107 @@ fn read_printable<T: Read>(buffer: &mut T) -> Result<bool> {
 }
 
 fn read_side<T: Read>(buffer: &mut T) -> Result<Side> {
-    let side = match buffer.read_u8().map(char::from)? {
-        'B' => Side::Buy,
-        'S' => Side::Sell,
-        unknown_code => {
-            return Err(Error::new(
-                ErrorKind::InvalidData,
-                format!("Invalid code for trading: {}", unknown_code),
-            ));
-        }
+    let side = match buffer.read_u8()? {
+        b'B' => Side::Buy,
+        b'S' => Side::Sell,
+        unknown_code => return Err(Error::InvalidSide(unknown_code)),
     };
     Ok(side)
 }
 
 fn read_event_code<T: Read>(buffer: &mut T) -> Result<EventCode> {
-    let event_code = match buffer.read_u8().map(char::from)? {
-        'O' => EventCode::StartMessages,
-        'S' => EventCode::StartSystem,
-        'Q' => EventCode::StartMarketHours,
-        'M' => EventCode::EndMarketHours,
-        'E' => EventCode::EndSystem,
-        'C' => EventCode::EndMessages,
-        'A' => EventCode::EmergencyMarketHalt,
-        'R' => EventCode::EmergencyMarketQuoteOnly,
-        'B' => EventCode::EmergencyMarketResumption,
-        unknown_code => {
-            return Err(Error::new(
-                ErrorKind::InvalidData,
-                format!("Invalid event code encountered: {}", unknown_code),
-            ));
-        }
+    let event_code = match buffer.read_u8()? {
+        b'O' => EventCode::StartMessages,
+        b'S' => EventCode::StartSystem,
+        b'Q' => EventCode::StartMarketHours,
+        b'M' => EventCode::EndMarketHours,
+        b'E' => EventCode::EndSystem,
+        b'C' => EventCode::EndMessages,
+        b'A' => EventCode::EmergencyMarketHalt,
+        b'R' => EventCode::EmergencyMarketQuoteOnly,
+        b'B' => EventCode::EmergencyMarketResumption,
+        unknown_code => return Err(Error::InvalidEventCode(unknown_code)),
     };
     Ok(event_code)
 }
 
-fn read_ticker<T: Read>(buffer: &mut T) -> Result<String> {
-    let mut buf = vec![0; 8];
-    buffer.read_exact(&mut buf)?;
-    match String::from_utf8(buf) {
-        Ok(s) => Ok(s.trim().to_string()),
-        Err(e) => Err(Error::new(ErrorKind::InvalidData, e)),
-    }
+fn read_trading_state<T: Read>(buffer: &mut T) -> Result<TradingState> {
+    let trading_state = match buffer.read_u8().map(char::from)? {
+        'H' => TradingState::Halted,
+        'P' => TradingState::Paused,
+        'Q' => TradingState::QuotationOnly,
+        'T' => TradingState::Trading,
+        unknown_code => return Err(invalid_field("trading state", &[unknown_code as u8])),
+    };
+    Ok(trading_state)
+}
+
+fn read_reg_sho_action<T: Read>(buffer: &mut T) -> Result<RegSHOAction> {
+    let action = match buffer.read_u8().map(char::from)? {
+        '0' => RegSHOAction::NoPriceTest,
+        '1' => RegSHOAction::Intraday,
+        '2' => RegSHOAction::Continued,
+        unknown_code => return Err(invalid_field("Reg SHO action", &[unknown_code as u8])),
+    };
+    Ok(action)
+}
+
+fn read_ticker<T: Read>(buffer: &mut T, version: &Version) -> Result<Ticker> {
+    let mut buf = [0; 8];
+    let buf = &mut buf[..version.ticker_size()];
+    buffer.read_exact(buf)?;
+    parse_ticker(buf)
+}
+
+fn parse_ticker(buf: &[u8]) -> Result<Ticker> {
+    Ticker::from_bytes(buf).map_err(|_| invalid_field("ticker", buf))
 }
 
 fn read_mpid<T: Read>(buffer: &mut T) -> Result<String> {
     let mut buf = vec![0; 4];
     buffer.read_exact(&mut buf)?;
-    match String::from_utf8(buf) {
+    match std::str::from_utf8(&buf) {
         Ok(s) => Ok(s.trim().to_string()),
-        Err(e) => Err(Error::new(ErrorKind::InvalidData, e)),
+        Err(_) => Err(invalid_field("MPID", &buf)),
     }
 }
 
 pub(crate) fn read_size<T: Read>(buffer: &mut T) -> Result<u16> {
-    buffer.read_u16::<NetworkEndian>()
+    Ok(buffer.read_u16::<NetworkEndian>()?)
 }
 
 pub(crate) fn read_kind<T: Read>(buffer: &mut T) -> Result<char> {
-    buffer.read_u8().map(char::from)
+    Ok(buffer.read_u8().map(char::from)?)
 }
 
-pub(crate) fn read_seconds<T: Read>(buffer: &mut T) -> Result<u32> {
-    buffer.read_u32::<NetworkEndian>()
+pub(crate) fn read_seconds<T: Read>(buffer: &mut T, version: &Version) -> Result<u32> {
+    if version.is_text() {
+        read_numeric_u32(buffer, 5)
+    } else {
+        Ok(buffer.read_u32::<NetworkEndian>()?)
+    }
+}
+
+// Reads a newline-terminated record of a text version (a trailing carriage return is dropped),
+// adding the number of bytes read to `consumed`
+pub(crate) fn read_line<T: Read>(buffer: &mut T, consumed: &mut u64) -> Result<Vec<u8>> {
+    let mut line = Vec::new();
+    loop {
+        match buffer.read_u8() {
+            Ok(b'\n') => {
+                *consumed += 1;
+                break;
+            }
+            Ok(byte) => line.push(byte),
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof && !line.is_empty() => break,
+            Err(e) => return Err(e.into()),
+        }
+    }
+    *consumed += line.len() as u64;
+    if line.last() == Some(&b'\r') {
+        line.pop();
+    }
+    Ok(line)
 }
 
 pub(crate) fn peek_kind<T: Peek>(buffer: &mut T) -> Result<char> {
@@ -212,36 +577,72 @@ pub(crate) fn peek_kind<T: Peek>(buffer: &mut T) -> Result<char> {
     Ok(kind)
 }
 
-pub(crate) fn peek_ticker<T: Peek>(buffer: &mut T, at: usize, version: &Version) -> Result<String> {
-    let ahead = match version {
-        Version::V41 => at,     // 18,
-        Version::V50 => at + 6, // 24,
-    };
-    let buf = buffer.peek(ahead, 8)?;
-    match String::from_utf8(buf) {
-        Ok(s) => Ok(s.trim().to_string()),
-        Err(e) => Err(Error::new(ErrorKind::InvalidData, e)),
-    }
+// Peeks the ticker located `at` bytes from the start of the message
+pub(crate) fn peek_ticker<T: Peek>(buffer: &mut T, at: usize, version: &Version) -> Result<Ticker> {
+    parse_ticker(buffer.peek(at, version.ticker_size())?)
 }
 
+// Peeks the order reference number, which immediately follows the header of order messages
 pub(crate) fn peek_refno<T: Peek>(buffer: &mut T, version: &Version) -> Result<u64> {
-    let ahead = match version {
-        Version::V41 => 5,
-        Version::V50 => 11,
+    let buf = buffer.peek(version.header_size(), version.refno_size())?;
+    if version.is_text() {
+        parse_numeric(buf)
+    } else {
+        let arr: [u8; 8] = buf.try_into().unwrap();
+        Ok(u64::from_be_bytes(arr))
+    }
+}
+
+// Peeks the stock locate code, which follows the message type in Version 5.0
+pub(crate) fn peek_locate<T: Peek>(buffer: &mut T) -> Result<u16> {
+    let buf = buffer.peek(1, 2)?;
+    Ok(u16::from_be_bytes([buf[0], buf[1]]))
+}
+
+// Reads the next length-prefixed message into `frame`. Returns `false` at the end of the input.
+pub(crate) fn read_frame<R: Read>(reader: &mut R, frame: &mut Vec<u8>) -> Result<bool> {
+    let size = match reader.read_u16::<NetworkEndian>() {
+        Ok(size) => size as usize,
+        Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(false),
+        Err(e) => return Err(e.into()),
     };
-    let buf = buffer.peek(ahead, 8)?;
-    let arr: [u8; 8] = buf.try_into().unwrap();
-    Ok(u64::from_be_bytes(arr))
+    frame.resize(size, 0);
+    reader.read_exact(frame).map_err(|e| match e.kind() {
+        ErrorKind::UnexpectedEof => Error::TruncatedMessage { size },
+        _ => e.into(),
+    })?;
+    Ok(true)
+}
+
+// Returns a field of a message held in memory (without its length prefix)
+pub(crate) fn frame_field(frame: &[u8], at: usize, size: usize) -> Result<&[u8]> {
+    frame.get(at..at + size).ok_or_else(|| Error::SizeMismatch {
+        kind: frame.first().map_or('?', |&b| char::from(b)),
+        expected: None,
+        found: frame.len(),
+    })
+}
+
+pub(crate) fn frame_u32(frame: &[u8], at: usize) -> Result<u32> {
+    Ok(u32::from_be_bytes(
+        frame_field(frame, at, 4)?.try_into().unwrap(),
+    ))
+}
+
+pub(crate) fn frame_u64(frame: &[u8], at: usize) -> Result<u64> {
+    Ok(u64::from_be_bytes(
+        frame_field(frame, at, 8)?.try_into().unwrap(),
+    ))
 }
 
 // Data schema for storing order-related messages
-#[derive(Debug, Getters, Serialize)]
+#[derive(Debug, Getters, Setters, Serialize)]
 pub struct OrderMessage {
     #[getset(get = "pub")]
-    date: String,
+    date: Arc<str>,
     nanoseconds: u64,
     kind: char,
-    ticker: String,
+    ticker: Ticker,
     side: Side,
     price: u32,
     shares: u32,
@@ -250,10 +651,48 @@ pub struct OrderMessage {
     mpid: Option<String>,
     printable: Option<bool>,
     execution_price: Option<u32>,
+    #[getset(set = "pub")]
+    short_sale_restricted: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stock_locate: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tracking_number: Option<u16>,
+}
+
+/// Output schemas that carry the Version 5.0 stock locate and tracking number.
+pub trait Locate {
+    /// Drops the stock locate and tracking number so that they are not serialized.
+    fn clear_locate(&mut self);
+}
+
+macro_rules! impl_locate {
+    ($($schema:ty),* $(,)?) => {
+        $(
+            impl Locate for $schema {
+                fn clear_locate(&mut self) {
+                    self.stock_locate = None;
+                    self.tracking_number = None;
+                }
+            }
+        )*
+    };
 }
 
+impl_locate!(
+    OrderMessage,
+    TradeMessage,
+    NOIIMessage,
+    MarketParticipantMessage,
+    MWCBMessage,
+    IPOQuotingMessage,
+    LULDCollarMessage,
+    OperationalHaltMessage,
+    RPIIMessage,
+    DLCRMessage,
+);
+
 pub trait IntoOrderMessage {
-    fn into_order_message(self, date: String) -> OrderMessage;
+    fn into_order_message(self, date: Arc<str>) -> OrderMessage;
 }
 
 // Trade messages provide execution details for *non-displayable order types*.
@@ -261,25 +700,31 @@ pub trait IntoOrderMessage {
 // Therefore, it is possible to receive multiple trade messages for the same order. Trade messages
 // should be included in volume and market statistics, but they should not be included in order book
 // reconstruction as non-displayable orders do not impact the order book.
-#[derive(Debug, Getters, Serialize)]
+#[derive(Debug, Getters, Setters, Serialize)]
 #[getset(get = "pub")]
 pub struct TradeMessage {
-    date: String,
+    date: Arc<str>,
     nanoseconds: u64,
     kind: char, // P = non-cross, Q = cross, B = broken
     refno: u64,
     side: Side, /* The type of non-display order on the book being matched (always "B" effective
                  * 07/14/2014) */
     shares: u64,
-    ticker: String,
+    ticker: Ticker,
     price: u32,
     matchno: u64,
     cross_price: u32,
     cross_type: char,
+    #[getset(set = "pub")]
+    short_sale_restricted: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stock_locate: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tracking_number: Option<u16>,
 }
 
 pub trait IntoTradeMessage {
-    fn into_trade_message(self, date: String) -> TradeMessage;
+    fn into_trade_message(self, date: Arc<str>) -> TradeMessage;
 }
 
 // NOII (net order imbalance indicator) messages indicate the imbalance between buy and sell orders
@@ -292,10 +737,10 @@ pub trait IntoTradeMessage {
 #[derive(Debug, Getters, Serialize)]
 #[getset(get = "pub")]
 pub struct NOIIMessage {
-    date: String,
+    date: Arc<str>,
     nanoseconds: u64,
     kind: char, // I
-    ticker: String,
+    ticker: Ticker,
     paired_shares: u64, // total number of shares eligible to be matched at current reference price
     imbalance_shares: u64, // number of shares not paired at current reference price
     imbalance_direction: char, // B = buy, S = sell, N = none
@@ -304,10 +749,172 @@ pub struct NOIIMessage {
     ref_price: u32,
     cross_type: char,    // O = open, C = close, H = IPO and halted/paused securities
     var_indicator: char, // See protocol
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stock_locate: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tracking_number: Option<u16>,
 }
 
 pub trait IntoNOIIMessage {
-    fn into_noii_message(self, date: String) -> NOIIMessage;
+    fn into_noii_message(self, date: Arc<str>) -> NOIIMessage;
+}
+
+// Market participant messages record the registration of market makers (MPIDs) in a security,
+// i.e., whether the MPID is the primary market maker, its mode and its participation state.
+#[derive(Debug, Getters, Serialize)]
+#[getset(get = "pub")]
+pub struct MarketParticipantMessage {
+    date: Arc<str>,
+    nanoseconds: u64,
+    kind: char, // L
+    mpid: String,
+    ticker: Ticker,
+    primary_market_maker: bool,
+    mode: char,  // N = normal, P = passive, S = syndicate, R = pre-syndicate, L = penalty
+    state: char, // A = active, E = excused, W = withdrawn, S = suspended, D = deleted
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stock_locate: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tracking_number: Option<u16>,
+}
+
+pub trait IntoMarketParticipantMessage {
+    fn into_market_participant_message(self, date: Arc<str>) -> MarketParticipantMessage;
+}
+
+// MWCB (market-wide circuit breaker) messages record the daily decline levels of the breakers
+// (`kind` V) and breaches of those levels (`kind` W). Breaches trigger a market-wide halt, which
+// is announced through system event messages.
+#[derive(Debug, Getters, Serialize)]
+#[getset(get = "pub")]
+pub struct MWCBMessage {
+    date: Arc<str>,
+    nanoseconds: u64,
+    kind: char, // V = decline level, W = status
+    level1: Option<u64>,
+    level2: Option<u64>,
+    level3: Option<u64>,
+    breached_level: Option<char>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stock_locate: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tracking_number: Option<u16>,
+}
+
+pub trait IntoMWCBMessage {
+    fn into_mwcb_message(self, date: Arc<str>) -> MWCBMessage;
+}
+
+// IPO quoting period messages announce when quotations of an IPO security are anticipated to be
+// released (or that the release has been canceled/postponed).
+#[derive(Debug, Getters, Serialize)]
+#[getset(get = "pub")]
+pub struct IPOQuotingMessage {
+    date: Arc<str>,
+    nanoseconds: u64,
+    kind: char, // K
+    ticker: Ticker,
+    release_time: u32,       // seconds since midnight
+    release_qualifier: char, // A = anticipated, C = canceled or postponed
+    ipo_price: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stock_locate: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tracking_number: Option<u16>,
+}
+
+pub trait IntoIPOQuotingMessage {
+    fn into_ipo_quoting_message(self, date: Arc<str>) -> IPOQuotingMessage;
+}
+
+// LULD (limit up/limit down) auction collar messages indicate the price bounds of the reopening
+// auction that follows a LULD trading pause.
+#[derive(Debug, Getters, Serialize)]
+#[getset(get = "pub")]
+pub struct LULDCollarMessage {
+    date: Arc<str>,
+    nanoseconds: u64,
+    kind: char, // J
+    ticker: Ticker,
+    reference_price: u32,
+    upper_price: u32,
+    lower_price: u32,
+    extension: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stock_locate: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tracking_number: Option<u16>,
+}
+
+pub trait IntoLULDCollarMessage {
+    fn into_luld_collar_message(self, date: Arc<str>) -> LULDCollarMessage;
+}
+
+// Operational halt messages indicate that trading in a security has been halted or resumed on a
+// specific Nasdaq market center for operational reasons.
+#[derive(Debug, Getters, Serialize)]
+#[getset(get = "pub")]
+pub struct OperationalHaltMessage {
+    date: Arc<str>,
+    nanoseconds: u64,
+    kind: char, // h
+    ticker: Ticker,
+    market_code: char, // Q = Nasdaq, B = BX, X = PSX
+    action: char,      // H = halted, T = resumed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stock_locate: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tracking_number: Option<u16>,
+}
+
+pub trait IntoOperationalHaltMessage {
+    fn into_operational_halt_message(self, date: Arc<str>) -> OperationalHaltMessage;
+}
+
+// Retail price improvement indicator (RPII) messages flag retail interest on either side of the
+// book.
+#[derive(Debug, Getters, Serialize)]
+#[getset(get = "pub")]
+pub struct RPIIMessage {
+    date: Arc<str>,
+    nanoseconds: u64,
+    kind: char, // N
+    ticker: Ticker,
+    interest_flag: char, // B = buy side, S = sell side, A = both sides, N = none
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stock_locate: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tracking_number: Option<u16>,
+}
+
+pub trait IntoRPIIMessage {
+    fn into_rpii_message(self, date: Arc<str>) -> RPIIMessage;
+}
+
+// Direct listing with capital raise (DLCR) messages report price discovery during the opening
+// cross of a DLCR security.
+#[derive(Debug, Getters, Serialize)]
+#[getset(get = "pub")]
+pub struct DLCRMessage {
+    date: Arc<str>,
+    nanoseconds: u64,
+    kind: char, // O
+    ticker: Ticker,
+    open_eligibility: char, // Y = eligible, N = not eligible
+    minimum_price: u32,
+    maximum_price: u32,
+    near_execution_price: u32,
+    near_execution_time: u64,
+    lower_collar: u32,
+    upper_collar: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stock_locate: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tracking_number: Option<u16>,
+}
+
+pub trait IntoDLCRMessage {
+    fn into_dlcr_message(self, date: Arc<str>) -> DLCRMessage;
 }
 
 #[cfg(test)]
@@ -324,6 +931,88 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn message_sizes_match_builders() {
+        use test_helpers::message_builders::*;
+
+        let v41 = [
+            timestamp_v41(1),
+            system_event_v41(0, 'O'),
+            stock_directory_v41(0, "AAPL", 'Q', 'N', 100),
+            trading_action_v41(0, "AAPL", 'T', "    "),
+            reg_sho_v41(0, "AAPL", '0'),
+            market_participant_v41(0, "GSCO", "AAPL", true, 'N', 'A'),
+            add_order_v41(0, 1, Side::Buy, 100, "AAPL", 1000),
+            add_order_with_mpid_v41(0, 1, Side::Buy, 100, "AAPL", 1000, "GSCO"),
+            execute_order_v41(0, 1, 100),
+            execute_order_with_price_v41(0, 1, 100, true, 1000),
+            cancel_order_v41(0, 1, 100),
+            delete_order_v41(0, 1),
+            replace_order_v41(0, 1, 2, 100, 1000),
+        ];
+        for data in v41 {
+            let data = data.into_inner();
+            let kind = char::from(data[0]);
+            assert_eq!(
+                Version::V41.message_size(kind),
+                Some(data.len()),
+                "{}",
+                kind
+            );
+        }
+
+        let v50 = [
+            system_event_v50(0, 'O'),
+            stock_directory_v50(0, "AAPL", 'Q', 'N', 100, 'C', "Z", 'N', 'N'),
+            trading_action_v50(0, "AAPL", 'T', "    "),
+            reg_sho_v50(0, "AAPL", '0'),
+            market_participant_v50(0, "GSCO", "AAPL", true, 'N', 'A'),
+            mwcb_decline_level_v50(0, 1, 2, 3),
+            mwcb_status_v50(0, '1'),
+            ipo_quoting_period_v50(0, "AAPL", 0, 'A', 1000),
+            luld_auction_collar_v50(0, "AAPL", 1000, 1100, 900, 0),
+            operational_halt_v50(0, "AAPL", 'Q', 'H'),
+            rpii_v50(0, "AAPL", 'B'),
+            dlcr_v50(0, "AAPL", 'Y', 1000, 2000, 1500, 0, 1400, 1600),
+            add_order_v50(0, 1, Side::Buy, 100, "AAPL", 1000),
+            execute_order_v50(0, 1, 100),
+            cancel_order_v50(0, 1, 100),
+            delete_order_v50(0, 1),
+            replace_order_v50(0, 1, 2, 100, 1000),
+            broken_trade_v50(0, 1),
+            noii_v50(0, "AAPL", 1, 2, 'B', 3, 4, 5, 'O'),
+        ];
+        for data in v50 {
+            let data = data.into_inner();
+            let kind = char::from(data[0]);
+            assert_eq!(
+                Version::V50.message_size(kind),
+                Some(data.len()),
+                "{}",
+                kind
+            );
+        }
+
+        // Trade, cross trade and NOII lengths from the specifications
+        assert_eq!(Version::V50.message_size('P'), Some(44));
+        assert_eq!(Version::V50.message_size('Q'), Some(40));
+        assert_eq!(Version::V50.message_size('I'), Some(50));
+        assert_eq!(Version::V41.message_size('I'), Some(44));
+        assert_eq!(Version::V40.message_size('A'), Some(28));
+
+        assert_eq!(Version::V50.message_size('T'), None);
+        assert_eq!(Version::V41.message_size('O'), None);
+        assert_eq!(Version::V30.message_size('A'), None);
+    }
+
+    #[test]
+    fn parse_version() {
+        assert_eq!("50".parse::<Version>().unwrap(), Version::V50);
+        assert_eq!("v41".parse::<Version>().unwrap(), Version::V41);
+        assert_eq!("4.0".parse::<Version>().unwrap(), Version::V40);
+        assert!("60".parse::<Version>().is_err());
+    }
+
     #[test]
     fn read_nanoseconds_v41() {
         let bytes = 999_u32.to_be_bytes();
@@ -361,16 +1050,140 @@ mod tests {
         assert!(read_event_code(&mut buffer).is_err());
     }
 
+    #[test]
+    fn read_unknown_trading_state() {
+        let mut buffer = "X".as_bytes();
+        assert!(read_trading_state(&mut buffer).is_err());
+    }
+
+    #[test]
+    fn read_unknown_reg_sho_action() {
+        let mut buffer = "3".as_bytes();
+        assert!(read_reg_sho_action(&mut buffer).is_err());
+    }
+
     #[test]
     fn read_unknown_side() {
         let mut buffer = "X".as_bytes();
         assert!(read_side(&mut buffer).is_err());
     }
 
+    #[test]
+    fn read_nanoseconds_v40() {
+        let bytes = 999_u32.to_be_bytes();
+        let mut buffer = bytes.as_slice();
+        assert_eq!(
+            read_nanoseconds(&mut buffer, &Version::V40, Some(1)).unwrap(),
+            1_000_000_999
+        );
+    }
+
+    #[test]
+    fn read_nanoseconds_v30() {
+        let mut buffer = "250".as_bytes();
+        assert_eq!(
+            read_nanoseconds(&mut buffer, &Version::V30, Some(1)).unwrap(),
+            1_250_000_000
+        );
+    }
+
+    #[test]
+    fn read_nanoseconds_v20() {
+        let mut buffer = "34200250".as_bytes();
+        assert_eq!(
+            read_nanoseconds(&mut buffer, &Version::V20, None).unwrap(),
+            34_200_250_000_000
+        );
+    }
+
+    #[test]
+    fn read_text_fields() {
+        let mut buffer = "000012345   100   1234500".as_bytes();
+        assert_eq!(read_refno(&mut buffer, &Version::V30).unwrap(), 12345);
+        assert_eq!(read_shares(&mut buffer, &Version::V30).unwrap(), 100);
+        assert_eq!(read_price(&mut buffer, &Version::V30).unwrap(), 1_234_500);
+    }
+
+    #[test]
+    fn read_malformed_text_field() {
+        let mut buffer = "12a456".as_bytes();
+        assert!(read_shares(&mut buffer, &Version::V20).is_err());
+    }
+
+    #[test]
+    fn read_line_drops_terminators() {
+        let mut buffer = "S123O\r\nT12345".as_bytes();
+        let mut consumed = 0;
+        assert_eq!(read_line(&mut buffer, &mut consumed).unwrap(), b"S123O");
+        assert_eq!(read_line(&mut buffer, &mut consumed).unwrap(), b"T12345");
+        assert_eq!(consumed, 13);
+        assert!(read_line(&mut buffer, &mut consumed).is_err());
+    }
+
+    #[test]
+    fn read_frames_until_end_of_input() {
+        let mut buffer: &[u8] = &[0, 2, b'T', 1, 0, 1, b'S'];
+        let mut frame = Vec::new();
+        assert!(read_frame(&mut buffer, &mut frame).unwrap());
+        assert_eq!(frame, b"T\x01");
+        assert!(read_frame(&mut buffer, &mut frame).unwrap());
+        assert_eq!(frame, b"S");
+        assert!(!read_frame(&mut buffer, &mut frame).unwrap());
+
+        let mut buffer: &[u8] = &[0, 4, b'T', 1];
+        let err = read_frame(&mut buffer, &mut frame).unwrap_err();
+        assert!(matches!(err, Error::TruncatedMessage { size: 4 }));
+    }
+
+    #[test]
+    fn read_invalid_codes() {
+        let err = read_side(&mut "Z".as_bytes()).unwrap_err();
+        assert!(matches!(err, Error::InvalidSide(b'Z')));
+        let err = read_event_code(&mut "Z".as_bytes()).unwrap_err();
+        assert!(matches!(err, Error::InvalidEventCode(b'Z')));
+    }
+
+    #[test]
+    fn timestamps_require_clock() {
+        let mut buffer = [0, 0, 0, 1].as_slice();
+        let err = read_nanoseconds(&mut buffer, &Version::V41, None).unwrap_err();
+        assert!(matches!(err, Error::MissingClock));
+    }
+
     #[test]
     fn read_ticker_trimmed() {
         let mut buffer = "AAPL    ".as_bytes();
-        assert_eq!(read_ticker(&mut buffer).unwrap(), "AAPL");
+        assert_eq!(read_ticker(&mut buffer, &Version::V41).unwrap(), "AAPL");
+    }
+
+    #[test]
+    fn read_ticker_v40() {
+        let mut buffer = "MSFT  AAPL  ".as_bytes();
+        assert_eq!(read_ticker(&mut buffer, &Version::V40).unwrap(), "MSFT");
+        assert_eq!(read_ticker(&mut buffer, &Version::V40).unwrap(), "AAPL");
+    }
+
+    #[test]
+    fn locate_columns_are_optional() {
+        use test_helpers::message_builders::{add_order_v50, with_locate};
+
+        let serialize = |message: &OrderMessage| {
+            let mut writer = csv::Writer::from_writer(vec![]);
+            writer.serialize(message).unwrap();
+            String::from_utf8(writer.into_inner().unwrap()).unwrap()
+        };
+
+        let mut data = with_locate(add_order_v50(0, 1, Side::Buy, 100, "A", 1), 13, 2);
+        let message = AddOrder::read(&mut data, &Version::V50, &mut Context::new()).unwrap();
+        let mut message = message.into_order_message("2020-01-30".into());
+
+        let output = serialize(&message);
+        assert!(output.starts_with("date,") && output.contains(",stock_locate,tracking_number\n"));
+        assert!(output.ends_with(",13,2\n"));
+
+        message.clear_locate();
+        let output = serialize(&message);
+        assert!(!output.contains("stock_locate"));
     }
 
     #[test]
@@ -390,7 +1203,7 @@ mod tests {
     fn present_order() {
         let mut context = Context::new();
         let order = OrderState {
-            ticker: String::from("A"),
+            ticker: "A".parse().unwrap(),
             side: Side::Buy,
             price: 0,
             shares: 0,
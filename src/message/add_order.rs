@@ -1,18 +1,25 @@
-use std::io::{Read, Result, Seek, SeekFrom};
+use std::{
+    io::{Read, Seek},
+    sync::Arc,
+};
 
 use getset::Getters;
 
 use super::{
-    read_kind, read_mpid, read_nanoseconds, read_price, read_refno, read_shares, read_side,
-    read_ticker, Context, IntoOrderMessage, OrderMessage, OrderState, ReadMessage, Side, Version,
+    read_kind, read_locate, read_mpid, read_nanoseconds, read_price, read_refno, read_shares,
+    read_side, read_ticker, Context, IntoOrderMessage, OrderMessage, OrderState, ReadMessage, Side,
+    Ticker, Version,
 };
+use crate::error::Result;
 
 #[derive(Debug, Getters)]
 #[getset(get = "pub")]
 pub struct AddOrder {
     nanoseconds: u64,
     kind: char,
-    pub(crate) ticker: String,
+    stock_locate: Option<u16>,
+    tracking_number: Option<u16>,
+    pub(crate) ticker: Ticker,
     side: Side,
     price: u32,
     shares: u32,
@@ -25,7 +32,7 @@ impl AddOrder {
     pub(crate) fn new(
         nanoseconds: u64,
         kind: char,
-        ticker: String,
+        ticker: Ticker,
         side: Side,
         price: u32,
         shares: u32,
@@ -36,6 +43,8 @@ impl AddOrder {
         Self {
             nanoseconds,
             kind,
+            stock_locate: None,
+            tracking_number: None,
             ticker,
             side,
             price,
@@ -45,6 +54,16 @@ impl AddOrder {
             mpid,
         }
     }
+
+    pub(crate) fn with_locate(
+        mut self,
+        stock_locate: Option<u16>,
+        tracking_number: Option<u16>,
+    ) -> Self {
+        self.stock_locate = stock_locate;
+        self.tracking_number = tracking_number;
+        self
+    }
 }
 
 impl ReadMessage for AddOrder {
@@ -54,15 +73,13 @@ impl ReadMessage for AddOrder {
     {
         // Read data from buffer
         let kind = read_kind(buffer)?;
-        if version == &Version::V50 {
-            buffer.seek(SeekFrom::Current(4))?; // Discard stock locate and tracking number
-        }
+        let (stock_locate, tracking_number) = read_locate(buffer, version)?;
         let nanoseconds = read_nanoseconds(buffer, version, context.clock)?;
-        let refno = read_refno(buffer)?;
+        let refno = read_refno(buffer, version)?;
         let side = read_side(buffer)?;
-        let shares = read_shares(buffer)?;
-        let ticker = read_ticker(buffer)?;
-        let price = read_price(buffer)?;
+        let shares = read_shares(buffer, version)?;
+        let ticker = read_ticker(buffer, version)?;
+        let price = read_price(buffer, version)?;
         let mpid = if kind == 'F' {
             Some(read_mpid(buffer)?)
         } else {
@@ -71,7 +88,7 @@ impl ReadMessage for AddOrder {
 
         // Update context
         let order = OrderState {
-            ticker: ticker.clone(),
+            ticker,
             side,
             price,
             shares,
@@ -82,6 +99,8 @@ impl ReadMessage for AddOrder {
         Ok(Self {
             nanoseconds,
             kind,
+            stock_locate,
+            tracking_number,
             ticker,
             side,
             price,
@@ -93,21 +112,24 @@ impl ReadMessage for AddOrder {
     }
 }
 
-impl IntoOrderMessage for AddOrder {
-    fn into_order_message(self, date: String) -> OrderMessage {
+impl IntoOrderMessage for &AddOrder {
+    fn into_order_message(self, date: Arc<str>) -> OrderMessage {
         OrderMessage {
             date,
             nanoseconds: self.nanoseconds,
             kind: self.kind,
+            stock_locate: self.stock_locate,
+            tracking_number: self.tracking_number,
             ticker: self.ticker,
             side: self.side,
             price: self.price,
             shares: self.shares,
             refno: self.refno,
             from_replace: self.from_replace,
-            mpid: self.mpid,
+            mpid: self.mpid.clone(),
             printable: None,
             execution_price: None,
+            short_sale_restricted: None,
         }
     }
 }
@@ -167,6 +189,63 @@ mod tests {
         assert_eq!(order.shares, 200);
     }
 
+    #[test]
+    fn preserves_locate_and_tracking_number() {
+        let data = add_order_v50(1000, 12345, Side::Buy, 100, "AAPL", 15000);
+        let mut data = with_locate(data, 13, 2);
+        let mut context = Context::new();
+
+        let message = AddOrder::read(&mut data, &Version::V50, &mut context).unwrap();
+
+        assert_eq!(*message.stock_locate(), Some(13));
+        assert_eq!(*message.tracking_number(), Some(2));
+        assert_eq!(*message.refno(), 12345);
+
+        let mut data = add_order_v41(1000, 12345, Side::Buy, 100, "AAPL", 15000);
+        context.update_clock(0);
+        let message = AddOrder::read(&mut data, &Version::V41, &mut context).unwrap();
+
+        assert_eq!(*message.stock_locate(), None);
+        assert_eq!(*message.tracking_number(), None);
+    }
+
+    #[test]
+    fn returns_message_and_updates_context_v40() {
+        let mut data = add_order_v40(1000, 12345, Side::Sell, 200, "TSLA", 25000);
+        let mut context = Context::new();
+        context.update_clock(1);
+
+        let message = AddOrder::read(&mut data, &Version::V40, &mut context).unwrap();
+
+        assert_eq!(*message.nanoseconds(), 1_000_001_000);
+        assert_eq!(*message.refno(), 12345);
+        assert_eq!(*message.shares(), 200);
+        assert_eq!(*message.ticker(), "TSLA");
+        assert_eq!(*message.price(), 25000);
+        assert!(context.has_order(12345));
+    }
+
+    #[test]
+    fn returns_message_and_updates_context_v30() {
+        let mut data = add_order_v30(250, 12345, Side::Buy, 300, "MSFT", 281500);
+        let mut context = Context::new();
+        context.update_clock(34200);
+
+        let message = AddOrder::read(&mut data, &Version::V30, &mut context).unwrap();
+
+        assert_eq!(*message.kind(), 'A');
+        assert_eq!(*message.nanoseconds(), 34_200_250_000_000);
+        assert_eq!(*message.refno(), 12345);
+        assert_eq!(*message.side(), Side::Buy);
+        assert_eq!(*message.shares(), 300);
+        assert_eq!(*message.ticker(), "MSFT");
+        assert_eq!(*message.price(), 281500);
+
+        let order = &context.active_orders[&12345];
+        assert_eq!(order.ticker, "MSFT");
+        assert_eq!(order.shares, 300);
+    }
+
     #[test]
     fn handles_attribution_message_with_mpid() {
         let mut data = add_order_with_mpid_v41(2000, 54321, Side::Buy, 50, "MSFT", 30000, "NSDQ");
@@ -197,7 +276,9 @@ mod tests {
         let add_order = AddOrder {
             nanoseconds: 1000,
             kind: 'A',
-            ticker: "GOOG".to_string(),
+            stock_locate: None,
+            tracking_number: None,
+            ticker: "GOOG".parse().unwrap(),
             side: Side::Buy,
             price: 280000,
             shares: 75,
@@ -206,9 +287,9 @@ mod tests {
             mpid: None,
         };
 
-        let order_message = add_order.into_order_message("2023-01-15".to_string());
+        let order_message = add_order.into_order_message("2023-01-15".into());
 
-        assert_eq!(*order_message.date(), "2023-01-15");
+        assert_eq!(&**order_message.date(), "2023-01-15");
         assert_eq!(order_message.nanoseconds, 1000);
         assert_eq!(order_message.kind, 'A');
         assert_eq!(order_message.ticker, "GOOG");
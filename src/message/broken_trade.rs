@@ -1,17 +1,23 @@
-use std::io::{Read, Result, Seek, SeekFrom};
+use std::{
+    io::{Read, Seek},
+    sync::Arc,
+};
 
 use getset::Getters;
 
 use super::{
-    read_kind, read_matchno, read_nanoseconds, Context, IntoTradeMessage, ReadMessage, Side,
-    TradeMessage, Version,
+    read_kind, read_locate, read_matchno, read_nanoseconds, Context, IntoTradeMessage, ReadMessage,
+    Side, Ticker, TradeMessage, Version,
 };
+use crate::error::Result;
 
 #[derive(Debug, Getters)]
 #[getset(get = "pub")]
 pub struct BrokenTrade {
     nanoseconds: u64,
     kind: char,
+    stock_locate: Option<u16>,
+    tracking_number: Option<u16>,
     matchno: u64,
 }
 
@@ -21,34 +27,37 @@ impl ReadMessage for BrokenTrade {
         T: Read + Seek,
     {
         let kind = read_kind(buffer)?;
-        if version == &Version::V50 {
-            buffer.seek(SeekFrom::Current(4))?; // Discard stock locate and tracking number
-        }
+        let (stock_locate, tracking_number) = read_locate(buffer, version)?;
         let nanoseconds = read_nanoseconds(buffer, version, context.clock)?;
-        let matchno = read_matchno(buffer)?;
+        let matchno = read_matchno(buffer, version)?;
 
         Ok(Self {
             nanoseconds,
             kind,
+            stock_locate,
+            tracking_number,
             matchno,
         })
     }
 }
 
-impl IntoTradeMessage for BrokenTrade {
-    fn into_trade_message(self, date: String) -> TradeMessage {
+impl IntoTradeMessage for &BrokenTrade {
+    fn into_trade_message(self, date: Arc<str>) -> TradeMessage {
         TradeMessage {
             date,
             nanoseconds: self.nanoseconds,
             kind: self.kind,
-            refno: 0,               // Broken trades don't have reference numbers
-            side: Side::Buy,        // Broken trades don't have a specific side
-            shares: 0,              // Broken trades don't have shares
-            ticker: "".to_string(), // Broken trades don't specify ticker
-            price: 0,               // Broken trades don't have price
+            stock_locate: self.stock_locate,
+            tracking_number: self.tracking_number,
+            refno: 0,                  // Broken trades don't have reference numbers
+            side: Side::Buy,           // Broken trades don't have a specific side
+            shares: 0,                 // Broken trades don't have shares
+            ticker: Ticker::default(), // Broken trades don't specify ticker
+            price: 0,                  // Broken trades don't have price
             matchno: self.matchno,
             cross_price: 0,
             cross_type: ' ',
+            short_sale_restricted: None,
         }
     }
 }
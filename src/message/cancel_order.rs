@@ -1,18 +1,24 @@
-use std::io::{Read, Result, Seek, SeekFrom};
+use std::{
+    io::{Read, Seek},
+    sync::Arc,
+};
 
 use getset::Getters;
 
 use super::{
-    read_kind, read_nanoseconds, read_refno, read_shares, Context, IntoOrderMessage, OrderMessage,
-    ReadMessage, Side, Version,
+    read_kind, read_locate, read_nanoseconds, read_refno, read_shares, Context, IntoOrderMessage,
+    OrderMessage, ReadMessage, Side, Ticker, Version,
 };
+use crate::error::{Error, Result};
 
 #[derive(Debug, Getters)]
 #[getset(get = "pub")]
 pub struct CancelOrder {
     nanoseconds: u64,
     kind: char,
-    pub(crate) ticker: String,
+    stock_locate: Option<u16>,
+    tracking_number: Option<u16>,
+    pub(crate) ticker: Ticker,
     side: Side,
     price: u32,
     shares: u32,
@@ -26,25 +32,32 @@ impl ReadMessage for CancelOrder {
     {
         // Read data from buffer
         let kind = read_kind(buffer)?;
-        if version == &Version::V50 {
-            buffer.seek(SeekFrom::Current(4))?; // Discard stock locate and tracking number
-        }
+        let (stock_locate, tracking_number) = read_locate(buffer, version)?;
         let nanoseconds = read_nanoseconds(buffer, version, context.clock)?;
-        let refno = read_refno(buffer)?;
-        let shares = read_shares(buffer)?;
+        let refno = read_refno(buffer, version)?;
+        let shares = read_shares(buffer, version)?;
 
         // Update context
         let order = context
             .active_orders
             .get_mut(&refno)
-            .expect("Order not found");
-        order.shares -= shares;
+            .ok_or(Error::UnknownRefno(refno))?;
+        order.shares = order
+            .shares
+            .checked_sub(shares)
+            .ok_or(Error::OrderUnderflow {
+                refno,
+                shares,
+                remaining: order.shares,
+            })?;
 
         // Return message
         Ok(Self {
             nanoseconds,
             kind,
-            ticker: order.ticker.clone(),
+            stock_locate,
+            tracking_number,
+            ticker: order.ticker,
             side: order.side,
             price: order.price,
             shares,
@@ -53,12 +66,14 @@ impl ReadMessage for CancelOrder {
     }
 }
 
-impl IntoOrderMessage for CancelOrder {
-    fn into_order_message(self, date: String) -> OrderMessage {
+impl IntoOrderMessage for &CancelOrder {
+    fn into_order_message(self, date: Arc<str>) -> OrderMessage {
         OrderMessage {
             date,
             nanoseconds: self.nanoseconds,
             kind: self.kind,
+            stock_locate: self.stock_locate,
+            tracking_number: self.tracking_number,
             ticker: self.ticker,
             side: self.side,
             price: self.price,
@@ -68,6 +83,7 @@ impl IntoOrderMessage for CancelOrder {
             mpid: None,
             printable: None,
             execution_price: None,
+            short_sale_restricted: None,
         }
     }
 }
@@ -104,11 +120,11 @@ mod tests {
     }
 
     #[test]
-    #[should_panic(expected = "Order not found")]
-    fn panics_with_message() {
+    fn unknown_order_is_an_error() {
         let mut data = cancel_order_v41(0, 0, 10);
         let mut context = Context::new();
         context.update_clock(0);
-        let _ = CancelOrder::read(&mut data, &Version::V41, &mut context).unwrap();
+        let err = CancelOrder::read(&mut data, &Version::V41, &mut context).unwrap_err();
+        assert!(matches!(err, Error::UnknownRefno(0)));
     }
 }
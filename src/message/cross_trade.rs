@@ -1,20 +1,26 @@
-use std::io::{Read, Result, Seek, SeekFrom};
+use std::{
+    io::{Read, Seek},
+    sync::Arc,
+};
 
-use byteorder::{NetworkEndian, ReadBytesExt};
+use byteorder::ReadBytesExt;
 use getset::Getters;
 
 use super::{
-    read_kind, read_matchno, read_nanoseconds, read_price, read_ticker, Context, IntoTradeMessage,
-    ReadMessage, Side, TradeMessage, Version,
+    read_kind, read_locate, read_long_shares, read_matchno, read_nanoseconds, read_price,
+    read_ticker, Context, IntoTradeMessage, ReadMessage, Side, Ticker, TradeMessage, Version,
 };
+use crate::error::Result;
 
 #[derive(Debug, Getters)]
 #[getset(get = "pub")]
 pub struct CrossTrade {
     nanoseconds: u64,
     kind: char,
+    stock_locate: Option<u16>,
+    tracking_number: Option<u16>,
     shares: u64,
-    ticker: String,
+    ticker: Ticker,
     cross_price: u32,
     matchno: u64,
     cross_type: char,
@@ -26,19 +32,19 @@ impl ReadMessage for CrossTrade {
         T: Read + Seek,
     {
         let kind = read_kind(buffer)?;
-        if version == &Version::V50 {
-            buffer.seek(SeekFrom::Current(4))?; // Discard stock locate and tracking number
-        }
+        let (stock_locate, tracking_number) = read_locate(buffer, version)?;
         let nanoseconds = read_nanoseconds(buffer, version, context.clock)?;
-        let shares = buffer.read_u64::<NetworkEndian>()?; // 8 bytes for cross trades
-        let ticker = read_ticker(buffer)?;
-        let cross_price = read_price(buffer)?;
-        let matchno = read_matchno(buffer)?;
+        let shares = read_long_shares(buffer, version)?; // Wider than order shares
+        let ticker = read_ticker(buffer, version)?;
+        let cross_price = read_price(buffer, version)?;
+        let matchno = read_matchno(buffer, version)?;
         let cross_type = buffer.read_u8().map(char::from)?;
 
         Ok(Self {
             nanoseconds,
             kind,
+            stock_locate,
+            tracking_number,
             shares,
             ticker,
             cross_price,
@@ -48,12 +54,14 @@ impl ReadMessage for CrossTrade {
     }
 }
 
-impl IntoTradeMessage for CrossTrade {
-    fn into_trade_message(self, date: String) -> TradeMessage {
+impl IntoTradeMessage for &CrossTrade {
+    fn into_trade_message(self, date: Arc<str>) -> TradeMessage {
         TradeMessage {
             date,
             nanoseconds: self.nanoseconds,
             kind: self.kind,
+            stock_locate: self.stock_locate,
+            tracking_number: self.tracking_number,
             refno: 0,        // Cross trades don't have reference numbers
             side: Side::Buy, // Cross trades don't have a specific side
             shares: self.shares,
@@ -62,6 +70,7 @@ impl IntoTradeMessage for CrossTrade {
             matchno: self.matchno,
             cross_price: self.cross_price,
             cross_type: self.cross_type,
+            short_sale_restricted: None,
         }
     }
 }
@@ -1,18 +1,24 @@
-use std::io::{Read, Result, Seek, SeekFrom};
+use std::{
+    io::{Read, Seek},
+    sync::Arc,
+};
 
 use getset::Getters;
 
 use super::{
-    read_kind, read_nanoseconds, read_refno, Context, IntoOrderMessage, OrderMessage, ReadMessage,
-    Side, Version,
+    read_kind, read_locate, read_nanoseconds, read_refno, Context, IntoOrderMessage, OrderMessage,
+    ReadMessage, Side, Ticker, Version,
 };
+use crate::error::{Error, Result};
 
 #[derive(Debug, Getters)]
 #[getset(get = "pub")]
 pub struct DeleteOrder {
     nanoseconds: u64,
     kind: char,
-    ticker: String,
+    stock_locate: Option<u16>,
+    tracking_number: Option<u16>,
+    ticker: Ticker,
     side: Side,
     price: u32,
     shares: u32,
@@ -24,7 +30,7 @@ impl DeleteOrder {
     pub(crate) fn new(
         nanoseconds: u64,
         kind: char,
-        ticker: String,
+        ticker: Ticker,
         side: Side,
         price: u32,
         shares: u32,
@@ -34,6 +40,8 @@ impl DeleteOrder {
         Self {
             nanoseconds,
             kind,
+            stock_locate: None,
+            tracking_number: None,
             ticker,
             side,
             price,
@@ -42,6 +50,16 @@ impl DeleteOrder {
             from_replace,
         }
     }
+
+    pub(crate) fn with_locate(
+        mut self,
+        stock_locate: Option<u16>,
+        tracking_number: Option<u16>,
+    ) -> Self {
+        self.stock_locate = stock_locate;
+        self.tracking_number = tracking_number;
+        self
+    }
 }
 
 impl ReadMessage for DeleteOrder {
@@ -51,22 +69,22 @@ impl ReadMessage for DeleteOrder {
     {
         // Read data from buffer
         let kind = read_kind(buffer)?;
-        if version == &Version::V50 {
-            buffer.seek(SeekFrom::Current(4))?; // Discard stock locate and tracking number
-        }
+        let (stock_locate, tracking_number) = read_locate(buffer, version)?;
         let nanoseconds = read_nanoseconds(buffer, version, context.clock)?;
-        let refno = read_refno(buffer)?;
+        let refno = read_refno(buffer, version)?;
 
         // Update context
         let order = context
             .active_orders
             .remove(&refno)
-            .expect("Order not found");
+            .ok_or(Error::UnknownRefno(refno))?;
 
         // Return message
         Ok(Self {
             nanoseconds,
             kind,
+            stock_locate,
+            tracking_number,
             ticker: order.ticker,
             side: order.side,
             price: order.price,
@@ -77,12 +95,14 @@ impl ReadMessage for DeleteOrder {
     }
 }
 
-impl IntoOrderMessage for DeleteOrder {
-    fn into_order_message(self, date: String) -> OrderMessage {
+impl IntoOrderMessage for &DeleteOrder {
+    fn into_order_message(self, date: Arc<str>) -> OrderMessage {
         OrderMessage {
             date,
             nanoseconds: self.nanoseconds,
             kind: self.kind,
+            stock_locate: self.stock_locate,
+            tracking_number: self.tracking_number,
             ticker: self.ticker,
             side: self.side,
             price: self.price,
@@ -92,6 +112,7 @@ impl IntoOrderMessage for DeleteOrder {
             mpid: None,
             printable: None,
             execution_price: None,
+            short_sale_restricted: None,
         }
     }
 }
@@ -132,7 +153,7 @@ mod tests {
         context.active_orders.insert(
             11111,
             OrderState {
-                ticker: "AMD".to_string(),
+                ticker: "AMD".parse().unwrap(),
                 side: Side::Buy,
                 price: 12000,
                 shares: 300,
@@ -154,13 +175,13 @@ mod tests {
     }
 
     #[test]
-    #[should_panic(expected = "Order not found")]
-    fn panics_when_order_not_found() {
+    fn unknown_order_is_an_error() {
         let mut data = delete_order_v50(1000, 99999);
         let mut context = Context::new();
         context.update_clock(0);
 
-        DeleteOrder::read(&mut data, &Version::V50, &mut context).unwrap();
+        let err = DeleteOrder::read(&mut data, &Version::V50, &mut context).unwrap_err();
+        assert!(matches!(err, Error::UnknownRefno(99999)));
     }
 
     #[test]
@@ -168,7 +189,9 @@ mod tests {
         let delete_order = DeleteOrder {
             nanoseconds: 5000,
             kind: 'D',
-            ticker: "META".to_string(),
+            stock_locate: None,
+            tracking_number: None,
+            ticker: "META".parse().unwrap(),
             side: Side::Buy,
             price: 32000,
             shares: 80,
@@ -176,9 +199,9 @@ mod tests {
             from_replace: Some(true),
         };
 
-        let order_message = delete_order.into_order_message("2023-12-25".to_string());
+        let order_message = delete_order.into_order_message("2023-12-25".into());
 
-        assert_eq!(*order_message.date(), "2023-12-25");
+        assert_eq!(&**order_message.date(), "2023-12-25");
         assert_eq!(order_message.nanoseconds, 5000);
         assert_eq!(order_message.kind, 'D');
         assert_eq!(order_message.ticker, "META");
@@ -197,7 +220,7 @@ mod tests {
         let delete_order = DeleteOrder::new(
             1500,
             'D',
-            "GOOGL".to_string(),
+            ticker("GOOGL"),
             Side::Sell,
             275000,
             25,
@@ -0,0 +1,145 @@
+use std::{
+    io::{Read, Seek},
+    sync::Arc,
+};
+
+use byteorder::{NetworkEndian, ReadBytesExt};
+use getset::Getters;
+
+use super::{
+    read_kind, read_locate, read_nanoseconds, read_price, read_ticker, require_v50, Context,
+    DLCRMessage, IntoDLCRMessage, ReadMessage, Ticker, Version,
+};
+use crate::error::Result;
+
+// Direct listing with capital raise (DLCR) price discovery messages (Version 5.0 only) are
+// disseminated during the opening cross of a DLCR security once it is eligible to open.
+#[derive(Debug, Getters)]
+#[getset(get = "pub")]
+pub struct DirectListingCapitalRaise {
+    nanoseconds: u64,
+    kind: char,
+    stock_locate: Option<u16>,
+    tracking_number: Option<u16>,
+    ticker: Ticker,
+    open_eligibility: char, // Y = eligible to open, N = not eligible
+    minimum_price: u32,
+    maximum_price: u32,
+    near_execution_price: u32,
+    near_execution_time: u64, // Nanoseconds since midnight
+    lower_collar: u32,
+    upper_collar: u32,
+}
+
+impl ReadMessage for DirectListingCapitalRaise {
+    fn read<T>(buffer: &mut T, version: &Version, context: &mut Context) -> Result<Self>
+    where
+        T: Read + Seek,
+    {
+        require_v50(version, 'O')?;
+
+        // Read data from buffer
+        let kind = read_kind(buffer)?;
+        let (stock_locate, tracking_number) = read_locate(buffer, version)?;
+        let nanoseconds = read_nanoseconds(buffer, version, context.clock)?;
+        let ticker = read_ticker(buffer, version)?;
+        let open_eligibility = buffer.read_u8().map(char::from)?;
+        let minimum_price = read_price(buffer, version)?;
+        let maximum_price = read_price(buffer, version)?;
+        let near_execution_price = read_price(buffer, version)?;
+        let near_execution_time = buffer.read_u64::<NetworkEndian>()?;
+        let lower_collar = read_price(buffer, version)?;
+        let upper_collar = read_price(buffer, version)?;
+
+        // Return message
+        Ok(Self {
+            nanoseconds,
+            kind,
+            stock_locate,
+            tracking_number,
+            ticker,
+            open_eligibility,
+            minimum_price,
+            maximum_price,
+            near_execution_price,
+            near_execution_time,
+            lower_collar,
+            upper_collar,
+        })
+    }
+}
+
+impl IntoDLCRMessage for &DirectListingCapitalRaise {
+    fn into_dlcr_message(self, date: Arc<str>) -> DLCRMessage {
+        DLCRMessage {
+            date,
+            nanoseconds: self.nanoseconds,
+            kind: self.kind,
+            stock_locate: self.stock_locate,
+            tracking_number: self.tracking_number,
+            ticker: self.ticker,
+            open_eligibility: self.open_eligibility,
+            minimum_price: self.minimum_price,
+            maximum_price: self.maximum_price,
+            near_execution_price: self.near_execution_price,
+            near_execution_time: self.near_execution_time,
+            lower_collar: self.lower_collar,
+            upper_collar: self.upper_collar,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::message::test_helpers::message_builders::*;
+
+    #[test]
+    fn returns_message() {
+        let mut data = dlcr_v50(
+            1000, "SPOT", 'Y', 100_000, 200_000, 150_000, 900, 140_000, 160_000,
+        );
+        let mut context = Context::new();
+
+        let message =
+            DirectListingCapitalRaise::read(&mut data, &Version::V50, &mut context).unwrap();
+
+        assert_eq!(*message.kind(), 'O');
+        assert_eq!(*message.nanoseconds(), 1000);
+        assert_eq!(*message.ticker(), "SPOT");
+        assert_eq!(*message.open_eligibility(), 'Y');
+        assert_eq!(*message.minimum_price(), 100_000);
+        assert_eq!(*message.maximum_price(), 200_000);
+        assert_eq!(*message.near_execution_price(), 150_000);
+        assert_eq!(*message.near_execution_time(), 900);
+        assert_eq!(*message.lower_collar(), 140_000);
+        assert_eq!(*message.upper_collar(), 160_000);
+    }
+
+    #[test]
+    fn errors_for_version_41() {
+        let mut data = dlcr_v50(1000, "SPOT", 'Y', 0, 0, 0, 0, 0, 0);
+        let mut context = Context::new();
+        context.update_clock(0);
+
+        assert!(DirectListingCapitalRaise::read(&mut data, &Version::V41, &mut context).is_err());
+    }
+
+    #[test]
+    fn into_dlcr_message_conversion() {
+        let mut data = dlcr_v50(
+            1000, "SPOT", 'N', 100_000, 200_000, 150_000, 900, 140_000, 160_000,
+        );
+        let mut context = Context::new();
+        let message =
+            DirectListingCapitalRaise::read(&mut data, &Version::V50, &mut context).unwrap();
+
+        let message = message.into_dlcr_message("2018-04-03".into());
+
+        assert_eq!(&**message.date(), "2018-04-03");
+        assert_eq!(*message.ticker(), "SPOT");
+        assert_eq!(*message.open_eligibility(), 'N');
+        assert_eq!(*message.near_execution_price(), 150_000);
+        assert_eq!(*message.upper_collar(), 160_000);
+    }
+}
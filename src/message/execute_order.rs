@@ -1,18 +1,24 @@
-use std::io::{Read, Result, Seek, SeekFrom};
+use std::{
+    io::{Read, Seek},
+    sync::Arc,
+};
 
 use getset::Getters;
 
 use super::{
-    read_kind, read_nanoseconds, read_price, read_printable, read_refno, read_shares, Context,
-    IntoOrderMessage, OrderMessage, ReadMessage, Side, Version,
+    read_kind, read_locate, read_matchno, read_nanoseconds, read_price, read_printable, read_refno,
+    read_shares, Context, IntoOrderMessage, OrderMessage, ReadMessage, Side, Ticker, Version,
 };
+use crate::error::{Error, Result};
 
 #[derive(Debug, Getters)]
 #[getset(get = "pub")]
 pub struct ExecuteOrder {
     nanoseconds: u64,
     kind: char,
-    ticker: String,
+    stock_locate: Option<u16>,
+    tracking_number: Option<u16>,
+    ticker: Ticker,
     side: Side,
     price: u32,
     shares: u32,
@@ -28,16 +34,14 @@ impl ReadMessage for ExecuteOrder {
     {
         // Read data from buffer
         let kind = read_kind(buffer)?;
-        if version == &Version::V50 {
-            buffer.seek(SeekFrom::Current(4))?; // Discard stock locate and tracking number
-        }
+        let (stock_locate, tracking_number) = read_locate(buffer, version)?;
         let nanoseconds = read_nanoseconds(buffer, version, context.clock)?;
-        let refno = read_refno(buffer)?;
-        let shares = read_shares(buffer)?;
-        buffer.seek(SeekFrom::Current(8))?; // Discard match number
+        let refno = read_refno(buffer, version)?;
+        let shares = read_shares(buffer, version)?;
+        let _matchno = read_matchno(buffer, version)?; // Discard match number
         let (printable, execution_price) = if kind == 'C' {
             let printable = Some(read_printable(buffer)?);
-            let execution_price = Some(read_price(buffer)?);
+            let execution_price = Some(read_price(buffer, version)?);
             (printable, execution_price)
         } else {
             (None, None)
@@ -47,14 +51,23 @@ impl ReadMessage for ExecuteOrder {
         let order = context
             .active_orders
             .get_mut(&refno)
-            .expect("Order not found");
-        order.shares -= shares;
+            .ok_or(Error::UnknownRefno(refno))?;
+        order.shares = order
+            .shares
+            .checked_sub(shares)
+            .ok_or(Error::OrderUnderflow {
+                refno,
+                shares,
+                remaining: order.shares,
+            })?;
 
         // Return message
         Ok(Self {
             nanoseconds,
             kind,
-            ticker: order.ticker.clone(),
+            stock_locate,
+            tracking_number,
+            ticker: order.ticker,
             side: order.side,
             price: order.price,
             shares,
@@ -65,12 +78,14 @@ impl ReadMessage for ExecuteOrder {
     }
 }
 
-impl IntoOrderMessage for ExecuteOrder {
-    fn into_order_message(self, date: String) -> OrderMessage {
+impl IntoOrderMessage for &ExecuteOrder {
+    fn into_order_message(self, date: Arc<str>) -> OrderMessage {
         OrderMessage {
             date,
             nanoseconds: self.nanoseconds,
             kind: self.kind,
+            stock_locate: self.stock_locate,
+            tracking_number: self.tracking_number,
             ticker: self.ticker,
             side: self.side,
             price: self.price,
@@ -80,6 +95,7 @@ impl IntoOrderMessage for ExecuteOrder {
             mpid: None,
             printable: self.printable,
             execution_price: self.execution_price,
+            short_sale_restricted: None,
         }
     }
 }
@@ -97,7 +113,7 @@ mod tests {
         context.active_orders.insert(
             55555,
             OrderState {
-                ticker: "INTC".to_string(),
+                ticker: "INTC".parse().unwrap(),
                 side: Side::Buy,
                 price: 5500,
                 shares: 200,
@@ -128,7 +144,7 @@ mod tests {
         context.active_orders.insert(
             33333,
             OrderState {
-                ticker: "ORCL".to_string(),
+                ticker: "ORCL".parse().unwrap(),
                 side: Side::Sell,
                 price: 8200,
                 shares: 300,
@@ -151,6 +167,30 @@ mod tests {
         assert_eq!(order.shares, 225); // 300 - 75
     }
 
+    #[test]
+    fn returns_message_and_updates_shares_v30() {
+        let mut data = execute_order_v30(500, 33333, 75, 987654321);
+        let mut context = Context::new();
+        context.update_clock(10);
+        context.active_orders.insert(
+            33333,
+            OrderState {
+                ticker: "ORCL".parse().unwrap(),
+                side: Side::Sell,
+                price: 8200,
+                shares: 300,
+            },
+        );
+
+        let message = ExecuteOrder::read(&mut data, &Version::V30, &mut context).unwrap();
+
+        assert_eq!(*message.kind(), 'E');
+        assert_eq!(*message.nanoseconds(), 10_500_000_000);
+        assert_eq!(*message.refno(), 33333);
+        assert_eq!(*message.shares(), 75);
+        assert_eq!(context.active_orders[&33333].shares, 225);
+    }
+
     #[test]
     fn handles_execute_with_price_message() {
         let mut data = execute_order_with_price_v41(6000, 77777, 100, true, 15500);
@@ -159,7 +199,7 @@ mod tests {
         context.active_orders.insert(
             77777,
             OrderState {
-                ticker: "NFLX".to_string(),
+                ticker: "NFLX".parse().unwrap(),
                 side: Side::Buy,
                 price: 15000,
                 shares: 250,
@@ -190,7 +230,7 @@ mod tests {
         context.active_orders.insert(
             88888,
             OrderState {
-                ticker: "UBER".to_string(),
+                ticker: "UBER".parse().unwrap(),
                 side: Side::Sell,
                 price: 22000,
                 shares: 100,
@@ -208,13 +248,13 @@ mod tests {
     }
 
     #[test]
-    #[should_panic(expected = "Order not found")]
-    fn panics_when_order_not_found() {
+    fn unknown_order_is_an_error() {
         let mut data = execute_order_v50(1000, 99999, 50);
         let mut context = Context::new();
         context.update_clock(0);
 
-        ExecuteOrder::read(&mut data, &Version::V50, &mut context).unwrap();
+        let err = ExecuteOrder::read(&mut data, &Version::V50, &mut context).unwrap_err();
+        assert!(matches!(err, Error::UnknownRefno(99999)));
     }
 
     #[test]
@@ -222,7 +262,9 @@ mod tests {
         let execute_order = ExecuteOrder {
             nanoseconds: 8000,
             kind: 'C',
-            ticker: "SNAP".to_string(),
+            stock_locate: None,
+            tracking_number: None,
+            ticker: "SNAP".parse().unwrap(),
             side: Side::Buy,
             price: 1200,
             shares: 40,
@@ -231,9 +273,9 @@ mod tests {
             execution_price: Some(1250),
         };
 
-        let order_message = execute_order.into_order_message("2024-03-15".to_string());
+        let order_message = execute_order.into_order_message("2024-03-15".into());
 
-        assert_eq!(*order_message.date(), "2024-03-15");
+        assert_eq!(&**order_message.date(), "2024-03-15");
         assert_eq!(order_message.nanoseconds, 8000);
         assert_eq!(order_message.kind, 'C');
         assert_eq!(order_message.ticker, "SNAP");
@@ -255,7 +297,7 @@ mod tests {
         context.active_orders.insert(
             12321,
             OrderState {
-                ticker: "SPOT".to_string(),
+                ticker: "SPOT".parse().unwrap(),
                 side: Side::Buy,
                 price: 18000,
                 shares: 400,
@@ -0,0 +1,118 @@
+use std::{
+    io::{Read, Seek},
+    sync::Arc,
+};
+
+use byteorder::{NetworkEndian, ReadBytesExt};
+use getset::Getters;
+
+use super::{
+    read_kind, read_locate, read_nanoseconds, read_price, read_ticker, require_v50, Context,
+    IPOQuotingMessage, IntoIPOQuotingMessage, ReadMessage, Ticker, Version,
+};
+use crate::error::Result;
+
+// IPO quoting period update messages (Version 5.0 only) announce the anticipated quotation release
+// time of an IPO security, or that the release has been canceled or postponed.
+#[derive(Debug, Getters)]
+#[getset(get = "pub")]
+pub struct IPOQuotingPeriod {
+    nanoseconds: u64,
+    kind: char,
+    stock_locate: Option<u16>,
+    tracking_number: Option<u16>,
+    ticker: Ticker,
+    release_time: u32,       // Seconds since midnight
+    release_qualifier: char, // A = anticipated, C = canceled or postponed
+    ipo_price: u32,
+}
+
+impl ReadMessage for IPOQuotingPeriod {
+    fn read<T>(buffer: &mut T, version: &Version, context: &mut Context) -> Result<Self>
+    where
+        T: Read + Seek,
+    {
+        require_v50(version, 'K')?;
+
+        // Read data from buffer
+        let kind = read_kind(buffer)?;
+        let (stock_locate, tracking_number) = read_locate(buffer, version)?;
+        let nanoseconds = read_nanoseconds(buffer, version, context.clock)?;
+        let ticker = read_ticker(buffer, version)?;
+        let release_time = buffer.read_u32::<NetworkEndian>()?;
+        let release_qualifier = buffer.read_u8().map(char::from)?;
+        let ipo_price = read_price(buffer, version)?;
+
+        // Return message
+        Ok(Self {
+            nanoseconds,
+            kind,
+            stock_locate,
+            tracking_number,
+            ticker,
+            release_time,
+            release_qualifier,
+            ipo_price,
+        })
+    }
+}
+
+impl IntoIPOQuotingMessage for &IPOQuotingPeriod {
+    fn into_ipo_quoting_message(self, date: Arc<str>) -> IPOQuotingMessage {
+        IPOQuotingMessage {
+            date,
+            nanoseconds: self.nanoseconds,
+            kind: self.kind,
+            stock_locate: self.stock_locate,
+            tracking_number: self.tracking_number,
+            ticker: self.ticker,
+            release_time: self.release_time,
+            release_qualifier: self.release_qualifier,
+            ipo_price: self.ipo_price,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::message::test_helpers::message_builders::*;
+
+    #[test]
+    fn returns_message() {
+        let mut data = ipo_quoting_period_v50(1000, "ABNB", 43_200, 'A', 680_000);
+        let mut context = Context::new();
+
+        let message = IPOQuotingPeriod::read(&mut data, &Version::V50, &mut context).unwrap();
+
+        assert_eq!(*message.kind(), 'K');
+        assert_eq!(*message.nanoseconds(), 1000);
+        assert_eq!(*message.ticker(), "ABNB");
+        assert_eq!(*message.release_time(), 43_200);
+        assert_eq!(*message.release_qualifier(), 'A');
+        assert_eq!(*message.ipo_price(), 680_000);
+    }
+
+    #[test]
+    fn errors_for_version_41() {
+        let mut data = ipo_quoting_period_v50(1000, "ABNB", 43_200, 'A', 680_000);
+        let mut context = Context::new();
+        context.update_clock(0);
+
+        assert!(IPOQuotingPeriod::read(&mut data, &Version::V41, &mut context).is_err());
+    }
+
+    #[test]
+    fn into_ipo_quoting_message_conversion() {
+        let mut data = ipo_quoting_period_v50(1000, "ABNB", 43_200, 'C', 680_000);
+        let mut context = Context::new();
+        let message = IPOQuotingPeriod::read(&mut data, &Version::V50, &mut context).unwrap();
+
+        let message = message.into_ipo_quoting_message("2020-12-10".into());
+
+        assert_eq!(&**message.date(), "2020-12-10");
+        assert_eq!(*message.ticker(), "ABNB");
+        assert_eq!(*message.release_qualifier(), 'C');
+        assert_eq!(*message.ipo_price(), 680_000);
+    }
+}
@@ -0,0 +1,114 @@
+use std::{
+    io::{Read, Seek},
+    sync::Arc,
+};
+
+use byteorder::{NetworkEndian, ReadBytesExt};
+use getset::Getters;
+
+use super::{
+    read_kind, read_locate, read_nanoseconds, read_price, read_ticker, require_v50, Context,
+    IntoLULDCollarMessage, LULDCollarMessage, ReadMessage, Ticker, Version,
+};
+use crate::error::Result;
+
+// Limit up/limit down (LULD) auction collar messages (Version 5.0 only) indicate the price bounds
+// of the reopening auction that follows a LULD trading pause.
+#[derive(Debug, Getters)]
+#[getset(get = "pub")]
+pub struct LULDAuctionCollar {
+    nanoseconds: u64,
+    kind: char,
+    stock_locate: Option<u16>,
+    tracking_number: Option<u16>,
+    ticker: Ticker,
+    reference_price: u32,
+    upper_price: u32,
+    lower_price: u32,
+    extension: u32, // Number of extensions of the reopening auction
+}
+
+impl ReadMessage for LULDAuctionCollar {
+    fn read<T>(buffer: &mut T, version: &Version, context: &mut Context) -> Result<Self>
+    where
+        T: Read + Seek,
+    {
+        require_v50(version, 'J')?;
+
+        // Read data from buffer
+        let kind = read_kind(buffer)?;
+        let (stock_locate, tracking_number) = read_locate(buffer, version)?;
+        let nanoseconds = read_nanoseconds(buffer, version, context.clock)?;
+        let ticker = read_ticker(buffer, version)?;
+        let reference_price = read_price(buffer, version)?;
+        let upper_price = read_price(buffer, version)?;
+        let lower_price = read_price(buffer, version)?;
+        let extension = buffer.read_u32::<NetworkEndian>()?;
+
+        // Return message
+        Ok(Self {
+            nanoseconds,
+            kind,
+            stock_locate,
+            tracking_number,
+            ticker,
+            reference_price,
+            upper_price,
+            lower_price,
+            extension,
+        })
+    }
+}
+
+impl IntoLULDCollarMessage for &LULDAuctionCollar {
+    fn into_luld_collar_message(self, date: Arc<str>) -> LULDCollarMessage {
+        LULDCollarMessage {
+            date,
+            nanoseconds: self.nanoseconds,
+            kind: self.kind,
+            stock_locate: self.stock_locate,
+            tracking_number: self.tracking_number,
+            ticker: self.ticker,
+            reference_price: self.reference_price,
+            upper_price: self.upper_price,
+            lower_price: self.lower_price,
+            extension: self.extension,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::message::test_helpers::message_builders::*;
+
+    #[test]
+    fn returns_message() {
+        let mut data = luld_auction_collar_v50(2000, "GME", 1_000_000, 1_100_000, 900_000, 1);
+        let mut context = Context::new();
+
+        let message = LULDAuctionCollar::read(&mut data, &Version::V50, &mut context).unwrap();
+
+        assert_eq!(*message.kind(), 'J');
+        assert_eq!(*message.nanoseconds(), 2000);
+        assert_eq!(*message.ticker(), "GME");
+        assert_eq!(*message.reference_price(), 1_000_000);
+        assert_eq!(*message.upper_price(), 1_100_000);
+        assert_eq!(*message.lower_price(), 900_000);
+        assert_eq!(*message.extension(), 1);
+    }
+
+    #[test]
+    fn into_luld_collar_message_conversion() {
+        let mut data = luld_auction_collar_v50(2000, "GME", 1_000_000, 1_100_000, 900_000, 0);
+        let mut context = Context::new();
+        let message = LULDAuctionCollar::read(&mut data, &Version::V50, &mut context).unwrap();
+
+        let message = message.into_luld_collar_message("2021-01-27".into());
+
+        assert_eq!(&**message.date(), "2021-01-27");
+        assert_eq!(*message.ticker(), "GME");
+        assert_eq!(*message.upper_price(), 1_100_000);
+        assert_eq!(*message.extension(), 0);
+    }
+}
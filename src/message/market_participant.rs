@@ -0,0 +1,168 @@
+use std::{
+    io::{Read, Seek},
+    sync::Arc,
+};
+
+use byteorder::ReadBytesExt;
+use getset::Getters;
+
+use super::{
+    read_kind, read_locate, read_mpid, read_nanoseconds, read_ticker, Context,
+    IntoMarketParticipantMessage, MarketParticipantMessage, ReadMessage, Ticker, Version,
+};
+use crate::error::Result;
+
+// Market participant position messages describe the registration of a market participant (MPID)
+// in a security. Nasdaq sends a message for every registered market maker at the start of the day,
+// and another whenever a registration changes intraday.
+#[derive(Debug, Clone, Getters)]
+#[getset(get = "pub")]
+pub struct MarketParticipantPosition {
+    nanoseconds: u64,
+    kind: char,
+    stock_locate: Option<u16>,
+    tracking_number: Option<u16>,
+    mpid: String,
+    ticker: Ticker,
+    primary_market_maker: bool,
+    market_maker_mode: char, // N = normal, P = passive, S = syndicate, R = pre-syndicate, L = penalty
+    participant_state: char, // A = active, E = excused, W = withdrawn, S = suspended, D = deleted
+}
+
+impl ReadMessage for MarketParticipantPosition {
+    fn read<T>(buffer: &mut T, version: &Version, context: &mut Context) -> Result<Self>
+    where
+        T: Read + Seek,
+    {
+        // Read data from buffer
+        let kind = read_kind(buffer)?;
+        let (stock_locate, tracking_number) = read_locate(buffer, version)?;
+        let nanoseconds = read_nanoseconds(buffer, version, context.clock)?;
+        let mpid = read_mpid(buffer)?;
+        let ticker = read_ticker(buffer, version)?;
+        let primary_market_maker = buffer.read_u8().map(char::from)? == 'Y';
+        let market_maker_mode = buffer.read_u8().map(char::from)?;
+        let participant_state = buffer.read_u8().map(char::from)?;
+
+        let message = Self {
+            nanoseconds,
+            kind,
+            stock_locate,
+            tracking_number,
+            mpid,
+            ticker,
+            primary_market_maker,
+            market_maker_mode,
+            participant_state,
+        };
+
+        // Update context
+        context
+            .participants
+            .insert((message.mpid.clone(), message.ticker), message.clone());
+
+        // Return message
+        Ok(message)
+    }
+}
+
+impl IntoMarketParticipantMessage for &MarketParticipantPosition {
+    fn into_market_participant_message(self, date: Arc<str>) -> MarketParticipantMessage {
+        MarketParticipantMessage {
+            date,
+            nanoseconds: self.nanoseconds,
+            kind: self.kind,
+            stock_locate: self.stock_locate,
+            tracking_number: self.tracking_number,
+            mpid: self.mpid.clone(),
+            ticker: self.ticker,
+            primary_market_maker: self.primary_market_maker,
+            mode: self.market_maker_mode,
+            state: self.participant_state,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::message::test_helpers::message_builders::*;
+
+    #[test]
+    fn returns_message_and_updates_registry_v50() {
+        let mut data = market_participant_v50(4000, "GSCO", "AAPL", true, 'N', 'A');
+        let mut context = Context::new();
+
+        let message =
+            MarketParticipantPosition::read(&mut data, &Version::V50, &mut context).unwrap();
+
+        assert_eq!(*message.kind(), 'L');
+        assert_eq!(*message.nanoseconds(), 4000);
+        assert_eq!(*message.mpid(), "GSCO");
+        assert_eq!(*message.ticker(), "AAPL");
+        assert!(message.primary_market_maker());
+        assert_eq!(*message.market_maker_mode(), 'N');
+        assert_eq!(*message.participant_state(), 'A');
+
+        let key = ("GSCO".to_string(), ticker("AAPL"));
+        assert_eq!(*context.participants[&key].participant_state(), 'A');
+    }
+
+    #[test]
+    fn returns_message_and_updates_registry_v41() {
+        let mut data = market_participant_v41(100, "MSCO", "MSFT", false, 'P', 'E');
+        let mut context = Context::new();
+        context.update_clock(1);
+
+        let message =
+            MarketParticipantPosition::read(&mut data, &Version::V41, &mut context).unwrap();
+
+        assert_eq!(*message.nanoseconds(), 1_000_000_100);
+        assert_eq!(*message.mpid(), "MSCO");
+        assert!(!message.primary_market_maker());
+        assert_eq!(*message.market_maker_mode(), 'P');
+
+        let key = ("MSCO".to_string(), ticker("MSFT"));
+        assert!(context.participants.contains_key(&key));
+    }
+
+    #[test]
+    fn registry_is_keyed_by_mpid_and_ticker() {
+        let mut first = market_participant_v50(0, "GSCO", "AAPL", true, 'N', 'A');
+        let mut second = market_participant_v50(0, "GSCO", "MSFT", false, 'N', 'A');
+        let mut third = market_participant_v50(0, "GSCO", "AAPL", true, 'N', 'W');
+        let mut context = Context::new();
+
+        MarketParticipantPosition::read(&mut first, &Version::V50, &mut context).unwrap();
+        MarketParticipantPosition::read(&mut second, &Version::V50, &mut context).unwrap();
+        MarketParticipantPosition::read(&mut third, &Version::V50, &mut context).unwrap();
+
+        assert_eq!(context.participants.len(), 2);
+        let key = ("GSCO".to_string(), ticker("AAPL"));
+        assert_eq!(*context.participants[&key].participant_state(), 'W');
+    }
+
+    #[test]
+    fn into_market_participant_message_conversion() {
+        let position = MarketParticipantPosition {
+            nanoseconds: 10,
+            kind: 'L',
+            stock_locate: None,
+            tracking_number: None,
+            mpid: "GSCO".to_string(),
+            ticker: "AAPL".parse().unwrap(),
+            primary_market_maker: true,
+            market_maker_mode: 'N',
+            participant_state: 'A',
+        };
+
+        let message = position.into_market_participant_message("2020-03-16".into());
+
+        assert_eq!(&**message.date(), "2020-03-16");
+        assert_eq!(*message.mpid(), "GSCO");
+        assert_eq!(*message.ticker(), "AAPL");
+        assert!(message.primary_market_maker());
+        assert_eq!(*message.mode(), 'N');
+        assert_eq!(*message.state(), 'A');
+    }
+}
@@ -0,0 +1,196 @@
+use std::{
+    io::{Read, Seek},
+    sync::Arc,
+};
+
+use byteorder::{NetworkEndian, ReadBytesExt};
+use getset::Getters;
+
+use super::{
+    read_kind, read_locate, read_nanoseconds, require_v50, Context, IntoMWCBMessage, MWCBMessage,
+    ReadMessage, Version,
+};
+use crate::error::Result;
+
+// Market-wide circuit breaker (MWCB) messages are only defined for Version 5.0. Decline level
+// messages announce the S&P 500 levels (8 implied decimal places) of the three breaker levels at
+// the start of the day, and status messages indicate that one of the levels has been breached.
+#[derive(Debug, Getters)]
+#[getset(get = "pub")]
+pub struct MWCBDeclineLevel {
+    nanoseconds: u64,
+    kind: char,
+    stock_locate: Option<u16>,
+    tracking_number: Option<u16>,
+    level1: u64,
+    level2: u64,
+    level3: u64,
+}
+
+#[derive(Debug, Getters)]
+#[getset(get = "pub")]
+pub struct MWCBStatus {
+    nanoseconds: u64,
+    kind: char,
+    stock_locate: Option<u16>,
+    tracking_number: Option<u16>,
+    breached_level: char, // 1, 2 or 3
+}
+
+impl ReadMessage for MWCBDeclineLevel {
+    fn read<T>(buffer: &mut T, version: &Version, context: &mut Context) -> Result<Self>
+    where
+        T: Read + Seek,
+    {
+        require_v50(version, 'V')?;
+
+        // Read data from buffer
+        let kind = read_kind(buffer)?;
+        let (stock_locate, tracking_number) = read_locate(buffer, version)?;
+        let nanoseconds = read_nanoseconds(buffer, version, context.clock)?;
+        let level1 = buffer.read_u64::<NetworkEndian>()?;
+        let level2 = buffer.read_u64::<NetworkEndian>()?;
+        let level3 = buffer.read_u64::<NetworkEndian>()?;
+
+        // Return message
+        Ok(Self {
+            nanoseconds,
+            kind,
+            stock_locate,
+            tracking_number,
+            level1,
+            level2,
+            level3,
+        })
+    }
+}
+
+impl ReadMessage for MWCBStatus {
+    fn read<T>(buffer: &mut T, version: &Version, context: &mut Context) -> Result<Self>
+    where
+        T: Read + Seek,
+    {
+        require_v50(version, 'W')?;
+
+        // Read data from buffer
+        let kind = read_kind(buffer)?;
+        let (stock_locate, tracking_number) = read_locate(buffer, version)?;
+        let nanoseconds = read_nanoseconds(buffer, version, context.clock)?;
+        let breached_level = buffer.read_u8().map(char::from)?;
+
+        // Return message
+        Ok(Self {
+            nanoseconds,
+            kind,
+            stock_locate,
+            tracking_number,
+            breached_level,
+        })
+    }
+}
+
+impl IntoMWCBMessage for &MWCBDeclineLevel {
+    fn into_mwcb_message(self, date: Arc<str>) -> MWCBMessage {
+        MWCBMessage {
+            date,
+            nanoseconds: self.nanoseconds,
+            kind: self.kind,
+            stock_locate: self.stock_locate,
+            tracking_number: self.tracking_number,
+            level1: Some(self.level1),
+            level2: Some(self.level2),
+            level3: Some(self.level3),
+            breached_level: None,
+        }
+    }
+}
+
+impl IntoMWCBMessage for &MWCBStatus {
+    fn into_mwcb_message(self, date: Arc<str>) -> MWCBMessage {
+        MWCBMessage {
+            date,
+            nanoseconds: self.nanoseconds,
+            kind: self.kind,
+            stock_locate: self.stock_locate,
+            tracking_number: self.tracking_number,
+            level1: None,
+            level2: None,
+            level3: None,
+            breached_level: Some(self.breached_level),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::message::test_helpers::message_builders::*;
+
+    #[test]
+    fn returns_decline_level_message() {
+        let mut data = mwcb_decline_level_v50(
+            1000,
+            2_386_000_000_000,
+            2_208_000_000_000,
+            2_032_000_000_000,
+        );
+        let mut context = Context::new();
+
+        let message = MWCBDeclineLevel::read(&mut data, &Version::V50, &mut context).unwrap();
+
+        assert_eq!(*message.kind(), 'V');
+        assert_eq!(*message.nanoseconds(), 1000);
+        assert_eq!(*message.level1(), 2_386_000_000_000);
+        assert_eq!(*message.level2(), 2_208_000_000_000);
+        assert_eq!(*message.level3(), 2_032_000_000_000);
+    }
+
+    #[test]
+    fn returns_status_message() {
+        let mut data = mwcb_status_v50(2000, '1');
+        let mut context = Context::new();
+
+        let message = MWCBStatus::read(&mut data, &Version::V50, &mut context).unwrap();
+
+        assert_eq!(*message.kind(), 'W');
+        assert_eq!(*message.nanoseconds(), 2000);
+        assert_eq!(*message.breached_level(), '1');
+    }
+
+    #[test]
+    fn errors_for_version_41() {
+        let mut data = mwcb_status_v50(2000, '1');
+        let mut context = Context::new();
+        context.update_clock(0);
+
+        assert!(MWCBStatus::read(&mut data, &Version::V41, &mut context).is_err());
+    }
+
+    #[test]
+    fn into_mwcb_message_conversion() {
+        let decline_level = MWCBDeclineLevel {
+            nanoseconds: 10,
+            kind: 'V',
+            stock_locate: None,
+            tracking_number: None,
+            level1: 1,
+            level2: 2,
+            level3: 3,
+        };
+        let status = MWCBStatus {
+            nanoseconds: 20,
+            kind: 'W',
+            stock_locate: None,
+            tracking_number: None,
+            breached_level: '2',
+        };
+
+        let decline_level = decline_level.into_mwcb_message("2020-03-09".into());
+        let status = status.into_mwcb_message("2020-03-09".into());
+
+        assert_eq!(*decline_level.level1(), Some(1));
+        assert_eq!(*decline_level.breached_level(), None);
+        assert_eq!(*status.level1(), None);
+        assert_eq!(*status.breached_level(), Some('2'));
+    }
+}
@@ -1,22 +1,28 @@
-use std::io::{Read, Result, Seek, SeekFrom};
+use std::{
+    io::{Read, Seek},
+    sync::Arc,
+};
 
-use byteorder::{NetworkEndian, ReadBytesExt};
+use byteorder::ReadBytesExt;
 use getset::Getters;
 
 use super::{
-    read_kind, read_nanoseconds, read_price, read_ticker, Context, IntoNOIIMessage, NOIIMessage,
-    ReadMessage, Version,
+    read_kind, read_locate, read_long_shares, read_nanoseconds, read_price, read_ticker, Context,
+    IntoNOIIMessage, NOIIMessage, ReadMessage, Ticker, Version,
 };
+use crate::error::Result;
 
 #[derive(Debug, Getters)]
 #[getset(get = "pub")]
 pub struct NetOrderImbalanceIndicator {
     nanoseconds: u64,
     kind: char,
+    stock_locate: Option<u16>,
+    tracking_number: Option<u16>,
     paired_shares: u64,
     imbalance_shares: u64,
     imbalance_direction: char,
-    ticker: String,
+    ticker: Ticker,
     far_price: u32,
     near_price: u32,
     current_reference_price: u32,
@@ -30,23 +36,23 @@ impl ReadMessage for NetOrderImbalanceIndicator {
         T: Read + Seek,
     {
         let kind = read_kind(buffer)?;
-        if version == &Version::V50 {
-            buffer.seek(SeekFrom::Current(4))?; // Discard stock locate and tracking number
-        }
+        let (stock_locate, tracking_number) = read_locate(buffer, version)?;
         let nanoseconds = read_nanoseconds(buffer, version, context.clock)?;
-        let paired_shares = buffer.read_u64::<NetworkEndian>()?;
-        let imbalance_shares = buffer.read_u64::<NetworkEndian>()?;
+        let paired_shares = read_long_shares(buffer, version)?;
+        let imbalance_shares = read_long_shares(buffer, version)?;
         let imbalance_direction = buffer.read_u8().map(char::from)?;
-        let ticker = read_ticker(buffer)?;
-        let far_price = read_price(buffer)?;
-        let near_price = read_price(buffer)?;
-        let current_reference_price = read_price(buffer)?;
+        let ticker = read_ticker(buffer, version)?;
+        let far_price = read_price(buffer, version)?;
+        let near_price = read_price(buffer, version)?;
+        let current_reference_price = read_price(buffer, version)?;
         let cross_type = buffer.read_u8().map(char::from)?;
         let price_variation_indicator = buffer.read_u8().map(char::from)?;
 
         Ok(Self {
             nanoseconds,
             kind,
+            stock_locate,
+            tracking_number,
             paired_shares,
             imbalance_shares,
             imbalance_direction,
@@ -60,12 +66,14 @@ impl ReadMessage for NetOrderImbalanceIndicator {
     }
 }
 
-impl IntoNOIIMessage for NetOrderImbalanceIndicator {
-    fn into_noii_message(self, date: String) -> NOIIMessage {
+impl IntoNOIIMessage for &NetOrderImbalanceIndicator {
+    fn into_noii_message(self, date: Arc<str>) -> NOIIMessage {
         NOIIMessage {
             date,
             nanoseconds: self.nanoseconds,
             kind: self.kind,
+            stock_locate: self.stock_locate,
+            tracking_number: self.tracking_number,
             ticker: self.ticker,
             paired_shares: self.paired_shares,
             imbalance_shares: self.imbalance_shares,
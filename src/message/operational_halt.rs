@@ -0,0 +1,103 @@
+use std::{
+    io::{Read, Seek},
+    sync::Arc,
+};
+
+use byteorder::ReadBytesExt;
+use getset::Getters;
+
+use super::{
+    read_kind, read_locate, read_nanoseconds, read_ticker, require_v50, Context,
+    IntoOperationalHaltMessage, OperationalHaltMessage, ReadMessage, Ticker, Version,
+};
+use crate::error::Result;
+
+// Operational halt messages (Version 5.0 only) indicate that trading in a security has been halted
+// or resumed on a specific Nasdaq market center for operational reasons.
+#[derive(Debug, Getters)]
+#[getset(get = "pub")]
+pub struct OperationalHalt {
+    nanoseconds: u64,
+    kind: char,
+    stock_locate: Option<u16>,
+    tracking_number: Option<u16>,
+    ticker: Ticker,
+    market_code: char, // Q = Nasdaq, B = BX, X = PSX
+    action: char,      // H = halted, T = resumed
+}
+
+impl ReadMessage for OperationalHalt {
+    fn read<T>(buffer: &mut T, version: &Version, context: &mut Context) -> Result<Self>
+    where
+        T: Read + Seek,
+    {
+        require_v50(version, 'h')?;
+
+        // Read data from buffer
+        let kind = read_kind(buffer)?;
+        let (stock_locate, tracking_number) = read_locate(buffer, version)?;
+        let nanoseconds = read_nanoseconds(buffer, version, context.clock)?;
+        let ticker = read_ticker(buffer, version)?;
+        let market_code = buffer.read_u8().map(char::from)?;
+        let action = buffer.read_u8().map(char::from)?;
+
+        // Return message
+        Ok(Self {
+            nanoseconds,
+            kind,
+            stock_locate,
+            tracking_number,
+            ticker,
+            market_code,
+            action,
+        })
+    }
+}
+
+impl IntoOperationalHaltMessage for &OperationalHalt {
+    fn into_operational_halt_message(self, date: Arc<str>) -> OperationalHaltMessage {
+        OperationalHaltMessage {
+            date,
+            nanoseconds: self.nanoseconds,
+            kind: self.kind,
+            stock_locate: self.stock_locate,
+            tracking_number: self.tracking_number,
+            ticker: self.ticker,
+            market_code: self.market_code,
+            action: self.action,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::message::test_helpers::message_builders::*;
+
+    #[test]
+    fn returns_message() {
+        let mut data = operational_halt_v50(3000, "AAPL", 'Q', 'H');
+        let mut context = Context::new();
+
+        let message = OperationalHalt::read(&mut data, &Version::V50, &mut context).unwrap();
+
+        assert_eq!(*message.kind(), 'h');
+        assert_eq!(*message.nanoseconds(), 3000);
+        assert_eq!(*message.ticker(), "AAPL");
+        assert_eq!(*message.market_code(), 'Q');
+        assert_eq!(*message.action(), 'H');
+    }
+
+    #[test]
+    fn into_operational_halt_message_conversion() {
+        let mut data = operational_halt_v50(3000, "AAPL", 'B', 'T');
+        let mut context = Context::new();
+        let message = OperationalHalt::read(&mut data, &Version::V50, &mut context).unwrap();
+
+        let message = message.into_operational_halt_message("2022-06-01".into());
+
+        assert_eq!(&**message.date(), "2022-06-01");
+        assert_eq!(*message.market_code(), 'B');
+        assert_eq!(*message.action(), 'T');
+    }
+}
@@ -0,0 +1,91 @@
+use std::io::{Read, Seek};
+
+use getset::Getters;
+
+use super::{
+    read_kind, read_locate, read_nanoseconds, read_reg_sho_action, read_ticker, Context,
+    ReadMessage, RegSHOAction, Ticker, Version,
+};
+use crate::error::Result;
+
+// Reg SHO short sale price test restriction messages indicate whether the alternative uptick rule
+// (SEC Rule 201) is in effect for a security. Nasdaq sends a message for every security at the
+// start of the day, and another whenever the restriction is triggered or lifted intraday.
+#[derive(Debug, Getters)]
+#[getset(get = "pub")]
+pub struct RegSHORestriction {
+    nanoseconds: u64,
+    kind: char,
+    stock_locate: Option<u16>,
+    tracking_number: Option<u16>,
+    ticker: Ticker,
+    action: RegSHOAction,
+}
+
+impl ReadMessage for RegSHORestriction {
+    fn read<T>(buffer: &mut T, version: &Version, context: &mut Context) -> Result<Self>
+    where
+        T: Read + Seek,
+    {
+        // Read data from buffer
+        let kind = read_kind(buffer)?;
+        let (stock_locate, tracking_number) = read_locate(buffer, version)?;
+        let nanoseconds = read_nanoseconds(buffer, version, context.clock)?;
+        let ticker = read_ticker(buffer, version)?;
+        let action = read_reg_sho_action(buffer)?;
+
+        // Update context
+        context.reg_sho_actions.insert(ticker, action);
+
+        // Return message
+        Ok(Self {
+            nanoseconds,
+            kind,
+            stock_locate,
+            tracking_number,
+            ticker,
+            action,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::message::test_helpers::message_builders::*;
+
+    #[test]
+    fn returns_message_and_updates_context_v50() {
+        let mut data = reg_sho_v50(9000, "GME", '1');
+        let mut context = Context::new();
+
+        let message = RegSHORestriction::read(&mut data, &Version::V50, &mut context).unwrap();
+
+        assert_eq!(*message.kind(), 'Y');
+        assert_eq!(*message.nanoseconds(), 9000);
+        assert_eq!(*message.ticker(), "GME");
+        assert_eq!(*message.action(), RegSHOAction::Intraday);
+        assert!(message.action().is_restricted());
+        assert_eq!(
+            context.reg_sho_actions[&ticker("GME")],
+            RegSHOAction::Intraday
+        );
+    }
+
+    #[test]
+    fn returns_message_and_updates_context_v41() {
+        let mut data = reg_sho_v41(100, "AMC", '0');
+        let mut context = Context::new();
+        context.update_clock(3);
+
+        let message = RegSHORestriction::read(&mut data, &Version::V41, &mut context).unwrap();
+
+        assert_eq!(*message.nanoseconds(), 3_000_000_100);
+        assert_eq!(*message.action(), RegSHOAction::NoPriceTest);
+        assert!(!message.action().is_restricted());
+        assert_eq!(
+            context.reg_sho_actions[&ticker("AMC")],
+            RegSHOAction::NoPriceTest
+        );
+    }
+}
@@ -1,9 +1,10 @@
-use std::io::{Read, Result, Seek, SeekFrom};
+use std::io::{Read, Seek};
 
 use super::{
-    read_kind, read_nanoseconds, read_price, read_refno, read_shares, AddOrder, Context,
-    DeleteOrder, Version,
+    read_kind, read_locate, read_nanoseconds, read_price, read_refno, read_shares, AddOrder,
+    Context, DeleteOrder, Version,
 };
+use crate::error::{Error, Result};
 
 pub(crate) fn read_replace_order<T>(
     buffer: &mut T,
@@ -15,21 +16,19 @@ where
 {
     // Read data from buffer
     let _kind = read_kind(buffer)?;
-    if version == &Version::V50 {
-        buffer.seek(SeekFrom::Current(4))?; // Discard stock locate and tracking number
-    }
+    let (stock_locate, tracking_number) = read_locate(buffer, version)?;
     let nanoseconds = read_nanoseconds(buffer, version, context.clock)?;
-    let old_refno = read_refno(buffer)?;
-    let new_refno = read_refno(buffer)?;
-    let new_shares = read_shares(buffer)?;
-    let new_price = read_price(buffer)?;
+    let old_refno = read_refno(buffer, version)?;
+    let new_refno = read_refno(buffer, version)?;
+    let new_shares = read_shares(buffer, version)?;
+    let new_price = read_price(buffer, version)?;
 
     // Update context
     let mut order = context
         .active_orders
         .remove(&old_refno)
-        .expect("Order not found");
-    let ticker = order.ticker.clone();
+        .ok_or(Error::UnknownRefno(old_refno))?;
+    let ticker = order.ticker;
     let side = order.side;
     let old_price = order.price;
     let old_shares = order.shares;
@@ -41,24 +40,26 @@ where
     let delete_order = DeleteOrder::new(
         nanoseconds,
         'D', // `kind`
-        ticker.clone(),
+        ticker,
         side,
         old_price,
         old_shares,
         old_refno,
         Some(true), // `from_replace`
-    );
+    )
+    .with_locate(stock_locate, tracking_number);
     let add_order = AddOrder::new(
         nanoseconds,
         'A', // `kind`
-        ticker.clone(),
+        ticker,
         side,
         new_price,
         new_shares,
         new_refno,
         Some(true), // `from_replace`
         None,       // `mpid`
-    );
+    )
+    .with_locate(stock_locate, tracking_number);
 
     // Return messages
     Ok((delete_order, add_order))
@@ -77,7 +78,7 @@ mod tests {
         context.active_orders.insert(
             44444,
             OrderState {
-                ticker: "PYPL".to_string(),
+                ticker: "PYPL".parse().unwrap(),
                 side: Side::Buy,
                 price: 25000,
                 shares: 100,
@@ -126,7 +127,7 @@ mod tests {
         context.active_orders.insert(
             11111,
             OrderState {
-                ticker: "SQ".to_string(),
+                ticker: "SQ".parse().unwrap(),
                 side: Side::Sell,
                 price: 17000,
                 shares: 120,
@@ -168,13 +169,37 @@ mod tests {
     }
 
     #[test]
-    #[should_panic(expected = "Order not found")]
-    fn panics_when_order_not_found() {
+    fn carries_locate_to_both_orders() {
+        let data = replace_order_v50(9000, 44444, 55555, 150, 28000);
+        let mut data = with_locate(data, 7, 3);
+        let mut context = Context::new();
+        context.active_orders.insert(
+            44444,
+            OrderState {
+                ticker: "PYPL".parse().unwrap(),
+                side: Side::Buy,
+                price: 25000,
+                shares: 100,
+            },
+        );
+
+        let (delete_order, add_order) =
+            read_replace_order(&mut data, &Version::V50, &mut context).unwrap();
+
+        assert_eq!(*delete_order.stock_locate(), Some(7));
+        assert_eq!(*delete_order.tracking_number(), Some(3));
+        assert_eq!(*add_order.stock_locate(), Some(7));
+        assert_eq!(*add_order.tracking_number(), Some(3));
+    }
+
+    #[test]
+    fn unknown_order_is_an_error() {
         let mut data = replace_order_v50(5000, 99999, 88888, 50, 10000);
         let mut context = Context::new();
         context.update_clock(0);
 
-        read_replace_order(&mut data, &Version::V50, &mut context).unwrap();
+        let err = read_replace_order(&mut data, &Version::V50, &mut context).unwrap_err();
+        assert!(matches!(err, Error::UnknownRefno(99999)));
     }
 
     #[test]
@@ -185,7 +210,7 @@ mod tests {
         context.active_orders.insert(
             33333,
             OrderState {
-                ticker: "ROKU".to_string(),
+                ticker: "ROKU".parse().unwrap(),
                 side: Side::Buy,
                 price: 30000,
                 shares: 175,
@@ -216,7 +241,7 @@ mod tests {
         context.active_orders.insert(
             66666,
             OrderState {
-                ticker: "TWTR".to_string(),
+                ticker: "TWTR".parse().unwrap(),
                 side: Side::Sell,
                 price: 40000,
                 shares: 250,
@@ -250,7 +275,7 @@ mod tests {
         context.active_orders.insert(
             77777,
             OrderState {
-                ticker: "DOCU".to_string(),
+                ticker: "DOCU".parse().unwrap(),
                 side: Side::Buy,
                 price: 50000,
                 shares: 125,
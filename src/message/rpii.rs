@@ -0,0 +1,108 @@
+use std::{
+    io::{Read, Seek},
+    sync::Arc,
+};
+
+use byteorder::ReadBytesExt;
+use getset::Getters;
+
+use super::{
+    read_kind, read_locate, read_nanoseconds, read_ticker, require_v50, Context, IntoRPIIMessage,
+    RPIIMessage, ReadMessage, Ticker, Version,
+};
+use crate::error::Result;
+
+// Retail price improvement indicator (RPII) messages (Version 5.0 only) indicate the presence or
+// absence of retail price improvement interest on either side of the book.
+#[derive(Debug, Getters)]
+#[getset(get = "pub")]
+pub struct RetailPriceImprovement {
+    nanoseconds: u64,
+    kind: char,
+    stock_locate: Option<u16>,
+    tracking_number: Option<u16>,
+    ticker: Ticker,
+    interest_flag: char, // B = buy side, S = sell side, A = both sides, N = none
+}
+
+impl ReadMessage for RetailPriceImprovement {
+    fn read<T>(buffer: &mut T, version: &Version, context: &mut Context) -> Result<Self>
+    where
+        T: Read + Seek,
+    {
+        require_v50(version, 'N')?;
+
+        // Read data from buffer
+        let kind = read_kind(buffer)?;
+        let (stock_locate, tracking_number) = read_locate(buffer, version)?;
+        let nanoseconds = read_nanoseconds(buffer, version, context.clock)?;
+        let ticker = read_ticker(buffer, version)?;
+        let interest_flag = buffer.read_u8().map(char::from)?;
+
+        // Return message
+        Ok(Self {
+            nanoseconds,
+            kind,
+            stock_locate,
+            tracking_number,
+            ticker,
+            interest_flag,
+        })
+    }
+}
+
+impl IntoRPIIMessage for &RetailPriceImprovement {
+    fn into_rpii_message(self, date: Arc<str>) -> RPIIMessage {
+        RPIIMessage {
+            date,
+            nanoseconds: self.nanoseconds,
+            kind: self.kind,
+            stock_locate: self.stock_locate,
+            tracking_number: self.tracking_number,
+            ticker: self.ticker,
+            interest_flag: self.interest_flag,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::message::test_helpers::message_builders::*;
+
+    #[test]
+    fn returns_message() {
+        let mut data = rpii_v50(1000, "AAPL", 'B');
+        let mut context = Context::new();
+
+        let message = RetailPriceImprovement::read(&mut data, &Version::V50, &mut context).unwrap();
+
+        assert_eq!(*message.kind(), 'N');
+        assert_eq!(*message.nanoseconds(), 1000);
+        assert_eq!(*message.ticker(), "AAPL");
+        assert_eq!(*message.interest_flag(), 'B');
+    }
+
+    #[test]
+    fn errors_for_version_41() {
+        let mut data = rpii_v50(1000, "AAPL", 'B');
+        let mut context = Context::new();
+        context.update_clock(0);
+
+        assert!(RetailPriceImprovement::read(&mut data, &Version::V41, &mut context).is_err());
+    }
+
+    #[test]
+    fn into_rpii_message_conversion() {
+        let mut data = rpii_v50(2000, "MSFT", 'A');
+        let mut context = Context::new();
+        let message = RetailPriceImprovement::read(&mut data, &Version::V50, &mut context).unwrap();
+
+        let message = message.into_rpii_message("2020-01-30".into());
+
+        assert_eq!(&**message.date(), "2020-01-30");
+        assert_eq!(*message.nanoseconds(), 2000);
+        assert_eq!(*message.ticker(), "MSFT");
+        assert_eq!(*message.interest_flag(), 'A');
+    }
+}
@@ -0,0 +1,157 @@
+use std::io::{Read, Seek};
+
+use byteorder::{NetworkEndian, ReadBytesExt};
+use getset::Getters;
+
+use super::{
+    read_kind, read_locate, read_nanoseconds, read_ticker, Context, ReadMessage, Ticker, Version,
+};
+use crate::error::Result;
+
+// Stock directory messages are disseminated at the start of each day for every security traded on
+// Nasdaq. Version 5.0 extends the message with issue classification, ETP and LULD attributes that
+// are not available in Version 4.1 (the corresponding fields are `None`).
+#[derive(Debug, Clone, Getters)]
+#[getset(get = "pub")]
+pub struct StockDirectory {
+    nanoseconds: u64,
+    kind: char,
+    stock_locate: Option<u16>,
+    tracking_number: Option<u16>,
+    ticker: Ticker,
+    market_category: char, // Q = Global Select, G = Global, S = Capital, N = NYSE, ...
+    financial_status: char, // D = deficient, E = delinquent, N = normal, ...
+    round_lot_size: u32,
+    round_lots_only: bool,
+    issue_classification: Option<char>,
+    issue_subtype: Option<String>,
+    authenticity: Option<char>,         // P = live/production, T = test
+    short_sale_threshold: Option<char>, // Y = restricted, N = not restricted, ' ' = not available
+    ipo_flag: Option<char>,
+    luld_reference_price_tier: Option<char>,
+    etp_flag: Option<char>,
+    etp_leverage_factor: Option<u32>,
+    inverse_indicator: Option<char>,
+}
+
+impl ReadMessage for StockDirectory {
+    fn read<T>(buffer: &mut T, version: &Version, context: &mut Context) -> Result<Self>
+    where
+        T: Read + Seek,
+    {
+        // Read data from buffer
+        let kind = read_kind(buffer)?;
+        let (stock_locate, tracking_number) = read_locate(buffer, version)?;
+        let nanoseconds = read_nanoseconds(buffer, version, context.clock)?;
+        let ticker = read_ticker(buffer, version)?;
+        let market_category = buffer.read_u8().map(char::from)?;
+        let financial_status = buffer.read_u8().map(char::from)?;
+        let round_lot_size = buffer.read_u32::<NetworkEndian>()?;
+        let round_lots_only = buffer.read_u8().map(char::from)? == 'Y';
+
+        let mut message = Self {
+            nanoseconds,
+            kind,
+            stock_locate,
+            tracking_number,
+            ticker,
+            market_category,
+            financial_status,
+            round_lot_size,
+            round_lots_only,
+            issue_classification: None,
+            issue_subtype: None,
+            authenticity: None,
+            short_sale_threshold: None,
+            ipo_flag: None,
+            luld_reference_price_tier: None,
+            etp_flag: None,
+            etp_leverage_factor: None,
+            inverse_indicator: None,
+        };
+
+        if version == &Version::V50 {
+            message.issue_classification = Some(buffer.read_u8().map(char::from)?);
+            let mut subtype = [0; 2];
+            buffer.read_exact(&mut subtype)?;
+            message.issue_subtype = Some(String::from_utf8_lossy(&subtype).trim().to_string());
+            message.authenticity = Some(buffer.read_u8().map(char::from)?);
+            message.short_sale_threshold = Some(buffer.read_u8().map(char::from)?);
+            message.ipo_flag = Some(buffer.read_u8().map(char::from)?);
+            message.luld_reference_price_tier = Some(buffer.read_u8().map(char::from)?);
+            message.etp_flag = Some(buffer.read_u8().map(char::from)?);
+            message.etp_leverage_factor = Some(buffer.read_u32::<NetworkEndian>()?);
+            message.inverse_indicator = Some(buffer.read_u8().map(char::from)?);
+        }
+
+        // Update context
+        context.directory.insert(message.ticker, message.clone());
+
+        // Return message
+        Ok(message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::message::test_helpers::message_builders::*;
+
+    #[test]
+    fn returns_message_and_updates_directory_v50() {
+        let mut data = stock_directory_v50(1000, "AAPL", 'Q', 'N', 100, 'C', "Z", 'Y', 'N');
+        let mut context = Context::new();
+
+        let message = StockDirectory::read(&mut data, &Version::V50, &mut context).unwrap();
+
+        assert_eq!(*message.kind(), 'R');
+        assert_eq!(*message.nanoseconds(), 1000);
+        assert_eq!(*message.ticker(), "AAPL");
+        assert_eq!(*message.market_category(), 'Q');
+        assert_eq!(*message.financial_status(), 'N');
+        assert_eq!(*message.round_lot_size(), 100);
+        assert!(!message.round_lots_only());
+        assert_eq!(*message.issue_classification(), Some('C'));
+        assert_eq!(*message.issue_subtype(), Some("Z".to_string()));
+        assert_eq!(*message.etp_flag(), Some('Y'));
+        assert_eq!(*message.inverse_indicator(), Some('N'));
+
+        let entry = &context.directory[&ticker("AAPL")];
+        assert_eq!(*entry.round_lot_size(), 100);
+        assert_eq!(*entry.etp_flag(), Some('Y'));
+    }
+
+    #[test]
+    fn returns_message_and_updates_directory_v41() {
+        let mut data = stock_directory_v41(2000, "MSFT", 'Q', 'N', 100);
+        let mut context = Context::new();
+        context.update_clock(5);
+
+        let message = StockDirectory::read(&mut data, &Version::V41, &mut context).unwrap();
+
+        assert_eq!(*message.kind(), 'R');
+        assert_eq!(*message.nanoseconds(), 5_000_002_000);
+        assert_eq!(*message.ticker(), "MSFT");
+        assert_eq!(*message.market_category(), 'Q');
+        assert_eq!(*message.round_lot_size(), 100);
+        assert_eq!(*message.issue_classification(), None);
+        assert_eq!(*message.etp_flag(), None);
+
+        assert!(context.directory.contains_key(&ticker("MSFT")));
+    }
+
+    #[test]
+    fn later_messages_replace_directory_entries() {
+        let mut first = stock_directory_v41(0, "IBM", 'N', 'N', 100);
+        let mut second = stock_directory_v41(0, "IBM", 'N', 'D', 10);
+        let mut context = Context::new();
+        context.update_clock(0);
+
+        StockDirectory::read(&mut first, &Version::V41, &mut context).unwrap();
+        StockDirectory::read(&mut second, &Version::V41, &mut context).unwrap();
+
+        assert_eq!(context.directory.len(), 1);
+        assert_eq!(*context.directory[&ticker("IBM")].financial_status(), 'D');
+        assert_eq!(*context.directory[&ticker("IBM")].round_lot_size(), 10);
+    }
+}
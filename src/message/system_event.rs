@@ -1,16 +1,20 @@
-use std::io::{Read, Result, Seek, SeekFrom};
+use std::io::{Read, Seek};
 
 use getset::Getters;
 
 use super::{
-    read_event_code, read_kind, read_nanoseconds, Context, EventCode, ReadMessage, Version,
+    read_event_code, read_kind, read_locate, read_nanoseconds, Context, EventCode, ReadMessage,
+    Version,
 };
+use crate::error::Result;
 
 #[derive(Debug, Getters)]
 #[getset(get = "pub")]
 pub struct SystemEvent {
     nanoseconds: u64,
     kind: char,
+    stock_locate: Option<u16>,
+    tracking_number: Option<u16>,
     event_code: EventCode,
 }
 
@@ -21,9 +25,7 @@ impl ReadMessage for SystemEvent {
     {
         // Read data from buffer
         let kind = read_kind(buffer)?;
-        if version == &Version::V50 {
-            buffer.seek(SeekFrom::Current(4))?; // Discard stock locate and tracking number
-        }
+        let (stock_locate, tracking_number) = read_locate(buffer, version)?;
         let nanoseconds = read_nanoseconds(buffer, version, context.clock)?;
         let event_code = read_event_code(buffer)?;
 
@@ -31,6 +33,8 @@ impl ReadMessage for SystemEvent {
         Ok(Self {
             nanoseconds,
             kind,
+            stock_locate,
+            tracking_number,
             event_code,
         })
     }
@@ -171,7 +175,7 @@ mod tests {
         context.active_orders.insert(
             12345,
             OrderState {
-                ticker: "TEST".to_string(),
+                ticker: "TEST".parse().unwrap(),
                 side: Side::Buy,
                 price: 10000,
                 shares: 100,
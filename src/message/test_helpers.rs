@@ -4,7 +4,7 @@ pub mod message_builders {
 
     use byteorder::{NetworkEndian, WriteBytesExt};
 
-    use crate::message::{OrderState, Side};
+    use crate::message::{OrderState, Side, Ticker};
 
     // Timestamp helpers
     pub fn timestamp_v41(seconds: u32) -> Cursor<Vec<u8>> {
@@ -228,6 +228,18 @@ pub mod message_builders {
         Cursor::new(data)
     }
 
+    // Trade helpers
+    pub fn broken_trade_v50(nanoseconds: u64, matchno: u64) -> Cursor<Vec<u8>> {
+        let mut data = Vec::<u8>::new();
+        data.push(b'B');
+        data.write_u16::<NetworkEndian>(0).unwrap(); // stock locate
+        data.write_u16::<NetworkEndian>(0).unwrap(); // tracking number
+        data.write_u48::<NetworkEndian>(nanoseconds).unwrap();
+        data.write_u64::<NetworkEndian>(matchno).unwrap();
+
+        Cursor::new(data)
+    }
+
     // System Event helpers
     pub fn system_event_v41(nanoseconds: u32, event_code: char) -> Cursor<Vec<u8>> {
         let mut data = Vec::<u8>::new();
@@ -249,21 +261,520 @@ pub mod message_builders {
         Cursor::new(data)
     }
 
+    // Stock Directory helpers
+    pub fn stock_directory_v41(
+        nanoseconds: u32,
+        ticker: &str,
+        market_category: char,
+        financial_status: char,
+        round_lot_size: u32,
+    ) -> Cursor<Vec<u8>> {
+        let mut data = Vec::<u8>::new();
+        data.push(b'R');
+        data.write_u32::<NetworkEndian>(nanoseconds).unwrap();
+        let mut ticker_bytes = [b' '; 8];
+        let ticker_bytes_slice = ticker.as_bytes();
+        ticker_bytes[..ticker_bytes_slice.len()].copy_from_slice(ticker_bytes_slice);
+        data.extend_from_slice(&ticker_bytes);
+        data.push(market_category as u8);
+        data.push(financial_status as u8);
+        data.write_u32::<NetworkEndian>(round_lot_size).unwrap();
+        data.push(b'N'); // round lots only
+
+        Cursor::new(data)
+    }
+
+    pub fn stock_directory_v50(
+        nanoseconds: u64,
+        ticker: &str,
+        market_category: char,
+        financial_status: char,
+        round_lot_size: u32,
+        issue_classification: char,
+        issue_subtype: &str,
+        etp_flag: char,
+        inverse_indicator: char,
+    ) -> Cursor<Vec<u8>> {
+        let mut data = Vec::<u8>::new();
+        data.push(b'R');
+        data.write_u16::<NetworkEndian>(0).unwrap(); // stock locate
+        data.write_u16::<NetworkEndian>(0).unwrap(); // tracking number
+        data.write_u48::<NetworkEndian>(nanoseconds).unwrap();
+        let mut ticker_bytes = [b' '; 8];
+        let ticker_bytes_slice = ticker.as_bytes();
+        ticker_bytes[..ticker_bytes_slice.len()].copy_from_slice(ticker_bytes_slice);
+        data.extend_from_slice(&ticker_bytes);
+        data.push(market_category as u8);
+        data.push(financial_status as u8);
+        data.write_u32::<NetworkEndian>(round_lot_size).unwrap();
+        data.push(b'N'); // round lots only
+        data.push(issue_classification as u8);
+        let mut subtype_bytes = [b' '; 2];
+        let subtype_bytes_slice = issue_subtype.as_bytes();
+        subtype_bytes[..subtype_bytes_slice.len()].copy_from_slice(subtype_bytes_slice);
+        data.extend_from_slice(&subtype_bytes);
+        data.push(b'P'); // authenticity
+        data.push(b'N'); // short sale threshold indicator
+        data.push(b'N'); // IPO flag
+        data.push(b'1'); // LULD reference price tier
+        data.push(etp_flag as u8);
+        data.write_u32::<NetworkEndian>(0).unwrap(); // ETP leverage factor
+        data.push(inverse_indicator as u8);
+
+        Cursor::new(data)
+    }
+
+    // Trading Action helpers
+    pub fn trading_action_v41(
+        nanoseconds: u32,
+        ticker: &str,
+        trading_state: char,
+        reason: &str,
+    ) -> Cursor<Vec<u8>> {
+        let mut data = Vec::<u8>::new();
+        data.push(b'H');
+        data.write_u32::<NetworkEndian>(nanoseconds).unwrap();
+        let mut ticker_bytes = [b' '; 8];
+        let ticker_bytes_slice = ticker.as_bytes();
+        ticker_bytes[..ticker_bytes_slice.len()].copy_from_slice(ticker_bytes_slice);
+        data.extend_from_slice(&ticker_bytes);
+        data.push(trading_state as u8);
+        data.push(b' '); // reserved
+        let mut reason_bytes = [b' '; 4];
+        let reason_bytes_slice = reason.as_bytes();
+        reason_bytes[..reason_bytes_slice.len()].copy_from_slice(reason_bytes_slice);
+        data.extend_from_slice(&reason_bytes);
+
+        Cursor::new(data)
+    }
+
+    pub fn trading_action_v50(
+        nanoseconds: u64,
+        ticker: &str,
+        trading_state: char,
+        reason: &str,
+    ) -> Cursor<Vec<u8>> {
+        let mut data = Vec::<u8>::new();
+        data.push(b'H');
+        data.write_u16::<NetworkEndian>(0).unwrap(); // stock locate
+        data.write_u16::<NetworkEndian>(0).unwrap(); // tracking number
+        data.write_u48::<NetworkEndian>(nanoseconds).unwrap();
+        let mut ticker_bytes = [b' '; 8];
+        let ticker_bytes_slice = ticker.as_bytes();
+        ticker_bytes[..ticker_bytes_slice.len()].copy_from_slice(ticker_bytes_slice);
+        data.extend_from_slice(&ticker_bytes);
+        data.push(trading_state as u8);
+        data.push(b' '); // reserved
+        let mut reason_bytes = [b' '; 4];
+        let reason_bytes_slice = reason.as_bytes();
+        reason_bytes[..reason_bytes_slice.len()].copy_from_slice(reason_bytes_slice);
+        data.extend_from_slice(&reason_bytes);
+
+        Cursor::new(data)
+    }
+
+    // Reg SHO helpers
+    pub fn reg_sho_v41(nanoseconds: u32, ticker: &str, action: char) -> Cursor<Vec<u8>> {
+        let mut data = Vec::<u8>::new();
+        data.push(b'Y');
+        data.write_u32::<NetworkEndian>(nanoseconds).unwrap();
+        let mut ticker_bytes = [b' '; 8];
+        let ticker_bytes_slice = ticker.as_bytes();
+        ticker_bytes[..ticker_bytes_slice.len()].copy_from_slice(ticker_bytes_slice);
+        data.extend_from_slice(&ticker_bytes);
+        data.push(action as u8);
+
+        Cursor::new(data)
+    }
+
+    pub fn reg_sho_v50(nanoseconds: u64, ticker: &str, action: char) -> Cursor<Vec<u8>> {
+        let mut data = Vec::<u8>::new();
+        data.push(b'Y');
+        data.write_u16::<NetworkEndian>(0).unwrap(); // stock locate
+        data.write_u16::<NetworkEndian>(0).unwrap(); // tracking number
+        data.write_u48::<NetworkEndian>(nanoseconds).unwrap();
+        let mut ticker_bytes = [b' '; 8];
+        let ticker_bytes_slice = ticker.as_bytes();
+        ticker_bytes[..ticker_bytes_slice.len()].copy_from_slice(ticker_bytes_slice);
+        data.extend_from_slice(&ticker_bytes);
+        data.push(action as u8);
+
+        Cursor::new(data)
+    }
+
+    // Market Participant Position helpers
+    pub fn market_participant_v41(
+        nanoseconds: u32,
+        mpid: &str,
+        ticker: &str,
+        primary_market_maker: bool,
+        mode: char,
+        state: char,
+    ) -> Cursor<Vec<u8>> {
+        let mut data = Vec::<u8>::new();
+        data.push(b'L');
+        data.write_u32::<NetworkEndian>(nanoseconds).unwrap();
+        let mut mpid_bytes = [b' '; 4];
+        let mpid_bytes_slice = mpid.as_bytes();
+        mpid_bytes[..mpid_bytes_slice.len()].copy_from_slice(mpid_bytes_slice);
+        data.extend_from_slice(&mpid_bytes);
+        let mut ticker_bytes = [b' '; 8];
+        let ticker_bytes_slice = ticker.as_bytes();
+        ticker_bytes[..ticker_bytes_slice.len()].copy_from_slice(ticker_bytes_slice);
+        data.extend_from_slice(&ticker_bytes);
+        data.push(if primary_market_maker { b'Y' } else { b'N' });
+        data.push(mode as u8);
+        data.push(state as u8);
+
+        Cursor::new(data)
+    }
+
+    pub fn market_participant_v50(
+        nanoseconds: u64,
+        mpid: &str,
+        ticker: &str,
+        primary_market_maker: bool,
+        mode: char,
+        state: char,
+    ) -> Cursor<Vec<u8>> {
+        let mut data = Vec::<u8>::new();
+        data.push(b'L');
+        data.write_u16::<NetworkEndian>(0).unwrap(); // stock locate
+        data.write_u16::<NetworkEndian>(0).unwrap(); // tracking number
+        data.write_u48::<NetworkEndian>(nanoseconds).unwrap();
+        let mut mpid_bytes = [b' '; 4];
+        let mpid_bytes_slice = mpid.as_bytes();
+        mpid_bytes[..mpid_bytes_slice.len()].copy_from_slice(mpid_bytes_slice);
+        data.extend_from_slice(&mpid_bytes);
+        let mut ticker_bytes = [b' '; 8];
+        let ticker_bytes_slice = ticker.as_bytes();
+        ticker_bytes[..ticker_bytes_slice.len()].copy_from_slice(ticker_bytes_slice);
+        data.extend_from_slice(&ticker_bytes);
+        data.push(if primary_market_maker { b'Y' } else { b'N' });
+        data.push(mode as u8);
+        data.push(state as u8);
+
+        Cursor::new(data)
+    }
+
+    // MWCB helpers
+    pub fn mwcb_decline_level_v50(
+        nanoseconds: u64,
+        level1: u64,
+        level2: u64,
+        level3: u64,
+    ) -> Cursor<Vec<u8>> {
+        let mut data = Vec::<u8>::new();
+        data.push(b'V');
+        data.write_u16::<NetworkEndian>(0).unwrap(); // stock locate
+        data.write_u16::<NetworkEndian>(0).unwrap(); // tracking number
+        data.write_u48::<NetworkEndian>(nanoseconds).unwrap();
+        data.write_u64::<NetworkEndian>(level1).unwrap();
+        data.write_u64::<NetworkEndian>(level2).unwrap();
+        data.write_u64::<NetworkEndian>(level3).unwrap();
+
+        Cursor::new(data)
+    }
+
+    pub fn mwcb_status_v50(nanoseconds: u64, breached_level: char) -> Cursor<Vec<u8>> {
+        let mut data = Vec::<u8>::new();
+        data.push(b'W');
+        data.write_u16::<NetworkEndian>(0).unwrap(); // stock locate
+        data.write_u16::<NetworkEndian>(0).unwrap(); // tracking number
+        data.write_u48::<NetworkEndian>(nanoseconds).unwrap();
+        data.push(breached_level as u8);
+
+        Cursor::new(data)
+    }
+
+    // IPO Quoting Period helpers
+    pub fn ipo_quoting_period_v50(
+        nanoseconds: u64,
+        ticker: &str,
+        release_time: u32,
+        release_qualifier: char,
+        ipo_price: u32,
+    ) -> Cursor<Vec<u8>> {
+        let mut data = Vec::<u8>::new();
+        data.push(b'K');
+        data.write_u16::<NetworkEndian>(0).unwrap(); // stock locate
+        data.write_u16::<NetworkEndian>(0).unwrap(); // tracking number
+        data.write_u48::<NetworkEndian>(nanoseconds).unwrap();
+        let mut ticker_bytes = [b' '; 8];
+        let ticker_bytes_slice = ticker.as_bytes();
+        ticker_bytes[..ticker_bytes_slice.len()].copy_from_slice(ticker_bytes_slice);
+        data.extend_from_slice(&ticker_bytes);
+        data.write_u32::<NetworkEndian>(release_time).unwrap();
+        data.push(release_qualifier as u8);
+        data.write_u32::<NetworkEndian>(ipo_price).unwrap();
+
+        Cursor::new(data)
+    }
+
+    // LULD Auction Collar helpers
+    pub fn luld_auction_collar_v50(
+        nanoseconds: u64,
+        ticker: &str,
+        reference_price: u32,
+        upper_price: u32,
+        lower_price: u32,
+        extension: u32,
+    ) -> Cursor<Vec<u8>> {
+        let mut data = Vec::<u8>::new();
+        data.push(b'J');
+        data.write_u16::<NetworkEndian>(0).unwrap(); // stock locate
+        data.write_u16::<NetworkEndian>(0).unwrap(); // tracking number
+        data.write_u48::<NetworkEndian>(nanoseconds).unwrap();
+        let mut ticker_bytes = [b' '; 8];
+        let ticker_bytes_slice = ticker.as_bytes();
+        ticker_bytes[..ticker_bytes_slice.len()].copy_from_slice(ticker_bytes_slice);
+        data.extend_from_slice(&ticker_bytes);
+        data.write_u32::<NetworkEndian>(reference_price).unwrap();
+        data.write_u32::<NetworkEndian>(upper_price).unwrap();
+        data.write_u32::<NetworkEndian>(lower_price).unwrap();
+        data.write_u32::<NetworkEndian>(extension).unwrap();
+
+        Cursor::new(data)
+    }
+
+    // Operational Halt helpers
+    pub fn operational_halt_v50(
+        nanoseconds: u64,
+        ticker: &str,
+        market_code: char,
+        action: char,
+    ) -> Cursor<Vec<u8>> {
+        let mut data = Vec::<u8>::new();
+        data.push(b'h');
+        data.write_u16::<NetworkEndian>(0).unwrap(); // stock locate
+        data.write_u16::<NetworkEndian>(0).unwrap(); // tracking number
+        data.write_u48::<NetworkEndian>(nanoseconds).unwrap();
+        let mut ticker_bytes = [b' '; 8];
+        let ticker_bytes_slice = ticker.as_bytes();
+        ticker_bytes[..ticker_bytes_slice.len()].copy_from_slice(ticker_bytes_slice);
+        data.extend_from_slice(&ticker_bytes);
+        data.push(market_code as u8);
+        data.push(action as u8);
+
+        Cursor::new(data)
+    }
+
+    // NOII helpers
+    #[allow(clippy::too_many_arguments)]
+    pub fn noii_v50(
+        nanoseconds: u64,
+        ticker: &str,
+        paired_shares: u64,
+        imbalance_shares: u64,
+        imbalance_direction: char,
+        far_price: u32,
+        near_price: u32,
+        current_reference_price: u32,
+        cross_type: char,
+    ) -> Cursor<Vec<u8>> {
+        let mut data = Vec::<u8>::new();
+        data.push(b'I');
+        data.write_u16::<NetworkEndian>(0).unwrap(); // stock locate
+        data.write_u16::<NetworkEndian>(0).unwrap(); // tracking number
+        data.write_u48::<NetworkEndian>(nanoseconds).unwrap();
+        data.write_u64::<NetworkEndian>(paired_shares).unwrap();
+        data.write_u64::<NetworkEndian>(imbalance_shares).unwrap();
+        data.push(imbalance_direction as u8);
+        let mut ticker_bytes = [b' '; 8];
+        let ticker_bytes_slice = ticker.as_bytes();
+        ticker_bytes[..ticker_bytes_slice.len()].copy_from_slice(ticker_bytes_slice);
+        data.extend_from_slice(&ticker_bytes);
+        data.write_u32::<NetworkEndian>(far_price).unwrap();
+        data.write_u32::<NetworkEndian>(near_price).unwrap();
+        data.write_u32::<NetworkEndian>(current_reference_price)
+            .unwrap();
+        data.push(cross_type as u8);
+        data.push(b' '); // price variation indicator
+
+        Cursor::new(data)
+    }
+
+    // RPII helpers
+    pub fn rpii_v50(nanoseconds: u64, ticker: &str, interest_flag: char) -> Cursor<Vec<u8>> {
+        let mut data = Vec::<u8>::new();
+        data.push(b'N');
+        data.write_u16::<NetworkEndian>(0).unwrap(); // stock locate
+        data.write_u16::<NetworkEndian>(0).unwrap(); // tracking number
+        data.write_u48::<NetworkEndian>(nanoseconds).unwrap();
+        let mut ticker_bytes = [b' '; 8];
+        let ticker_bytes_slice = ticker.as_bytes();
+        ticker_bytes[..ticker_bytes_slice.len()].copy_from_slice(ticker_bytes_slice);
+        data.extend_from_slice(&ticker_bytes);
+        data.push(interest_flag as u8);
+
+        Cursor::new(data)
+    }
+
+    // DLCR helpers
+    #[allow(clippy::too_many_arguments)]
+    pub fn dlcr_v50(
+        nanoseconds: u64,
+        ticker: &str,
+        open_eligibility: char,
+        minimum_price: u32,
+        maximum_price: u32,
+        near_execution_price: u32,
+        near_execution_time: u64,
+        lower_collar: u32,
+        upper_collar: u32,
+    ) -> Cursor<Vec<u8>> {
+        let mut data = Vec::<u8>::new();
+        data.push(b'O');
+        data.write_u16::<NetworkEndian>(0).unwrap(); // stock locate
+        data.write_u16::<NetworkEndian>(0).unwrap(); // tracking number
+        data.write_u48::<NetworkEndian>(nanoseconds).unwrap();
+        let mut ticker_bytes = [b' '; 8];
+        let ticker_bytes_slice = ticker.as_bytes();
+        ticker_bytes[..ticker_bytes_slice.len()].copy_from_slice(ticker_bytes_slice);
+        data.extend_from_slice(&ticker_bytes);
+        data.push(open_eligibility as u8);
+        data.write_u32::<NetworkEndian>(minimum_price).unwrap();
+        data.write_u32::<NetworkEndian>(maximum_price).unwrap();
+        data.write_u32::<NetworkEndian>(near_execution_price)
+            .unwrap();
+        data.write_u64::<NetworkEndian>(near_execution_time)
+            .unwrap();
+        data.write_u32::<NetworkEndian>(lower_collar).unwrap();
+        data.write_u32::<NetworkEndian>(upper_collar).unwrap();
+
+        Cursor::new(data)
+    }
+
+    // Legacy version helpers. Text records (Versions 2.0 and 3.0) are built without their
+    // terminating newline, see `create_record_sequence`.
+    fn text_side(side: Side) -> u8 {
+        match side {
+            Side::Buy => b'B',
+            Side::Sell => b'S',
+        }
+    }
+
+    pub fn timestamp_v30(seconds: u32) -> Cursor<Vec<u8>> {
+        Cursor::new(format!("T{:05}", seconds).into_bytes())
+    }
+
+    pub fn add_order_v40(
+        nanoseconds: u32,
+        refno: u64,
+        side: Side,
+        shares: u32,
+        ticker: &str,
+        price: u32,
+    ) -> Cursor<Vec<u8>> {
+        let mut data = Vec::<u8>::new();
+        data.push(b'A');
+        data.write_u32::<NetworkEndian>(nanoseconds).unwrap();
+        data.write_u64::<NetworkEndian>(refno).unwrap();
+        data.push(text_side(side));
+        data.write_u32::<NetworkEndian>(shares).unwrap();
+        data.extend_from_slice(format!("{:<6}", ticker).as_bytes());
+        data.write_u32::<NetworkEndian>(price).unwrap();
+
+        Cursor::new(data)
+    }
+
+    pub fn add_order_v30(
+        milliseconds: u32,
+        refno: u64,
+        side: Side,
+        shares: u32,
+        ticker: &str,
+        price: u32,
+    ) -> Cursor<Vec<u8>> {
+        let mut data = format!("A{:03}{:09}", milliseconds, refno).into_bytes();
+        data.push(text_side(side));
+        data.extend_from_slice(format!("{:6}{:<6}{:10}", shares, ticker, price).as_bytes());
+
+        Cursor::new(data)
+    }
+
+    pub fn add_order_v20(
+        milliseconds: u32,
+        refno: u64,
+        side: Side,
+        shares: u32,
+        ticker: &str,
+        price: u32,
+    ) -> Cursor<Vec<u8>> {
+        let mut data = format!("{:08}A{:09}", milliseconds, refno).into_bytes();
+        data.push(text_side(side));
+        data.extend_from_slice(format!("{:6}{:<6}{:10}", shares, ticker, price).as_bytes());
+
+        Cursor::new(data)
+    }
+
+    pub fn execute_order_v30(
+        milliseconds: u32,
+        refno: u64,
+        shares: u32,
+        matchno: u64,
+    ) -> Cursor<Vec<u8>> {
+        let data = format!("E{:03}{:09}{:6}{:09}", milliseconds, refno, shares, matchno);
+        Cursor::new(data.into_bytes())
+    }
+
+    pub fn delete_order_v20(milliseconds: u32, refno: u64) -> Cursor<Vec<u8>> {
+        Cursor::new(format!("{:08}D{:09}", milliseconds, refno).into_bytes())
+    }
+
+    pub fn system_event_v20(milliseconds: u32, event_code: char) -> Cursor<Vec<u8>> {
+        Cursor::new(format!("{:08}S{}", milliseconds, event_code).into_bytes())
+    }
+
+    pub fn trade_v30(
+        milliseconds: u32,
+        refno: u64,
+        side: Side,
+        shares: u32,
+        ticker: &str,
+        price: u32,
+        matchno: u64,
+    ) -> Cursor<Vec<u8>> {
+        let mut data = format!("P{:03}{:09}", milliseconds, refno).into_bytes();
+        data.push(text_side(side));
+        data.extend_from_slice(
+            format!("{:6}{:<6}{:10}{:09}", shares, ticker, price, matchno).as_bytes(),
+        );
+
+        Cursor::new(data)
+    }
+
+    pub fn ticker(symbol: &str) -> Ticker {
+        symbol.parse().unwrap()
+    }
+
     // Helper for creating OrderState for context setup
     pub(crate) fn create_order_state(
-        ticker: &str,
+        symbol: &str,
         side: Side,
         price: u32,
         shares: u32,
     ) -> OrderState {
         OrderState {
-            ticker: ticker.to_string(),
+            ticker: ticker(symbol),
             side,
             price,
             shares,
         }
     }
 
+    // Helper for setting the stock locate and tracking number of a Version 5.0 message
+    pub fn with_locate(
+        message_cursor: Cursor<Vec<u8>>,
+        stock_locate: u16,
+        tracking_number: u16,
+    ) -> Cursor<Vec<u8>> {
+        let mut data = message_cursor.into_inner();
+        data[1..3].copy_from_slice(&stock_locate.to_be_bytes());
+        data[3..5].copy_from_slice(&tracking_number.to_be_bytes());
+        Cursor::new(data)
+    }
+
     // Helper for writing message length prefix (useful for reader tests)
     pub fn with_length_prefix(message_cursor: Cursor<Vec<u8>>) -> Cursor<Vec<u8>> {
         let message_data = message_cursor.into_inner();
@@ -285,4 +796,16 @@ pub mod message_builders {
 
         Cursor::new(combined_data)
     }
+
+    // Helper for creating newline-terminated text records in sequence (useful for reader tests)
+    pub fn create_record_sequence(records: Vec<Cursor<Vec<u8>>>) -> Cursor<Vec<u8>> {
+        let mut combined_data = Vec::<u8>::new();
+
+        for record in records {
+            combined_data.extend_from_slice(&record.into_inner());
+            combined_data.push(b'\n');
+        }
+
+        Cursor::new(combined_data)
+    }
 }
@@ -0,0 +1,176 @@
+use std::{
+    fmt,
+    hash::{Hash, Hasher},
+    io::{Error, ErrorKind, Result},
+    str::FromStr,
+};
+
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+/// A stock symbol, stored as in the feed: up to eight ASCII characters padded with spaces.
+///
+/// Tickers are `Copy` and compare and hash as a single integer. They display (and serialize)
+/// without their padding.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Ticker([u8; 8]);
+
+impl Ticker {
+    pub const fn new(bytes: [u8; 8]) -> Self {
+        Self(bytes)
+    }
+
+    /// Creates a ticker from the (possibly padded) bytes of a message.
+    ///
+    /// Version 2.0 to 4.0 tickers are six bytes long and are padded to eight.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        if bytes.len() > 8 || !bytes.is_ascii() {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("Invalid ticker: {:?}", String::from_utf8_lossy(bytes)),
+            ));
+        }
+        let mut padded = [b' '; 8];
+        padded[..bytes.len()].copy_from_slice(bytes);
+        Ok(Self(padded))
+    }
+
+    /// Returns the padded bytes of the ticker.
+    pub fn as_bytes(&self) -> &[u8; 8] {
+        &self.0
+    }
+
+    /// Returns the ticker without its padding.
+    pub fn as_str(&self) -> &str {
+        // Only ASCII bytes are accepted, so the bytes are always valid UTF-8
+        std::str::from_utf8(&self.0).unwrap_or_default().trim_end()
+    }
+}
+
+// An all-blank ticker, e.g. for broken trades which do not identify their stock
+impl Default for Ticker {
+    fn default() -> Self {
+        Self([b' '; 8])
+    }
+}
+
+impl Hash for Ticker {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_u64(u64::from_ne_bytes(self.0));
+    }
+}
+
+impl FromStr for Ticker {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::from_bytes(s.trim().as_bytes())
+    }
+}
+
+impl fmt::Display for Ticker {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(self.as_str())
+    }
+}
+
+impl fmt::Debug for Ticker {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Ticker({:?})", self.as_str())
+    }
+}
+
+impl PartialEq<str> for Ticker {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<&str> for Ticker {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl Serialize for Ticker {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+// Accepts borrowed, transient and owned strings alike, so that tickers can be read from any
+// deserializer (e.g. one reading from an `io::Read`)
+struct TickerVisitor;
+
+impl de::Visitor<'_> for TickerVisitor {
+    type Value = Ticker;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a ticker of at most eight ASCII characters")
+    }
+
+    fn visit_str<E: de::Error>(self, s: &str) -> std::result::Result<Ticker, E> {
+        s.parse().map_err(E::custom)
+    }
+}
+
+impl<'de> Deserialize<'de> for Ticker {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        deserializer.deserialize_str(TickerVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_and_displays_without_padding() {
+        let ticker: Ticker = "AAPL".parse().unwrap();
+        assert_eq!(ticker, Ticker::new(*b"AAPL    "));
+        assert_eq!(ticker.to_string(), "AAPL");
+        assert_eq!(ticker, "AAPL");
+    }
+
+    #[test]
+    fn pads_short_tickers() {
+        // Version 4.0 tickers are six bytes long
+        let short = Ticker::from_bytes(b"MSFT  ").unwrap();
+        assert_eq!(short, Ticker::from_bytes(b"MSFT    ").unwrap());
+    }
+
+    #[test]
+    fn roundtrips_through_owned_deserializers() {
+        use serde::de::{value::StringDeserializer, IntoDeserializer};
+
+        let deserializer: StringDeserializer<de::value::Error> =
+            "MSFT".to_string().into_deserializer();
+        assert_eq!(Ticker::deserialize(deserializer).unwrap(), "MSFT");
+
+        let mut writer = csv::Writer::from_writer(vec![]);
+        writer
+            .serialize(("AAPL".parse::<Ticker>().unwrap(), 1))
+            .unwrap();
+        let data = writer.into_inner().unwrap();
+        let mut reader = csv::ReaderBuilder::new()
+            .has_headers(false)
+            .from_reader(data.as_slice());
+        let row: (Ticker, u32) = reader.deserialize().next().unwrap().unwrap();
+        assert_eq!(row, ("AAPL".parse().unwrap(), 1));
+    }
+
+    #[test]
+    fn rejects_invalid_tickers() {
+        assert!("TOOLONGTICKER".parse::<Ticker>().is_err());
+        assert!(Ticker::from_bytes(&[0xff, b'A']).is_err());
+    }
+
+    #[test]
+    fn serializes_as_string() {
+        let mut writer = csv::Writer::from_writer(vec![]);
+        writer
+            .serialize(("AAPL".parse::<Ticker>().unwrap(),))
+            .unwrap();
+        let output = String::from_utf8(writer.into_inner().unwrap()).unwrap();
+        assert_eq!(output, "AAPL\n");
+    }
+}
@@ -1,21 +1,28 @@
-use std::io::{Read, Result, Seek, SeekFrom};
+use std::{
+    io::{Read, Seek},
+    sync::Arc,
+};
 
 use getset::Getters;
 
 use super::{
-    read_kind, read_matchno, read_nanoseconds, read_price, read_refno, read_shares, read_side,
-    read_ticker, Context, IntoTradeMessage, ReadMessage, Side, TradeMessage, Version,
+    read_kind, read_locate, read_matchno, read_nanoseconds, read_price, read_refno, read_shares,
+    read_side, read_ticker, Context, IntoTradeMessage, ReadMessage, Side, Ticker, TradeMessage,
+    Version,
 };
+use crate::error::Result;
 
 #[derive(Debug, Getters)]
 #[getset(get = "pub")]
 pub struct Trade {
     nanoseconds: u64,
     kind: char,
+    stock_locate: Option<u16>,
+    tracking_number: Option<u16>,
     refno: u64,
     side: Side,
     shares: u32,
-    ticker: String,
+    ticker: Ticker,
     price: u32,
     matchno: u64,
 }
@@ -26,20 +33,20 @@ impl ReadMessage for Trade {
         T: Read + Seek,
     {
         let kind = read_kind(buffer)?;
-        if version == &Version::V50 {
-            buffer.seek(SeekFrom::Current(4))?; // Discard stock locate and tracking number
-        }
+        let (stock_locate, tracking_number) = read_locate(buffer, version)?;
         let nanoseconds = read_nanoseconds(buffer, version, context.clock)?;
-        let refno = read_refno(buffer)?;
+        let refno = read_refno(buffer, version)?;
         let side = read_side(buffer)?;
-        let shares = read_shares(buffer)?;
-        let ticker = read_ticker(buffer)?;
-        let price = read_price(buffer)?;
-        let matchno = read_matchno(buffer)?;
+        let shares = read_shares(buffer, version)?;
+        let ticker = read_ticker(buffer, version)?;
+        let price = read_price(buffer, version)?;
+        let matchno = read_matchno(buffer, version)?;
 
         Ok(Self {
             nanoseconds,
             kind,
+            stock_locate,
+            tracking_number,
             refno,
             side,
             shares,
@@ -50,12 +57,14 @@ impl ReadMessage for Trade {
     }
 }
 
-impl IntoTradeMessage for Trade {
-    fn into_trade_message(self, date: String) -> TradeMessage {
+impl IntoTradeMessage for &Trade {
+    fn into_trade_message(self, date: Arc<str>) -> TradeMessage {
         TradeMessage {
             date,
             nanoseconds: self.nanoseconds,
             kind: self.kind,
+            stock_locate: self.stock_locate,
+            tracking_number: self.tracking_number,
             refno: self.refno,
             side: self.side,
             shares: self.shares as u64,
@@ -64,6 +73,7 @@ impl IntoTradeMessage for Trade {
             matchno: self.matchno,
             cross_price: 0,  // Not applicable for regular trades
             cross_type: ' ', // Not applicable for regular trades
+            short_sale_restricted: None,
         }
     }
 }
@@ -0,0 +1,107 @@
+use std::io::{Read, Seek, SeekFrom};
+
+use getset::Getters;
+
+use super::{
+    read_kind, read_locate, read_nanoseconds, read_ticker, read_trading_state, Context,
+    ReadMessage, Ticker, TradingState, Version,
+};
+use crate::error::Result;
+
+// Stock trading action messages indicate the current trading state of a security. Nasdaq sends an
+// action message for every security at the start of the day, and another each time the security
+// is halted, paused, enters a quotation-only period or resumes trading.
+#[derive(Debug, Getters)]
+#[getset(get = "pub")]
+pub struct TradingAction {
+    nanoseconds: u64,
+    kind: char,
+    stock_locate: Option<u16>,
+    tracking_number: Option<u16>,
+    ticker: Ticker,
+    trading_state: TradingState,
+    reason: String,
+}
+
+impl ReadMessage for TradingAction {
+    fn read<T>(buffer: &mut T, version: &Version, context: &mut Context) -> Result<Self>
+    where
+        T: Read + Seek,
+    {
+        // Read data from buffer
+        let kind = read_kind(buffer)?;
+        let (stock_locate, tracking_number) = read_locate(buffer, version)?;
+        let nanoseconds = read_nanoseconds(buffer, version, context.clock)?;
+        let ticker = read_ticker(buffer, version)?;
+        let trading_state = read_trading_state(buffer)?;
+        buffer.seek(SeekFrom::Current(1))?; // Discard reserved byte
+        let mut reason = [0; 4];
+        buffer.read_exact(&mut reason)?;
+        let reason = String::from_utf8_lossy(&reason).trim().to_string();
+
+        // Update context
+        context.trading_states.insert(ticker, trading_state);
+
+        // Return message
+        Ok(Self {
+            nanoseconds,
+            kind,
+            stock_locate,
+            tracking_number,
+            ticker,
+            trading_state,
+            reason,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::message::test_helpers::message_builders::*;
+
+    #[test]
+    fn returns_message_and_updates_context_v50() {
+        let mut data = trading_action_v50(7000, "AAPL", 'H', "LUDP");
+        let mut context = Context::new();
+
+        let message = TradingAction::read(&mut data, &Version::V50, &mut context).unwrap();
+
+        assert_eq!(*message.kind(), 'H');
+        assert_eq!(*message.nanoseconds(), 7000);
+        assert_eq!(*message.ticker(), "AAPL");
+        assert_eq!(*message.trading_state(), TradingState::Halted);
+        assert_eq!(*message.reason(), "LUDP");
+        assert_eq!(
+            context.trading_states[&ticker("AAPL")],
+            TradingState::Halted
+        );
+    }
+
+    #[test]
+    fn returns_message_and_updates_context_v41() {
+        let mut data = trading_action_v41(500, "MSFT", 'T', "");
+        let mut context = Context::new();
+        context.update_clock(2);
+
+        let message = TradingAction::read(&mut data, &Version::V41, &mut context).unwrap();
+
+        assert_eq!(*message.nanoseconds(), 2_000_000_500);
+        assert_eq!(*message.ticker(), "MSFT");
+        assert_eq!(*message.trading_state(), TradingState::Trading);
+        assert_eq!(*message.reason(), "");
+        assert_eq!(
+            context.trading_states[&ticker("MSFT")],
+            TradingState::Trading
+        );
+    }
+
+    #[test]
+    fn errors_on_unknown_trading_state() {
+        let mut data = trading_action_v50(0, "AAPL", 'X', "");
+        let mut context = Context::new();
+
+        assert!(TradingAction::read(&mut data, &Version::V50, &mut context).is_err());
+        assert!(context.trading_states.is_empty());
+    }
+}
@@ -0,0 +1,831 @@
+//! Borrowed views over the raw bytes of binary messages.
+//!
+//! A `MessageRef` wraps the bytes of one message and decodes its fields only when they are
+//! accessed, so nothing is allocated for fields that are never looked at. Owned `Message`s remain
+//! available through `MessageRef::to_owned`.
+
+use std::{io::Cursor, ops::Deref};
+
+use byteorder::{ByteOrder, NetworkEndian};
+
+use super::{
+    invalid_field, parse_ticker, read_event_code, read_replace_order, read_side,
+    read_trading_state, AddOrder, BrokenTrade, CancelOrder, Context, CrossTrade, DeleteOrder,
+    DirectListingCapitalRaise, EventCode, ExecuteOrder, IPOQuotingPeriod, LULDAuctionCollar,
+    MWCBDeclineLevel, MWCBStatus, MarketParticipantPosition, Message, NetOrderImbalanceIndicator,
+    OperationalHalt, ReadMessage, RegSHORestriction, RetailPriceImprovement, Side, StockDirectory,
+    SystemEvent, Ticker, Trade, TradingAction, TradingState, Version,
+};
+use crate::error::{Error, Result};
+
+/// The bytes of a single binary message (without its length prefix) and the fields shared by all
+/// messages.
+#[derive(Debug, Clone, Copy)]
+pub struct Frame<'a> {
+    data: &'a [u8],
+    version: Version,
+    clock: u32, // Seconds past midnight of the last 'T' message (Version 4.x)
+}
+
+impl<'a> Frame<'a> {
+    pub fn bytes(&self) -> &'a [u8] {
+        self.data
+    }
+
+    pub fn kind(&self) -> char {
+        char::from(self.data[0])
+    }
+
+    pub fn nanoseconds(&self) -> u64 {
+        match self.version {
+            Version::V50 => NetworkEndian::read_u48(&self.data[5..11]),
+            _ => {
+                (self.clock as u64) * 1_000_000_000
+                    + NetworkEndian::read_u32(&self.data[1..5]) as u64
+            }
+        }
+    }
+
+    pub fn stock_locate(&self) -> Option<u16> {
+        (self.version == Version::V50).then(|| NetworkEndian::read_u16(&self.data[1..3]))
+    }
+
+    pub fn tracking_number(&self) -> Option<u16> {
+        (self.version == Version::V50).then(|| NetworkEndian::read_u16(&self.data[3..5]))
+    }
+
+    // Field accessors take offsets relative to the end of the header
+
+    fn field(&self, at: usize, size: usize) -> &'a [u8] {
+        let from = self.version.header_size() + at;
+        &self.data[from..from + size]
+    }
+
+    fn v50_only<T>(&self, read: impl FnOnce() -> T) -> Option<T> {
+        (self.version == Version::V50).then(read)
+    }
+
+    fn u32_at(&self, at: usize) -> u32 {
+        NetworkEndian::read_u32(self.field(at, 4))
+    }
+
+    fn u64_at(&self, at: usize) -> u64 {
+        NetworkEndian::read_u64(self.field(at, 8))
+    }
+
+    fn char_at(&self, at: usize) -> char {
+        char::from(self.field(at, 1)[0])
+    }
+
+    fn side_at(&self, at: usize) -> Result<Side> {
+        read_side(&mut self.field(at, 1))
+    }
+
+    fn str_at(&self, at: usize, size: usize) -> Result<&'a str> {
+        match std::str::from_utf8(self.field(at, size)) {
+            Ok(s) => Ok(s.trim()),
+            Err(_) => Err(invalid_field("text", self.field(at, size))),
+        }
+    }
+}
+
+/// A lazily decoded message borrowed from the bytes of a binary file.
+///
+/// System events, stock directory and trading action messages, order messages (add, execute,
+/// cancel, delete and replace), trade messages (including cross and broken trades) and NOII
+/// messages get typed views. Every other message type (e.g. Reg SHO or market participant
+/// messages) is exposed as its `Frame` under `Other`, and its fields are only available by
+/// decoding it with `to_owned`.
+#[derive(Debug, Clone, Copy)]
+pub enum MessageRef<'a> {
+    SystemEvent(SystemEventRef<'a>),
+    StockDirectory(StockDirectoryRef<'a>),
+    TradingAction(TradingActionRef<'a>),
+    AddOrder(AddOrderRef<'a>),
+    ExecuteOrder(ExecuteOrderRef<'a>),
+    CancelOrder(CancelOrderRef<'a>),
+    DeleteOrder(DeleteOrderRef<'a>),
+    ReplaceOrder(ReplaceOrderRef<'a>),
+    Trade(TradeRef<'a>),
+    CrossTrade(CrossTradeRef<'a>),
+    BrokenTrade(BrokenTradeRef<'a>),
+    NetOrderImbalanceIndicator(NetOrderImbalanceIndicatorRef<'a>),
+    Other(Frame<'a>),
+}
+
+impl<'a> MessageRef<'a> {
+    /// Wraps the bytes of a binary message (without its length prefix).
+    ///
+    /// `clock` is the number of seconds carried by the last 'T' message (ignored for Version 5.0).
+    /// Fails if `data` is too short for its message type or `version` is a text version.
+    pub fn new(data: &'a [u8], version: Version, clock: u32) -> Result<Self> {
+        if version.is_text() {
+            return Err(Error::Unframed(version));
+        }
+        let kind = match data.first() {
+            Some(&b) => char::from(b),
+            None => return Err(Error::TruncatedMessage { size: 0 }),
+        };
+
+        let frame = Frame {
+            data,
+            version,
+            clock,
+        };
+        let ticker = version.ticker_size();
+        let (size, message) = match kind {
+            'S' => (1, MessageRef::SystemEvent(SystemEventRef(frame))),
+            'R' if version == Version::V50 => (
+                20 + ticker,
+                MessageRef::StockDirectory(StockDirectoryRef(frame)),
+            ),
+            'R' => (
+                7 + ticker,
+                MessageRef::StockDirectory(StockDirectoryRef(frame)),
+            ),
+            'H' => (
+                6 + ticker,
+                MessageRef::TradingAction(TradingActionRef(frame)),
+            ),
+            'A' => (17 + ticker, MessageRef::AddOrder(AddOrderRef(frame))),
+            'F' => (21 + ticker, MessageRef::AddOrder(AddOrderRef(frame))),
+            'E' => (20, MessageRef::ExecuteOrder(ExecuteOrderRef(frame))),
+            'C' => (25, MessageRef::ExecuteOrder(ExecuteOrderRef(frame))),
+            'X' => (12, MessageRef::CancelOrder(CancelOrderRef(frame))),
+            'D' => (8, MessageRef::DeleteOrder(DeleteOrderRef(frame))),
+            'U' => (24, MessageRef::ReplaceOrder(ReplaceOrderRef(frame))),
+            'P' => (25 + ticker, MessageRef::Trade(TradeRef(frame))),
+            'Q' => (21 + ticker, MessageRef::CrossTrade(CrossTradeRef(frame))),
+            'B' => (8, MessageRef::BrokenTrade(BrokenTradeRef(frame))),
+            'I' => (
+                31 + ticker,
+                MessageRef::NetOrderImbalanceIndicator(NetOrderImbalanceIndicatorRef(frame)),
+            ),
+            _ => (0, MessageRef::Other(frame)),
+        };
+        if data.len() < version.header_size() + size {
+            return Err(Error::SizeMismatch {
+                kind,
+                expected: version.message_size(kind),
+                found: data.len(),
+            });
+        }
+
+        Ok(message)
+    }
+
+    pub fn frame(&self) -> &Frame<'a> {
+        match self {
+            MessageRef::SystemEvent(m) => m,
+            MessageRef::StockDirectory(m) => m,
+            MessageRef::TradingAction(m) => m,
+            MessageRef::AddOrder(m) => m,
+            MessageRef::ExecuteOrder(m) => m,
+            MessageRef::CancelOrder(m) => m,
+            MessageRef::DeleteOrder(m) => m,
+            MessageRef::ReplaceOrder(m) => m,
+            MessageRef::Trade(m) => m,
+            MessageRef::CrossTrade(m) => m,
+            MessageRef::BrokenTrade(m) => m,
+            MessageRef::NetOrderImbalanceIndicator(m) => m,
+            MessageRef::Other(frame) => frame,
+        }
+    }
+
+    pub fn kind(&self) -> char {
+        self.frame().kind()
+    }
+
+    pub fn nanoseconds(&self) -> u64 {
+        self.frame().nanoseconds()
+    }
+
+    /// Decodes the message into owned `Message`s.
+    ///
+    /// Order executions, cancellations, deletions and replacements only carry the reference
+    /// number of their order: the rest is looked up in (and updated in) `context`, so the add
+    /// order messages they refer to must have been converted with the same context first. A
+    /// replacement decodes into a delete order followed by an add order, hence the `Vec`.
+    pub fn to_owned(&self, context: &mut Context) -> Result<Vec<Message>> {
+        let frame = self.frame();
+        let version = &frame.version;
+        if *version != Version::V50 {
+            context.update_clock(frame.clock);
+        }
+        if let MessageRef::ExecuteOrder(ExecuteOrderRef(f))
+        | MessageRef::CancelOrder(CancelOrderRef(f))
+        | MessageRef::DeleteOrder(DeleteOrderRef(f))
+        | MessageRef::ReplaceOrder(ReplaceOrderRef(f)) = self
+        {
+            let refno = f.u64_at(0);
+            if !context.has_order(refno) {
+                return Err(Error::UnknownRefno(refno));
+            }
+        }
+
+        let buffer = &mut Cursor::new(frame.data);
+        let message = match frame.kind() {
+            'S' => Message::SystemEvent(SystemEvent::read(buffer, version, context)?),
+            'V' if *version == Version::V50 => {
+                Message::MWCBDeclineLevel(MWCBDeclineLevel::read(buffer, version, context)?)
+            }
+            'W' if *version == Version::V50 => {
+                Message::MWCBStatus(MWCBStatus::read(buffer, version, context)?)
+            }
+            'R' => Message::StockDirectory(StockDirectory::read(buffer, version, context)?),
+            'H' => Message::TradingAction(TradingAction::read(buffer, version, context)?),
+            'Y' => Message::RegSHORestriction(RegSHORestriction::read(buffer, version, context)?),
+            'L' => Message::MarketParticipantPosition(MarketParticipantPosition::read(
+                buffer, version, context,
+            )?),
+            'K' if *version == Version::V50 => {
+                Message::IPOQuotingPeriod(IPOQuotingPeriod::read(buffer, version, context)?)
+            }
+            'J' if *version == Version::V50 => {
+                Message::LULDAuctionCollar(LULDAuctionCollar::read(buffer, version, context)?)
+            }
+            'h' if *version == Version::V50 => {
+                Message::OperationalHalt(OperationalHalt::read(buffer, version, context)?)
+            }
+            'A' | 'F' => Message::AddOrder(AddOrder::read(buffer, version, context)?),
+            'E' | 'C' => Message::ExecuteOrder(ExecuteOrder::read(buffer, version, context)?),
+            'X' => Message::CancelOrder(CancelOrder::read(buffer, version, context)?),
+            'D' => Message::DeleteOrder(DeleteOrder::read(buffer, version, context)?),
+            'U' => {
+                let (delete_order, add_order) = read_replace_order(buffer, version, context)?;
+                return Ok(vec![
+                    Message::DeleteOrder(delete_order),
+                    Message::AddOrder(add_order),
+                ]);
+            }
+            'P' => Message::Trade(Trade::read(buffer, version, context)?),
+            'Q' => Message::CrossTrade(CrossTrade::read(buffer, version, context)?),
+            'B' => Message::BrokenTrade(BrokenTrade::read(buffer, version, context)?),
+            'I' => Message::NetOrderImbalanceIndicator(NetOrderImbalanceIndicator::read(
+                buffer, version, context,
+            )?),
+            'N' if *version == Version::V50 => Message::RetailPriceImprovement(
+                RetailPriceImprovement::read(buffer, version, context)?,
+            ),
+            'O' if *version == Version::V50 => Message::DirectListingCapitalRaise(
+                DirectListingCapitalRaise::read(buffer, version, context)?,
+            ),
+            kind => {
+                return Err(Error::UnsupportedMessage {
+                    kind,
+                    version: *version,
+                })
+            }
+        };
+
+        Ok(vec![message])
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct SystemEventRef<'a>(Frame<'a>);
+
+impl SystemEventRef<'_> {
+    pub fn event_code(&self) -> Result<EventCode> {
+        read_event_code(&mut self.field(0, 1))
+    }
+}
+
+/// A stock directory message. Fields from the issue classification on only exist in Version 5.0.
+#[derive(Debug, Clone, Copy)]
+pub struct StockDirectoryRef<'a>(Frame<'a>);
+
+impl<'a> StockDirectoryRef<'a> {
+    pub fn ticker(&self) -> Result<Ticker> {
+        parse_ticker(self.field(0, self.version.ticker_size()))
+    }
+
+    pub fn market_category(&self) -> char {
+        self.char_at(self.version.ticker_size())
+    }
+
+    pub fn financial_status(&self) -> char {
+        self.char_at(1 + self.version.ticker_size())
+    }
+
+    pub fn round_lot_size(&self) -> u32 {
+        self.u32_at(2 + self.version.ticker_size())
+    }
+
+    pub fn round_lots_only(&self) -> bool {
+        self.char_at(6 + self.version.ticker_size()) == 'Y'
+    }
+
+    pub fn issue_classification(&self) -> Option<char> {
+        self.v50_only(|| self.char_at(7 + self.version.ticker_size()))
+    }
+
+    pub fn issue_subtype(&self) -> Result<Option<&'a str>> {
+        self.v50_only(|| self.str_at(8 + self.version.ticker_size(), 2))
+            .transpose()
+    }
+
+    pub fn authenticity(&self) -> Option<char> {
+        self.v50_only(|| self.char_at(10 + self.version.ticker_size()))
+    }
+
+    pub fn short_sale_threshold(&self) -> Option<char> {
+        self.v50_only(|| self.char_at(11 + self.version.ticker_size()))
+    }
+
+    pub fn ipo_flag(&self) -> Option<char> {
+        self.v50_only(|| self.char_at(12 + self.version.ticker_size()))
+    }
+
+    pub fn luld_reference_price_tier(&self) -> Option<char> {
+        self.v50_only(|| self.char_at(13 + self.version.ticker_size()))
+    }
+
+    pub fn etp_flag(&self) -> Option<char> {
+        self.v50_only(|| self.char_at(14 + self.version.ticker_size()))
+    }
+
+    pub fn etp_leverage_factor(&self) -> Option<u32> {
+        self.v50_only(|| self.u32_at(15 + self.version.ticker_size()))
+    }
+
+    pub fn inverse_indicator(&self) -> Option<char> {
+        self.v50_only(|| self.char_at(19 + self.version.ticker_size()))
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct TradingActionRef<'a>(Frame<'a>);
+
+impl<'a> TradingActionRef<'a> {
+    pub fn ticker(&self) -> Result<Ticker> {
+        parse_ticker(self.field(0, self.version.ticker_size()))
+    }
+
+    pub fn trading_state(&self) -> Result<TradingState> {
+        read_trading_state(&mut self.field(self.version.ticker_size(), 1))
+    }
+
+    pub fn reason(&self) -> Result<&'a str> {
+        self.str_at(2 + self.version.ticker_size(), 4) // After a reserved byte
+    }
+}
+
+/// An add order message, with (kind 'F') or without (kind 'A') attribution.
+#[derive(Debug, Clone, Copy)]
+pub struct AddOrderRef<'a>(Frame<'a>);
+
+impl<'a> AddOrderRef<'a> {
+    pub fn refno(&self) -> u64 {
+        self.u64_at(0)
+    }
+
+    pub fn side(&self) -> Result<Side> {
+        self.side_at(8)
+    }
+
+    pub fn shares(&self) -> u32 {
+        self.u32_at(9)
+    }
+
+    pub fn ticker(&self) -> Result<Ticker> {
+        parse_ticker(self.field(13, self.version.ticker_size()))
+    }
+
+    pub fn price(&self) -> u32 {
+        self.u32_at(13 + self.version.ticker_size())
+    }
+
+    pub fn mpid(&self) -> Result<Option<&'a str>> {
+        match self.kind() {
+            'F' => self.str_at(17 + self.version.ticker_size(), 4).map(Some),
+            _ => Ok(None),
+        }
+    }
+}
+
+/// An order execution, without (kind 'E') or with (kind 'C') a price of its own.
+#[derive(Debug, Clone, Copy)]
+pub struct ExecuteOrderRef<'a>(Frame<'a>);
+
+impl ExecuteOrderRef<'_> {
+    pub fn refno(&self) -> u64 {
+        self.u64_at(0)
+    }
+
+    pub fn shares(&self) -> u32 {
+        self.u32_at(8)
+    }
+
+    pub fn matchno(&self) -> u64 {
+        self.u64_at(12)
+    }
+
+    pub fn printable(&self) -> Option<bool> {
+        (self.kind() == 'C').then(|| self.char_at(20) == 'Y')
+    }
+
+    pub fn execution_price(&self) -> Option<u32> {
+        (self.kind() == 'C').then(|| self.u32_at(21))
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct CancelOrderRef<'a>(Frame<'a>);
+
+impl CancelOrderRef<'_> {
+    pub fn refno(&self) -> u64 {
+        self.u64_at(0)
+    }
+
+    pub fn shares(&self) -> u32 {
+        self.u32_at(8)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct DeleteOrderRef<'a>(Frame<'a>);
+
+impl DeleteOrderRef<'_> {
+    pub fn refno(&self) -> u64 {
+        self.u64_at(0)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ReplaceOrderRef<'a>(Frame<'a>);
+
+impl ReplaceOrderRef<'_> {
+    pub fn old_refno(&self) -> u64 {
+        self.u64_at(0)
+    }
+
+    pub fn new_refno(&self) -> u64 {
+        self.u64_at(8)
+    }
+
+    pub fn shares(&self) -> u32 {
+        self.u32_at(16)
+    }
+
+    pub fn price(&self) -> u32 {
+        self.u32_at(20)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct TradeRef<'a>(Frame<'a>);
+
+impl TradeRef<'_> {
+    pub fn refno(&self) -> u64 {
+        self.u64_at(0)
+    }
+
+    pub fn side(&self) -> Result<Side> {
+        self.side_at(8)
+    }
+
+    pub fn shares(&self) -> u32 {
+        self.u32_at(9)
+    }
+
+    pub fn ticker(&self) -> Result<Ticker> {
+        parse_ticker(self.field(13, self.version.ticker_size()))
+    }
+
+    pub fn price(&self) -> u32 {
+        self.u32_at(13 + self.version.ticker_size())
+    }
+
+    pub fn matchno(&self) -> u64 {
+        self.u64_at(17 + self.version.ticker_size())
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct CrossTradeRef<'a>(Frame<'a>);
+
+impl CrossTradeRef<'_> {
+    pub fn shares(&self) -> u64 {
+        self.u64_at(0)
+    }
+
+    pub fn ticker(&self) -> Result<Ticker> {
+        parse_ticker(self.field(8, self.version.ticker_size()))
+    }
+
+    pub fn cross_price(&self) -> u32 {
+        self.u32_at(8 + self.version.ticker_size())
+    }
+
+    pub fn matchno(&self) -> u64 {
+        self.u64_at(12 + self.version.ticker_size())
+    }
+
+    pub fn cross_type(&self) -> char {
+        self.char_at(20 + self.version.ticker_size())
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct BrokenTradeRef<'a>(Frame<'a>);
+
+impl BrokenTradeRef<'_> {
+    pub fn matchno(&self) -> u64 {
+        self.u64_at(0)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct NetOrderImbalanceIndicatorRef<'a>(Frame<'a>);
+
+impl NetOrderImbalanceIndicatorRef<'_> {
+    pub fn paired_shares(&self) -> u64 {
+        self.u64_at(0)
+    }
+
+    pub fn imbalance_shares(&self) -> u64 {
+        self.u64_at(8)
+    }
+
+    pub fn imbalance_direction(&self) -> char {
+        self.char_at(16)
+    }
+
+    pub fn ticker(&self) -> Result<Ticker> {
+        parse_ticker(self.field(17, self.version.ticker_size()))
+    }
+
+    pub fn far_price(&self) -> u32 {
+        self.u32_at(17 + self.version.ticker_size())
+    }
+
+    pub fn near_price(&self) -> u32 {
+        self.u32_at(21 + self.version.ticker_size())
+    }
+
+    pub fn current_reference_price(&self) -> u32 {
+        self.u32_at(25 + self.version.ticker_size())
+    }
+
+    pub fn cross_type(&self) -> char {
+        self.char_at(29 + self.version.ticker_size())
+    }
+
+    pub fn price_variation_indicator(&self) -> char {
+        self.char_at(30 + self.version.ticker_size())
+    }
+}
+
+// Gives every view the accessors of its frame (`kind`, `nanoseconds`, ...)
+macro_rules! impl_deref_frame {
+    ($($view:ident),*) => {
+        $(
+            impl<'a> Deref for $view<'a> {
+                type Target = Frame<'a>;
+
+                fn deref(&self) -> &Frame<'a> {
+                    &self.0
+                }
+            }
+        )*
+    };
+}
+
+impl_deref_frame!(
+    SystemEventRef,
+    StockDirectoryRef,
+    TradingActionRef,
+    AddOrderRef,
+    ExecuteOrderRef,
+    CancelOrderRef,
+    DeleteOrderRef,
+    ReplaceOrderRef,
+    TradeRef,
+    CrossTradeRef,
+    BrokenTradeRef,
+    NetOrderImbalanceIndicatorRef
+);
+
+/// Iterates over the messages of a binary file held in memory (e.g. an `MmapFile`).
+///
+/// Timestamp ('T') messages are consumed to keep track of the clock of Version 4.x messages.
+/// Errors carry the offset of their message within the data.
+pub struct MessageRefs<'a> {
+    data: &'a [u8],
+    version: Version,
+    clock: u32,
+    failed: bool,
+    offset: u64,   // Offset of the next message
+    sequence: u64, // Number of messages (including 'T' messages) before the next one
+}
+
+impl<'a> MessageRefs<'a> {
+    pub fn new(data: &'a [u8], version: Version) -> Self {
+        Self {
+            data,
+            version,
+            clock: 0,
+            failed: false,
+            offset: 0,
+            sequence: 0,
+        }
+    }
+}
+
+impl<'a> Iterator for MessageRefs<'a> {
+    type Item = Result<MessageRef<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if self.failed || self.data.is_empty() {
+                return None;
+            }
+
+            let (offset, sequence) = (self.offset, self.sequence);
+            let frame = match self.data {
+                [a, b, rest @ ..] if rest.len() >= u16::from_be_bytes([*a, *b]) as usize => {
+                    let (frame, rest) = rest.split_at(u16::from_be_bytes([*a, *b]) as usize);
+                    self.data = rest;
+                    frame
+                }
+                data => {
+                    self.failed = true;
+                    let size = match data {
+                        [a, b, ..] => u16::from_be_bytes([*a, *b]) as usize,
+                        _ => 0,
+                    };
+                    let error = Error::TruncatedMessage { size };
+                    return Some(Err(error.at(offset, sequence)));
+                }
+            };
+            self.offset += 2 + frame.len() as u64;
+            self.sequence += 1;
+
+            if self.version != Version::V50 && frame.first() == Some(&b'T') && frame.len() >= 5 {
+                self.clock = NetworkEndian::read_u32(&frame[1..5]);
+                continue;
+            }
+
+            let message = MessageRef::new(frame, self.version, self.clock)
+                .map_err(|e| e.at(offset, sequence));
+            self.failed = message.is_err();
+            return Some(message);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::message::test_helpers::message_builders::*;
+
+    fn frames(messages: Vec<Cursor<Vec<u8>>>) -> Vec<u8> {
+        create_message_sequence(messages).into_inner()
+    }
+
+    #[test]
+    fn views_order_fields() {
+        let data = frames(vec![
+            add_order_v50(34_200_000_000_001, 7, Side::Sell, 200, "AAPL", 1_500_000),
+            delete_order_v50(34_200_000_000_002, 7),
+        ]);
+        let mut views = MessageRefs::new(&data, Version::V50);
+
+        let Some(Ok(MessageRef::AddOrder(add))) = views.next() else {
+            panic!("expected an add order");
+        };
+        assert_eq!(add.kind(), 'A');
+        assert_eq!(add.nanoseconds(), 34_200_000_000_001);
+        assert_eq!(add.refno(), 7);
+        assert_eq!(add.side().unwrap(), Side::Sell);
+        assert_eq!(add.shares(), 200);
+        assert_eq!(add.ticker().unwrap(), "AAPL");
+        assert_eq!(add.price(), 1_500_000);
+        assert_eq!(add.mpid().unwrap(), None);
+
+        let Some(Ok(MessageRef::DeleteOrder(delete))) = views.next() else {
+            panic!("expected a delete order");
+        };
+        assert_eq!(delete.refno(), 7);
+        assert!(views.next().is_none());
+    }
+
+    #[test]
+    fn views_stock_fields() {
+        let data = frames(vec![
+            stock_directory_v50(1, "AAPL", 'Q', 'N', 100, 'C', "Z", 'N', ' '),
+            trading_action_v50(2, "AAPL", 'H', "LUDP"),
+            noii_v50(
+                3, "AAPL", 500, 200, 'B', 1_510_000, 1_505_000, 1_500_000, 'O',
+            ),
+        ]);
+        let mut views = MessageRefs::new(&data, Version::V50);
+
+        let Some(Ok(MessageRef::StockDirectory(directory))) = views.next() else {
+            panic!("expected a stock directory message");
+        };
+        assert_eq!(directory.ticker().unwrap(), "AAPL");
+        assert_eq!(directory.market_category(), 'Q');
+        assert_eq!(directory.financial_status(), 'N');
+        assert_eq!(directory.round_lot_size(), 100);
+        assert!(!directory.round_lots_only());
+        assert_eq!(directory.issue_classification(), Some('C'));
+        assert_eq!(directory.issue_subtype().unwrap(), Some("Z"));
+        assert_eq!(directory.luld_reference_price_tier(), Some('1'));
+        assert_eq!(directory.etp_leverage_factor(), Some(0));
+        assert_eq!(directory.inverse_indicator(), Some(' '));
+
+        let Some(Ok(MessageRef::TradingAction(action))) = views.next() else {
+            panic!("expected a trading action");
+        };
+        assert_eq!(action.nanoseconds(), 2);
+        assert_eq!(action.ticker().unwrap(), "AAPL");
+        assert_eq!(action.trading_state().unwrap(), TradingState::Halted);
+        assert_eq!(action.reason().unwrap(), "LUDP");
+
+        let Some(Ok(MessageRef::NetOrderImbalanceIndicator(noii))) = views.next() else {
+            panic!("expected a NOII message");
+        };
+        assert_eq!(noii.paired_shares(), 500);
+        assert_eq!(noii.imbalance_shares(), 200);
+        assert_eq!(noii.imbalance_direction(), 'B');
+        assert_eq!(noii.ticker().unwrap(), "AAPL");
+        assert_eq!(noii.far_price(), 1_510_000);
+        assert_eq!(noii.near_price(), 1_505_000);
+        assert_eq!(noii.current_reference_price(), 1_500_000);
+        assert_eq!(noii.cross_type(), 'O');
+        assert_eq!(noii.price_variation_indicator(), ' ');
+        assert!(views.next().is_none());
+    }
+
+    #[test]
+    fn views_stock_directory_of_version_41() {
+        let data = frames(vec![stock_directory_v41(1, "MSFT", 'Q', 'N', 100)]);
+        let Some(Ok(MessageRef::StockDirectory(directory))) =
+            MessageRefs::new(&data, Version::V41).next()
+        else {
+            panic!("expected a stock directory message");
+        };
+        assert_eq!(directory.ticker().unwrap(), "MSFT");
+        assert_eq!(directory.round_lot_size(), 100);
+        assert_eq!(directory.issue_classification(), None);
+        assert_eq!(directory.issue_subtype().unwrap(), None);
+        assert_eq!(directory.inverse_indicator(), None);
+    }
+
+    #[test]
+    fn tracks_clock_of_version_41() {
+        let data = frames(vec![
+            timestamp_v41(34200),
+            add_order_v41(5, 1, Side::Buy, 100, "A", 1000),
+        ]);
+        let view = MessageRefs::new(&data, Version::V41)
+            .next()
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(view.kind(), 'A');
+        assert_eq!(view.nanoseconds(), 34_200_000_000_005);
+    }
+
+    #[test]
+    fn converts_to_owned_messages() {
+        let data = frames(vec![
+            add_order_v50(0, 7, Side::Buy, 100, "AAPL", 1_500_000),
+            delete_order_v50(1, 7),
+        ]);
+        let mut context = Context::new();
+        let owned: Vec<Message> = MessageRefs::new(&data, Version::V50)
+            .flat_map(|view| view.unwrap().to_owned(&mut context).unwrap())
+            .collect();
+
+        assert!(matches!(&owned[0], Message::AddOrder(m) if m.ticker() == "AAPL"));
+        assert!(matches!(&owned[1], Message::DeleteOrder(m)
+            if m.refno() == &7 && m.price() == &1_500_000 && m.shares() == &100));
+    }
+
+    #[test]
+    fn unknown_order_is_an_error() {
+        let data = frames(vec![delete_order_v50(1, 7)]);
+        let view = MessageRefs::new(&data, Version::V50)
+            .next()
+            .unwrap()
+            .unwrap();
+        let err = view.to_owned(&mut Context::new()).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn rejects_truncated_messages() {
+        let mut data = frames(vec![system_event_v50(0, 'O')]);
+        data.pop();
+        let mut views = MessageRefs::new(&data, Version::V50);
+        assert!(views.next().unwrap().is_err());
+        assert!(views.next().is_none());
+
+        // A frame too short for its type
+        assert!(MessageRef::new(b"A\x00\x01", Version::V50, 0).is_err());
+        assert!(MessageRef::new(b"A", Version::V30, 0).is_err());
+    }
+}
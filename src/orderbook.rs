@@ -1,46 +1,59 @@
-use std::{
-    collections::HashMap,
-    io::{Error, ErrorKind, Result},
-};
+use std::{collections::HashMap, sync::Arc};
 
 use serde::Serialize;
 
-use crate::message::Side;
+use crate::{
+    error::{Error, Result},
+    message::{Side, Ticker, TradingState},
+};
 
 #[derive(Debug, Serialize)]
 pub struct OrderBookSnapshot {
-    pub date: String,
-    pub ticker: String,
+    pub date: Arc<str>,
+    pub ticker: Ticker,
     pub timestamp: u64,
+    pub state: TradingState,
     pub data: Vec<i64>, /* [bid_price_1, bid_size_1, bid_price_2, bid_size_2, ..., ask_price_1,
                          * ask_size_1, ...] */
 }
 
 pub struct OrderBook {
-    date: String,
-    ticker: String,
+    date: Arc<str>,
+    ticker: Ticker,
     timestamp: u64,
+    state: TradingState,
     levels: usize,
     bids: HashMap<u32, u32>, // price -> total_shares
     asks: HashMap<u32, u32>, // price -> total_shares
 }
 
 impl OrderBook {
-    pub fn new(date: String, ticker: String, levels: usize) -> Self {
+    pub fn new(date: Arc<str>, ticker: Ticker, levels: usize) -> Self {
         Self {
             date,
             ticker,
             timestamp: 0,
+            state: TradingState::Trading,
             levels,
             bids: HashMap::new(),
             asks: HashMap::new(),
         }
     }
 
-    pub fn ticker(&self) -> &str {
+    pub fn ticker(&self) -> &Ticker {
         &self.ticker
     }
 
+    pub fn trading_state(&self) -> TradingState {
+        self.state
+    }
+
+    /// Mark the book with the trading state of its security (e.g., halted or paused)
+    pub fn set_trading_state(&mut self, state: TradingState, timestamp: u64) {
+        self.timestamp = timestamp;
+        self.state = state;
+    }
+
     pub fn bids(&self) -> &HashMap<u32, u32> {
         &self.bids
     }
@@ -65,7 +78,7 @@ impl OrderBook {
         let take = n.min(bids.len());
         bids.select_nth_unstable_by(take.saturating_sub(1), |a, b| b.0.cmp(&a.0));
         bids.truncate(take);
-        bids.sort_unstable_by(|a, b| b.0.cmp(&a.0));
+        bids.sort_unstable_by_key(|b| std::cmp::Reverse(b.0));
         bids
     }
 
@@ -85,7 +98,7 @@ impl OrderBook {
         let take = n.min(asks.len());
         asks.select_nth_unstable_by(take.saturating_sub(1), |a, b| a.0.cmp(&b.0));
         asks.truncate(take);
-        asks.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+        asks.sort_unstable_by_key(|a| a.0);
         asks
     }
 
@@ -114,8 +127,9 @@ impl OrderBook {
 
         OrderBookSnapshot {
             date: self.date.clone(),
-            ticker: self.ticker.clone(),
+            ticker: self.ticker,
             timestamp: self.timestamp,
+            state: self.state,
             data,
         }
     }
@@ -144,30 +158,7 @@ impl OrderBook {
             Side::Sell => &mut self.asks,
         };
 
-        match book.get_mut(&price) {
-            Some(current_shares) => {
-                if *current_shares < shares {
-                    return Err(Error::new(
-                        ErrorKind::InvalidInput,
-                        format!(
-                            "Cannot remove {} shares from price level {} (only {} available)",
-                            shares, price, current_shares
-                        ),
-                    ));
-                }
-
-                if *current_shares == shares {
-                    book.remove(&price);
-                } else {
-                    *current_shares -= shares;
-                }
-                Ok(())
-            }
-            None => Err(Error::new(
-                ErrorKind::NotFound,
-                format!("No orders found at price level {}", price),
-            )),
-        }
+        reduce_level(book, price, shares)
     }
 
     /// Execute shares at a price level (reduces volume)
@@ -184,29 +175,26 @@ impl OrderBook {
             Side::Sell => &mut self.asks,
         };
 
-        match book.get_mut(&price) {
-            Some(current_shares) => {
-                if *current_shares < executed_shares {
-                    return Err(Error::new(
-                        ErrorKind::InvalidInput,
-                        format!(
-                            "Cannot execute {} shares from price level {} (only {} available)",
-                            executed_shares, price, current_shares
-                        ),
-                    ));
-                }
-
-                if *current_shares == executed_shares {
-                    book.remove(&price);
-                } else {
-                    *current_shares -= executed_shares;
-                }
-                Ok(())
-            }
-            None => Err(Error::new(
-                ErrorKind::NotFound,
-                format!("No orders found at price level {}", price),
-            )),
+        reduce_level(book, price, executed_shares)
+    }
+}
+
+// Removes shares from a price level, dropping the level once it is empty
+fn reduce_level(book: &mut HashMap<u32, u32>, price: u32, shares: u32) -> Result<()> {
+    let resting = book.get(&price).copied().unwrap_or(0);
+    match resting.checked_sub(shares) {
+        None => Err(Error::LevelUnderflow {
+            price,
+            shares,
+            resting,
+        }),
+        Some(0) => {
+            book.remove(&price);
+            Ok(())
+        }
+        Some(left) => {
+            book.insert(price, left);
+            Ok(())
         }
     }
 }
@@ -217,7 +205,7 @@ mod tests {
 
     #[test]
     fn adds_shares() {
-        let mut book = OrderBook::new("01/01/2025".to_string(), "XYZ".to_string(), 5);
+        let mut book = OrderBook::new("01/01/2025".into(), "XYZ".parse().unwrap(), 5);
 
         book.add_order(Side::Buy, 1000, 100, 0);
         assert!(book.bids().get(&1000).is_some());
@@ -239,7 +227,7 @@ mod tests {
 
     #[test]
     fn removes_shares() {
-        let mut book = OrderBook::new("01/01/2025".to_string(), "XYZ".to_string(), 5);
+        let mut book = OrderBook::new("01/01/2025".into(), "XYZ".parse().unwrap(), 5);
 
         book.add_order(Side::Buy, 1000, 100, 0);
         assert!(book.bids().get(&1000).is_some());
@@ -265,9 +253,22 @@ mod tests {
         assert_eq!(book.timestamp, 5);
     }
 
+    #[test]
+    fn snapshots_carry_trading_state() {
+        let mut book = OrderBook::new("01/01/2025".into(), "XYZ".parse().unwrap(), 1);
+        book.add_order(Side::Buy, 1000, 100, 0);
+        assert_eq!(book.snapshot().state, TradingState::Trading);
+
+        book.set_trading_state(TradingState::Halted, 1);
+        let snapshot = book.snapshot();
+        assert_eq!(snapshot.state, TradingState::Halted);
+        assert_eq!(snapshot.timestamp, 1);
+        assert_eq!(snapshot.data, vec![1000, 100, -1, -1]);
+    }
+
     #[test]
     fn errors_if_shares_exceed_available() {
-        let mut book = OrderBook::new("01/01/2015".to_string(), "XYZ".to_string(), 3);
+        let mut book = OrderBook::new("01/01/2015".into(), "XYZ".parse().unwrap(), 3);
 
         book.add_order(Side::Buy, 1000, 100, 0);
         assert!(book.bids().contains_key(&1000));
@@ -0,0 +1,427 @@
+//! Processing of the messages read by a `Reader`.
+//!
+//! A `Processor` is called back for each message, e.g. to update order books, and produces the
+//! rows (`Output`) to write for it. A `Pipeline` passes every message through a processor, or a
+//! chain of them given as a tuple, and writes the rows into a `Sink` such as a `Writer`. The
+//! command line tool runs `(OrderBooks, Rows)`: an order book snapshot after each order update,
+//! followed by a row for each message.
+
+use std::{error, vec::Drain};
+
+mod order_books;
+mod rows;
+
+pub use order_books::OrderBooks;
+pub use rows::Rows;
+
+use crate::{
+    error::{Error, Result},
+    message::{
+        AddOrder, BrokenTrade, CancelOrder, CrossTrade, DLCRMessage, DeleteOrder,
+        DirectListingCapitalRaise, ExecuteOrder, IPOQuotingMessage, IPOQuotingPeriod,
+        LULDAuctionCollar, LULDCollarMessage, MWCBDeclineLevel, MWCBMessage, MWCBStatus,
+        MarketParticipantMessage, MarketParticipantPosition, Message, NOIIMessage,
+        NetOrderImbalanceIndicator, OperationalHalt, OperationalHaltMessage, OrderMessage,
+        RPIIMessage, RegSHORestriction, RetailPriceImprovement, StockDirectory, SystemEvent, Trade,
+        TradeMessage, TradingAction,
+    },
+    orderbook::OrderBookSnapshot,
+    reader::{Messages, Reader},
+    writer::{Flush, Writer},
+};
+
+/// A row to write for a message.
+#[derive(Debug)]
+pub enum Output {
+    OrderMessage(OrderMessage),
+    Snapshot(OrderBookSnapshot),
+    TradeMessage(TradeMessage),
+    NOIIMessage(NOIIMessage),
+    MarketParticipantMessage(MarketParticipantMessage),
+    MWCBMessage(MWCBMessage),
+    IPOQuotingMessage(IPOQuotingMessage),
+    LULDCollarMessage(LULDCollarMessage),
+    OperationalHaltMessage(OperationalHaltMessage),
+    RPIIMessage(RPIIMessage),
+    DLCRMessage(DLCRMessage),
+}
+
+/// Where the rows produced by a pipeline go.
+pub trait Sink {
+    fn write(
+        &mut self,
+        output: Output,
+    ) -> std::result::Result<(), Box<dyn error::Error + Send + Sync>>;
+}
+
+impl<T: Flush> Sink for Writer<T> {
+    fn write(
+        &mut self,
+        output: Output,
+    ) -> std::result::Result<(), Box<dyn error::Error + Send + Sync>> {
+        match output {
+            Output::OrderMessage(message) => self.write_order_message(message),
+            Output::Snapshot(snapshot) => self.write_snapshot(snapshot),
+            Output::TradeMessage(message) => self.write_trade_message(message),
+            Output::NOIIMessage(message) => self.write_noii_message(message),
+            Output::MarketParticipantMessage(message) => {
+                self.write_market_participant_message(message)
+            }
+            Output::MWCBMessage(message) => self.write_mwcb_message(message),
+            Output::IPOQuotingMessage(message) => self.write_ipo_quoting_message(message),
+            Output::LULDCollarMessage(message) => self.write_luld_collar_message(message),
+            Output::OperationalHaltMessage(message) => self.write_operational_halt_message(message),
+            Output::RPIIMessage(message) => self.write_rpii_message(message),
+            Output::DLCRMessage(message) => self.write_dlcr_message(message),
+        }
+    }
+}
+
+// Keeps the rows in memory
+impl Sink for Vec<Output> {
+    fn write(
+        &mut self,
+        output: Output,
+    ) -> std::result::Result<(), Box<dyn error::Error + Send + Sync>> {
+        self.push(output);
+        Ok(())
+    }
+}
+
+/// Callbacks for the messages read by a `Reader`, which is passed along for its state (e.g. the
+/// stock directory). Each callback may push rows to `outputs`, after those of the processors
+/// before it in a chain, and does nothing by default.
+pub trait Processor {
+    /// Passes a message to the callback for its type.
+    ///
+    /// This is the only method called by a `Pipeline` and chains of processors, so wrappers
+    /// (e.g. to time another processor) only need to override it.
+    fn process(&mut self, message: &Message, reader: &Reader, outputs: &mut Vec<Output>) {
+        match message {
+            Message::SystemEvent(m) => self.on_system_event(m, reader, outputs),
+            Message::MWCBDeclineLevel(m) => self.on_mwcb_decline_level(m, reader, outputs),
+            Message::MWCBStatus(m) => self.on_mwcb_status(m, reader, outputs),
+            Message::StockDirectory(m) => self.on_stock_directory(m, reader, outputs),
+            Message::TradingAction(m) => self.on_trading_action(m, reader, outputs),
+            Message::RegSHORestriction(m) => self.on_reg_sho(m, reader, outputs),
+            Message::MarketParticipantPosition(m) => self.on_market_participant(m, reader, outputs),
+            Message::IPOQuotingPeriod(m) => self.on_ipo_quoting_period(m, reader, outputs),
+            Message::LULDAuctionCollar(m) => self.on_luld_auction_collar(m, reader, outputs),
+            Message::OperationalHalt(m) => self.on_operational_halt(m, reader, outputs),
+            Message::AddOrder(m) => self.on_add(m, reader, outputs),
+            Message::ExecuteOrder(m) => self.on_execute(m, reader, outputs),
+            Message::CancelOrder(m) => self.on_cancel(m, reader, outputs),
+            Message::DeleteOrder(m) => self.on_delete(m, reader, outputs),
+            Message::Trade(m) => self.on_trade(m, reader, outputs),
+            Message::CrossTrade(m) => self.on_cross_trade(m, reader, outputs),
+            Message::BrokenTrade(m) => self.on_broken_trade(m, reader, outputs),
+            Message::NetOrderImbalanceIndicator(m) => self.on_noii(m, reader, outputs),
+            Message::RetailPriceImprovement(m) => self.on_rpii(m, reader, outputs),
+            Message::DirectListingCapitalRaise(m) => self.on_dlcr(m, reader, outputs),
+        }
+    }
+
+    fn on_system_event(&mut self, _m: &SystemEvent, _r: &Reader, _outputs: &mut Vec<Output>) {}
+
+    fn on_mwcb_decline_level(
+        &mut self,
+        _m: &MWCBDeclineLevel,
+        _r: &Reader,
+        _outputs: &mut Vec<Output>,
+    ) {
+    }
+
+    fn on_mwcb_status(&mut self, _m: &MWCBStatus, _r: &Reader, _outputs: &mut Vec<Output>) {}
+
+    fn on_stock_directory(&mut self, _m: &StockDirectory, _r: &Reader, _outputs: &mut Vec<Output>) {
+    }
+
+    fn on_trading_action(&mut self, _m: &TradingAction, _r: &Reader, _outputs: &mut Vec<Output>) {}
+
+    fn on_reg_sho(&mut self, _m: &RegSHORestriction, _r: &Reader, _outputs: &mut Vec<Output>) {}
+
+    fn on_market_participant(
+        &mut self,
+        _m: &MarketParticipantPosition,
+        _r: &Reader,
+        _outputs: &mut Vec<Output>,
+    ) {
+    }
+
+    fn on_ipo_quoting_period(
+        &mut self,
+        _m: &IPOQuotingPeriod,
+        _r: &Reader,
+        _outputs: &mut Vec<Output>,
+    ) {
+    }
+
+    fn on_luld_auction_collar(
+        &mut self,
+        _m: &LULDAuctionCollar,
+        _r: &Reader,
+        _outputs: &mut Vec<Output>,
+    ) {
+    }
+
+    fn on_operational_halt(
+        &mut self,
+        _m: &OperationalHalt,
+        _r: &Reader,
+        _outputs: &mut Vec<Output>,
+    ) {
+    }
+
+    fn on_add(&mut self, _m: &AddOrder, _r: &Reader, _outputs: &mut Vec<Output>) {}
+
+    fn on_execute(&mut self, _m: &ExecuteOrder, _r: &Reader, _outputs: &mut Vec<Output>) {}
+
+    fn on_cancel(&mut self, _m: &CancelOrder, _r: &Reader, _outputs: &mut Vec<Output>) {}
+
+    fn on_delete(&mut self, _m: &DeleteOrder, _r: &Reader, _outputs: &mut Vec<Output>) {}
+
+    fn on_trade(&mut self, _m: &Trade, _r: &Reader, _outputs: &mut Vec<Output>) {}
+
+    fn on_cross_trade(&mut self, _m: &CrossTrade, _r: &Reader, _outputs: &mut Vec<Output>) {}
+
+    fn on_broken_trade(&mut self, _m: &BrokenTrade, _r: &Reader, _outputs: &mut Vec<Output>) {}
+
+    fn on_noii(
+        &mut self,
+        _m: &NetOrderImbalanceIndicator,
+        _r: &Reader,
+        _outputs: &mut Vec<Output>,
+    ) {
+    }
+
+    fn on_rpii(&mut self, _m: &RetailPriceImprovement, _r: &Reader, _outputs: &mut Vec<Output>) {}
+
+    fn on_dlcr(&mut self, _m: &DirectListingCapitalRaise, _r: &Reader, _outputs: &mut Vec<Output>) {
+    }
+}
+
+// A chain of processors, called in order
+impl<A: Processor, B: Processor> Processor for (A, B) {
+    fn process(&mut self, message: &Message, reader: &Reader, outputs: &mut Vec<Output>) {
+        self.0.process(message, reader, outputs);
+        self.1.process(message, reader, outputs);
+    }
+}
+
+impl<P: Processor + ?Sized> Processor for Box<P> {
+    fn process(&mut self, message: &Message, reader: &Reader, outputs: &mut Vec<Output>) {
+        (**self).process(message, reader, outputs);
+    }
+}
+
+/// Passes messages through a processor (or a chain of them) and collects the rows it produces.
+pub struct Pipeline<P> {
+    processor: P,
+    outputs: Vec<Output>,
+}
+
+impl<P: Processor> Pipeline<P> {
+    pub fn new(processor: P) -> Self {
+        Self {
+            processor,
+            outputs: Vec::new(),
+        }
+    }
+
+    pub fn processor(&self) -> &P {
+        &self.processor
+    }
+
+    pub fn processor_mut(&mut self) -> &mut P {
+        &mut self.processor
+    }
+
+    /// Passes a message read by `reader` through the processor, returning the rows produced for
+    /// it (rows that are not consumed are dropped).
+    pub fn process(&mut self, message: &Message, reader: &Reader) -> Drain<'_, Output> {
+        self.outputs.clear();
+        self.processor.process(message, reader, &mut self.outputs);
+        self.outputs.drain(..)
+    }
+
+    /// Passes every message through the processor and writes the rows produced into `sink`,
+    /// until the end of the input or the first error.
+    pub fn run<T, S: Sink>(&mut self, messages: &mut Messages<T>, sink: &mut S) -> Result<()> {
+        while let Some(message) = messages.next() {
+            let message = message?;
+            for output in self.process(&message, messages.reader()) {
+                sink.write(output).map_err(Error::Sink)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashSet,
+        io::{self, Cursor},
+    };
+
+    use super::*;
+    use crate::message::{test_helpers::message_builders::*, Side, Ticker, TradingState, Version};
+
+    fn messages_v41() -> Cursor<Vec<u8>> {
+        create_message_sequence(vec![
+            timestamp_v41(34200),
+            add_order_v41(0, 1, Side::Buy, 100, "A", 1000),
+            add_order_v41(1, 2, Side::Sell, 200, "B", 2000),
+            execute_order_v41(2, 1, 40),
+            delete_order_v41(3, 2),
+        ])
+    }
+
+    // Counts the orders added and executed
+    #[derive(Default)]
+    struct Counter {
+        added: usize,
+        executed: u32,
+    }
+
+    impl Processor for Counter {
+        fn on_add(&mut self, _m: &AddOrder, _r: &Reader, _outputs: &mut Vec<Output>) {
+            self.added += 1;
+        }
+
+        fn on_execute(&mut self, m: &ExecuteOrder, _r: &Reader, _outputs: &mut Vec<Output>) {
+            self.executed += m.shares();
+        }
+    }
+
+    #[test]
+    fn dispatches_messages_to_callbacks() {
+        let reader = Reader::new(Version::V41, HashSet::from([ticker("*")]));
+        let mut messages = reader.messages(messages_v41());
+        let mut pipeline = Pipeline::new(Counter::default());
+        let mut rows: Vec<Output> = Vec::new();
+        pipeline.run(&mut messages, &mut rows).unwrap();
+
+        assert_eq!(pipeline.processor().added, 2);
+        assert_eq!(pipeline.processor().executed, 40);
+        assert!(rows.is_empty());
+    }
+
+    #[test]
+    fn chains_processors_in_order() {
+        let tickers: HashSet<Ticker> = HashSet::from([ticker("A")]);
+        let processor = (
+            OrderBooks::new(&tickers, "2024-01-02", 5),
+            Rows::new("2024-01-02"),
+        );
+        let mut pipeline = Pipeline::new((Counter::default(), processor));
+        let mut messages = Reader::new(Version::V41, tickers).messages(messages_v41());
+        let mut rows: Vec<Output> = Vec::new();
+        pipeline.run(&mut messages, &mut rows).unwrap();
+
+        // A snapshot precedes the row of each order update
+        assert_eq!(rows.len(), 4);
+        assert!(matches!(&rows[0], Output::Snapshot(s) if s.data[..2] == [1000, 100]));
+        assert!(matches!(&rows[1], Output::OrderMessage(_)));
+        assert!(matches!(&rows[2], Output::Snapshot(s) if s.data[..2] == [1000, 60]));
+        assert!(matches!(&rows[3], Output::OrderMessage(_)));
+
+        let (counter, (order_books, _)) = pipeline.processor();
+        assert_eq!(counter.added, 1);
+        let order_book = order_books.get(&ticker("A")).unwrap();
+        assert_eq!(order_book.bids()[&1000], 60);
+    }
+
+    #[test]
+    fn snapshots_changes_of_trading_state() {
+        let tickers = HashSet::from([ticker("A")]);
+        let mut messages =
+            Reader::new(Version::V41, tickers.clone()).messages(create_message_sequence(vec![
+                timestamp_v41(34200),
+                trading_action_v41(0, "A", 'H', "IPO1"),
+                trading_action_v41(1, "A", 'H', "IPO1"),
+                trading_action_v41(2, "B", 'H', "IPO1"),
+                trading_action_v41(3, "A", 'T', "    "),
+            ]));
+        let mut pipeline = Pipeline::new(OrderBooks::new(&tickers, "2024-01-02", 5));
+        let mut rows: Vec<Output> = Vec::new();
+        pipeline.run(&mut messages, &mut rows).unwrap();
+
+        // Repeating the current state changes nothing
+        let states: Vec<_> = rows
+            .iter()
+            .map(|row| match row {
+                Output::Snapshot(s) => (s.timestamp, s.state),
+                _ => panic!("expected a snapshot, got {:?}", row),
+            })
+            .collect();
+        assert_eq!(
+            states,
+            [
+                (34_200_000_000_000, TradingState::Halted),
+                (34_200_000_000_003, TradingState::Trading)
+            ]
+        );
+    }
+
+    #[test]
+    fn returns_rows_of_a_message() {
+        let tickers = HashSet::from([ticker("*")]);
+        let mut reader = Reader::new(Version::V41, tickers);
+        let mut pipeline = Pipeline::new(Box::new(Rows::new("2024-01-02")) as Box<dyn Processor>);
+        let mut buffer = messages_v41();
+        let message = reader.extract_message(&mut buffer).unwrap();
+
+        let rows = pipeline.process(&message, &reader).collect::<Vec<_>>();
+        assert!(matches!(&rows[..], [Output::OrderMessage(m)] if &**m.date() == "2024-01-02"));
+    }
+    // Refuses every row
+    struct Full;
+
+    impl Sink for Full {
+        fn write(
+            &mut self,
+            _output: Output,
+        ) -> std::result::Result<(), Box<dyn error::Error + Send + Sync>> {
+            Err(io::Error::new(io::ErrorKind::StorageFull, "no space left").into())
+        }
+    }
+
+    #[test]
+    fn keeps_errors_of_the_sink() {
+        let reader = Reader::new(Version::V41, HashSet::from([ticker("*")]));
+        let mut messages = reader.messages(messages_v41());
+        let mut pipeline = Pipeline::new(Rows::new("2024-01-02"));
+        let err = pipeline.run(&mut messages, &mut Full).unwrap_err();
+
+        assert_eq!(err.kind(), io::ErrorKind::StorageFull);
+        let Error::Sink(source) = err else {
+            panic!("expected a sink error, got {:?}", err);
+        };
+        assert_eq!(source.to_string(), "no space left");
+    }
+
+    #[test]
+    fn records_updates_that_do_not_match_the_book() {
+        let tickers = HashSet::from([ticker("A")]);
+        let mut reader = Reader::new(Version::V41, tickers.clone());
+        let mut pipeline = Pipeline::new(OrderBooks::new(&tickers, "2024-01-02", 5));
+        let mut buffer = messages_v41();
+
+        // The book misses the order added first, so its execution cannot be applied
+        reader.extract_message(&mut buffer).unwrap();
+        let message = reader.extract_message(&mut buffer).unwrap();
+        assert_eq!(pipeline.process(&message, &reader).count(), 0);
+
+        let diagnostics = pipeline.processor().diagnostics();
+        assert_eq!(diagnostics.len(), 1);
+        assert!(matches!(
+            diagnostics[0],
+            Error::LevelUnderflow {
+                price: 1000,
+                shares: 40,
+                resting: 0
+            }
+        ));
+    }
+}
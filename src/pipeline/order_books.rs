@@ -0,0 +1,94 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+use super::{Output, Processor};
+use crate::{
+    constants::EVERY_TICKER,
+    error::Error,
+    message::{AddOrder, CancelOrder, DeleteOrder, ExecuteOrder, Ticker, TradingAction},
+    orderbook::OrderBook,
+    reader::Reader,
+};
+
+/// Keeps the order book of each ticker up to date, producing a snapshot after every update and
+/// every change of trading state.
+///
+/// Updates that do not match the book (e.g. removing more shares than are resting at a price)
+/// produce no snapshot, and are recorded in the diagnostics instead.
+pub struct OrderBooks {
+    books: HashMap<Ticker, OrderBook>,
+    diagnostics: Vec<Error>, // Errors of the updates that failed so far
+}
+
+impl OrderBooks {
+    /// Creates an empty book of `depth` levels for each of `tickers` (except the wildcard).
+    pub fn new(tickers: &HashSet<Ticker>, date: &str, depth: usize) -> Self {
+        let date: Arc<str> = date.into();
+        let books = tickers
+            .iter()
+            .filter(|&&ticker| ticker != EVERY_TICKER)
+            .map(|&ticker| (ticker, OrderBook::new(date.clone(), ticker, depth)))
+            .collect();
+        Self {
+            books,
+            diagnostics: Vec::new(),
+        }
+    }
+
+    pub fn get(&self, ticker: &Ticker) -> Option<&OrderBook> {
+        self.books.get(ticker)
+    }
+
+    /// Returns the errors of the updates that could not be applied to a book, in input order.
+    pub fn diagnostics(&self) -> &[Error] {
+        &self.diagnostics
+    }
+}
+
+impl Processor for OrderBooks {
+    fn on_add(&mut self, m: &AddOrder, _r: &Reader, outputs: &mut Vec<Output>) {
+        if let Some(book) = self.books.get_mut(m.ticker()) {
+            book.add_order(*m.side(), *m.price(), *m.shares(), *m.nanoseconds());
+            outputs.push(Output::Snapshot(book.snapshot()));
+        }
+    }
+
+    fn on_execute(&mut self, m: &ExecuteOrder, _r: &Reader, outputs: &mut Vec<Output>) {
+        if let Some(book) = self.books.get_mut(m.ticker()) {
+            match book.execute_order(*m.side(), *m.price(), *m.shares(), *m.nanoseconds()) {
+                Ok(()) => outputs.push(Output::Snapshot(book.snapshot())),
+                Err(e) => self.diagnostics.push(e),
+            }
+        }
+    }
+
+    fn on_cancel(&mut self, m: &CancelOrder, _r: &Reader, outputs: &mut Vec<Output>) {
+        if let Some(book) = self.books.get_mut(m.ticker()) {
+            match book.remove_order(*m.side(), *m.price(), *m.shares(), *m.nanoseconds()) {
+                Ok(()) => outputs.push(Output::Snapshot(book.snapshot())),
+                Err(e) => self.diagnostics.push(e),
+            }
+        }
+    }
+
+    fn on_delete(&mut self, m: &DeleteOrder, _r: &Reader, outputs: &mut Vec<Output>) {
+        if let Some(book) = self.books.get_mut(m.ticker()) {
+            match book.remove_order(*m.side(), *m.price(), *m.shares(), *m.nanoseconds()) {
+                Ok(()) => outputs.push(Output::Snapshot(book.snapshot())),
+                Err(e) => self.diagnostics.push(e),
+            }
+        }
+    }
+
+    // Trading actions that repeat the current state leave the book as it is
+    fn on_trading_action(&mut self, m: &TradingAction, _r: &Reader, outputs: &mut Vec<Output>) {
+        if let Some(book) = self.books.get_mut(m.ticker()) {
+            if book.trading_state() != *m.trading_state() {
+                book.set_trading_state(*m.trading_state(), *m.nanoseconds());
+                outputs.push(Output::Snapshot(book.snapshot()));
+            }
+        }
+    }
+}
@@ -0,0 +1,137 @@
+use std::sync::Arc;
+
+use super::{Output, Processor};
+use crate::{
+    message::{
+        AddOrder, BrokenTrade, CancelOrder, CrossTrade, DeleteOrder, DirectListingCapitalRaise,
+        ExecuteOrder, IPOQuotingPeriod, IntoDLCRMessage, IntoIPOQuotingMessage,
+        IntoLULDCollarMessage, IntoMWCBMessage, IntoMarketParticipantMessage, IntoNOIIMessage,
+        IntoOperationalHaltMessage, IntoOrderMessage, IntoRPIIMessage, IntoTradeMessage,
+        LULDAuctionCollar, MWCBDeclineLevel, MWCBStatus, MarketParticipantPosition,
+        NetOrderImbalanceIndicator, OperationalHalt, RetailPriceImprovement, Ticker, Trade,
+    },
+    reader::Reader,
+};
+
+/// Produces the row of each message that has an output schema, dated `date`.
+///
+/// Order and trade rows are marked with whether the short sale price test is in effect for their
+/// stock. System events, stock directory, trading action and Reg SHO messages produce no rows.
+pub struct Rows {
+    date: Arc<str>, // Shared by every row rather than copied into each
+}
+
+impl Rows {
+    pub fn new(date: &str) -> Self {
+        Self { date: date.into() }
+    }
+
+    fn order<M: IntoOrderMessage>(&self, m: M, ticker: &Ticker, reader: &Reader) -> Output {
+        let mut order_message = m.into_order_message(self.date.clone());
+        order_message.set_short_sale_restricted(reader.short_sale_restricted(ticker));
+        Output::OrderMessage(order_message)
+    }
+
+    fn trade<M: IntoTradeMessage>(&self, m: M, ticker: &Ticker, reader: &Reader) -> Output {
+        let mut trade_message = m.into_trade_message(self.date.clone());
+        trade_message.set_short_sale_restricted(reader.short_sale_restricted(ticker));
+        Output::TradeMessage(trade_message)
+    }
+}
+
+impl Processor for Rows {
+    fn on_add(&mut self, m: &AddOrder, r: &Reader, outputs: &mut Vec<Output>) {
+        outputs.push(self.order(m, m.ticker(), r));
+    }
+
+    fn on_execute(&mut self, m: &ExecuteOrder, r: &Reader, outputs: &mut Vec<Output>) {
+        outputs.push(self.order(m, m.ticker(), r));
+    }
+
+    fn on_cancel(&mut self, m: &CancelOrder, r: &Reader, outputs: &mut Vec<Output>) {
+        outputs.push(self.order(m, m.ticker(), r));
+    }
+
+    fn on_delete(&mut self, m: &DeleteOrder, r: &Reader, outputs: &mut Vec<Output>) {
+        outputs.push(self.order(m, m.ticker(), r));
+    }
+
+    fn on_trade(&mut self, m: &Trade, r: &Reader, outputs: &mut Vec<Output>) {
+        outputs.push(self.trade(m, m.ticker(), r));
+    }
+
+    fn on_cross_trade(&mut self, m: &CrossTrade, r: &Reader, outputs: &mut Vec<Output>) {
+        outputs.push(self.trade(m, m.ticker(), r));
+    }
+
+    // Broken trades only identify the trade by its match number, so their stock is unknown
+    fn on_broken_trade(&mut self, m: &BrokenTrade, _r: &Reader, outputs: &mut Vec<Output>) {
+        let trade_message = m.into_trade_message(self.date.clone());
+        outputs.push(Output::TradeMessage(trade_message));
+    }
+
+    fn on_noii(&mut self, m: &NetOrderImbalanceIndicator, _r: &Reader, outputs: &mut Vec<Output>) {
+        let noii_message = m.into_noii_message(self.date.clone());
+        outputs.push(Output::NOIIMessage(noii_message));
+    }
+
+    fn on_market_participant(
+        &mut self,
+        m: &MarketParticipantPosition,
+        _r: &Reader,
+        outputs: &mut Vec<Output>,
+    ) {
+        let message = m.into_market_participant_message(self.date.clone());
+        outputs.push(Output::MarketParticipantMessage(message));
+    }
+
+    fn on_mwcb_decline_level(
+        &mut self,
+        m: &MWCBDeclineLevel,
+        _r: &Reader,
+        outputs: &mut Vec<Output>,
+    ) {
+        let mwcb_message = m.into_mwcb_message(self.date.clone());
+        outputs.push(Output::MWCBMessage(mwcb_message));
+    }
+
+    fn on_mwcb_status(&mut self, m: &MWCBStatus, _r: &Reader, outputs: &mut Vec<Output>) {
+        let mwcb_message = m.into_mwcb_message(self.date.clone());
+        outputs.push(Output::MWCBMessage(mwcb_message));
+    }
+
+    fn on_ipo_quoting_period(
+        &mut self,
+        m: &IPOQuotingPeriod,
+        _r: &Reader,
+        outputs: &mut Vec<Output>,
+    ) {
+        let ipo_quoting_message = m.into_ipo_quoting_message(self.date.clone());
+        outputs.push(Output::IPOQuotingMessage(ipo_quoting_message));
+    }
+
+    fn on_luld_auction_collar(
+        &mut self,
+        m: &LULDAuctionCollar,
+        _r: &Reader,
+        outputs: &mut Vec<Output>,
+    ) {
+        let luld_collar_message = m.into_luld_collar_message(self.date.clone());
+        outputs.push(Output::LULDCollarMessage(luld_collar_message));
+    }
+
+    fn on_operational_halt(&mut self, m: &OperationalHalt, _r: &Reader, outputs: &mut Vec<Output>) {
+        let halt_message = m.into_operational_halt_message(self.date.clone());
+        outputs.push(Output::OperationalHaltMessage(halt_message));
+    }
+
+    fn on_rpii(&mut self, m: &RetailPriceImprovement, _r: &Reader, outputs: &mut Vec<Output>) {
+        let rpii_message = m.into_rpii_message(self.date.clone());
+        outputs.push(Output::RPIIMessage(rpii_message));
+    }
+
+    fn on_dlcr(&mut self, m: &DirectListingCapitalRaise, _r: &Reader, outputs: &mut Vec<Output>) {
+        let dlcr_message = m.into_dlcr_message(self.date.clone());
+        outputs.push(Output::DLCRMessage(dlcr_message));
+    }
+}
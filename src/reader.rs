@@ -1,35 +1,202 @@
 use std::{
-    collections::{HashSet, VecDeque},
-    io::{Read, Result, Seek, SeekFrom},
+    collections::{HashMap, HashSet, VecDeque},
+    io::{Cursor, ErrorKind, Read, Seek, SeekFrom},
+    mem,
+    str::FromStr,
 };
 
+use strum_macros::Display;
+
 use crate::{
     buffer::Peek,
     constants::EVERY_TICKER,
+    error::{Error, Result},
+    index::IndexEntry,
     message::{
-        peek_kind, peek_refno, peek_ticker, read_kind, read_replace_order, read_seconds, read_size,
-        AddOrder, BrokenTrade, CancelOrder, Context, CrossTrade, DeleteOrder, ExecuteOrder,
-        Message, NetOrderImbalanceIndicator, ReadMessage, SystemEvent, Trade, Version,
+        peek_kind, peek_locate, peek_refno, peek_ticker, read_kind, read_line, read_replace_order,
+        read_seconds, read_size, AddOrder, BrokenTrade, CancelOrder, Context, CrossTrade,
+        DeleteOrder, DirectListingCapitalRaise, ExecuteOrder, IPOQuotingPeriod, LULDAuctionCollar,
+        MWCBDeclineLevel, MWCBStatus, MarketParticipantPosition, Message,
+        NetOrderImbalanceIndicator, OperationalHalt, ReadMessage, RegSHORestriction,
+        RetailPriceImprovement, StockDirectory, SystemEvent, Ticker, Trade, TradingAction,
+        TradingState, Version,
     },
 };
 
+mod messages;
+
+pub use messages::Messages;
+
+/// How a `Reader` handles a message it cannot interpret (e.g. a truncated or corrupt record).
+#[derive(Debug, Default, PartialEq, Clone, Copy, Display)]
+#[strum(serialize_all = "lowercase")]
+pub enum Policy {
+    /// Return the error.
+    #[default]
+    Strict,
+    /// Skip the message, recording the error in the diagnostics for the caller to report.
+    Warn,
+    /// Skip the message, recording the error in the diagnostics.
+    Skip,
+}
+
+impl FromStr for Policy {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "strict" => Ok(Policy::Strict),
+            "warn" => Ok(Policy::Warn),
+            "skip" => Ok(Policy::Skip),
+            _ => Err(Error::InvalidField {
+                field: "error policy",
+                value: s.to_string(),
+            }),
+        }
+    }
+}
+
 pub struct Reader {
     version: Version,
-    tickers: HashSet<String>,
+    tickers: HashSet<Ticker>,
     context: Context,
     buf: VecDeque<Message>, // To handle the case where multiple messages are parsed at once
+    locates: Option<Vec<Option<bool>>>, // Whether each stock locate code is watched, if filtering
+    offset: u64,            // Byte offset of the next message
+    sequence: u64,          // Number of messages (including 'T' messages) read before the next one
+    policy: Policy,
+    diagnostics: Vec<Error>, // Errors of the messages skipped so far
+    frame: Vec<u8>,          // Buffer the messages of non-seekable input are read into
 }
 
 impl Reader {
-    pub fn new(version: Version, tickers: HashSet<String>) -> Self {
+    pub fn new(version: Version, tickers: HashSet<Ticker>) -> Self {
         Self {
             version,
             tickers,
             context: Context::new(),
             buf: VecDeque::new(),
+            locates: None,
+            offset: 0,
+            sequence: 0,
+            policy: Policy::Strict,
+            diagnostics: Vec::new(),
+            frame: Vec::new(),
         }
     }
 
+    /// Sets how messages that cannot be interpreted are handled.
+    ///
+    /// Under a lenient policy (`Warn` or `Skip`), such a message is skipped and reading resumes
+    /// with the next one: each binary message is checked to be complete before it is parsed, so
+    /// that the next message can be found by its length prefix whatever was wrong with the last.
+    /// A truncated message ends the input.
+    pub fn with_policy(mut self, policy: Policy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Returns the errors of the messages skipped under a lenient policy, in input order.
+    pub fn diagnostics(&self) -> &[Error] {
+        &self.diagnostics
+    }
+
+    /// Filters Version 5.0 messages by their stock locate code.
+    ///
+    /// Once the stock directory message of a stock has been read, every later message about it is
+    /// kept or skipped based on its two-byte locate code alone, without peeking at its ticker or
+    /// looking up its reference number. Messages about stocks whose directory entry has not been
+    /// read yet are filtered as usual. Has no effect for other versions or when reading every
+    /// ticker.
+    pub fn with_locate_filter(mut self, enabled: bool) -> Self {
+        let applicable = self.version == Version::V50 && !self.tickers.contains(&EVERY_TICKER);
+        self.locates = (enabled && applicable).then(|| vec![None; 1 << 16]);
+        self
+    }
+
+    /// Returns the stock directory entries collected so far, keyed by ticker.
+    pub fn directory(&self) -> &HashMap<Ticker, StockDirectory> {
+        &self.context.directory
+    }
+
+    /// Returns the most recent stock directory entry for `ticker`, if any.
+    pub fn stock_directory(&self, ticker: &Ticker) -> Option<&StockDirectory> {
+        self.context.directory.get(ticker)
+    }
+
+    /// Returns the current trading state of `ticker`, if a trading action has been seen for it.
+    pub fn trading_state(&self, ticker: &Ticker) -> Option<TradingState> {
+        self.context.trading_states.get(ticker).copied()
+    }
+
+    /// Returns whether the short sale price test is in effect for `ticker`, if a Reg SHO message
+    /// has been seen for it.
+    pub fn short_sale_restricted(&self, ticker: &Ticker) -> Option<bool> {
+        self.context
+            .reg_sho_actions
+            .get(ticker)
+            .map(|action| action.is_restricted())
+    }
+
+    /// Returns the market participant registry collected so far, keyed by `(mpid, ticker)`.
+    pub fn market_participants(&self) -> &HashMap<(String, Ticker), MarketParticipantPosition> {
+        &self.context.participants
+    }
+
+    /// Returns the current registration of `mpid` in `ticker`, if any.
+    pub fn market_participant(
+        &self,
+        mpid: &str,
+        ticker: &Ticker,
+    ) -> Option<&MarketParticipantPosition> {
+        self.context.participants.get(&(mpid.to_string(), *ticker))
+    }
+
+    /// Moves `buffer` to the message of an index entry and restores the clock in effect there.
+    ///
+    /// Orders added before the entry are unknown to the reader, so updates to them are skipped
+    /// like those of unwatched orders.
+    pub fn seek<T: Seek>(&mut self, buffer: &mut T, entry: &IndexEntry) -> Result<u64> {
+        let position = buffer.seek(SeekFrom::Start(*entry.offset()))?;
+        self.resume_at(entry);
+        Ok(position)
+    }
+
+    /// Restores the clock in effect at an index entry, for input that is already positioned at
+    /// its message.
+    pub fn resume_at(&mut self, entry: &IndexEntry) {
+        self.context.clock = *entry.clock();
+        self.buf.clear();
+        self.set_position(*entry.offset(), *entry.message());
+    }
+
+    /// Sets the position reported by errors for the next message: its byte offset in the input
+    /// and the number of messages preceding it.
+    ///
+    /// The position advances as messages are read, so this is only needed when the input does
+    /// not start at the beginning of a file (e.g. when messages are fed one at a time).
+    pub fn set_position(&mut self, offset: u64, sequence: u64) {
+        self.offset = offset;
+        self.sequence = sequence;
+    }
+
+    /// Binds the reader to `buffer`, returning an iterator over its messages.
+    pub fn messages<T>(self, buffer: T) -> Messages<T>
+    where
+        T: Read + Seek + Peek,
+    {
+        Messages::new(self, buffer, Self::extract_message)
+    }
+
+    /// Binds the reader to a source that cannot seek, returning an iterator over its messages
+    /// (see `extract_streamed_message`).
+    pub fn streamed_messages<T>(self, buffer: T) -> Messages<T>
+    where
+        T: Read,
+    {
+        Messages::new(self, buffer, Self::extract_streamed_message)
+    }
+
     pub fn extract_message<T>(&mut self, buffer: &mut T) -> Result<Message>
     where
         T: Read + Seek + Peek,
@@ -38,45 +205,286 @@ impl Reader {
             return Ok(self.buf.pop_front().unwrap());
         }
 
+        if self.version.is_text() {
+            return self.extract_text_message(buffer);
+        }
+
         loop {
             let size = read_size(buffer)?;
-            let kind = peek_kind(buffer)?;
-
-            if kind == 'T' {
-                let _kind = read_kind(buffer)?;
-                let seconds = read_seconds(buffer)?;
-                self.context.update_clock(seconds);
-                continue;
-            }
+            let (offset, sequence) = self.advance(size);
 
-            let msg = match kind {
-                // system event message
-                'S' => self.parse_system_event(buffer)?,
-                // order messages
-                'A' | 'F' => self.parse_add_order(buffer)?,
-                'E' | 'C' => self.parse_execute_order(buffer)?,
-                'X' => self.parse_cancel_order(buffer)?,
-                'D' => self.parse_delete_order(buffer)?,
-                'U' => self.parse_replace_order(buffer)?,
-                // trade messages
-                'P' => self.parse_trade(buffer)?,
-                'Q' => self.parse_cross_trade(buffer)?,
-                'B' => self.parse_broken_trade(buffer)?,
-                // net order imbalance message
-                'I' => self.parse_noii(buffer)?,
-                _ => None,
+            // Under a lenient policy a bad frame is moved past as a whole, so it must be complete
+            let end = match self.policy {
+                Policy::Strict => None,
+                Policy::Warn | Policy::Skip => {
+                    if let Err(e) = buffer.peek(0, size as usize) {
+                        if e.kind() != ErrorKind::UnexpectedEof {
+                            return Err(Error::from(e).at(offset, sequence));
+                        }
+                        // Nothing follows a truncated message, so the input ends with it
+                        let size = size as usize;
+                        self.recover(Error::TruncatedMessage { size }.at(offset, sequence))?;
+                        return Err(e.into());
+                    }
+                    Some(buffer.stream_position()? + size as u64)
+                }
             };
 
-            match msg {
+            // Frames are parsed in place, so running out of bytes means the input was cut short
+            let message = match self.parse_frame(size, buffer) {
+                Ok(message) => message,
+                Err(e) => {
+                    let e = match e.kind() {
+                        ErrorKind::UnexpectedEof => Error::TruncatedMessage {
+                            size: size as usize,
+                        },
+                        _ => e,
+                    };
+                    let e = e.at(offset, sequence);
+                    match end {
+                        Some(end) => {
+                            self.recover(e)?;
+                            buffer.seek(SeekFrom::Start(end))?;
+                            None
+                        }
+                        None => return Err(e),
+                    }
+                }
+            };
+            match message {
                 Some(m) => return Ok(m),
                 None => {
-                    buffer.seek(SeekFrom::Current(size as i64))?;
                     buffer.peek(0, 1)?;
                 }
             }
         }
     }
 
+    // Parses the binary message at the current position of `buffer`, or moves past it if it is a
+    // timestamp or unwanted. A message whose length does not match its type is moved past before
+    // its error is returned, so that reading can go on with the next message.
+    fn parse_frame<T>(&mut self, size: u16, buffer: &mut T) -> Result<Option<Message>>
+    where
+        T: Read + Seek + Peek,
+    {
+        let kind = peek_kind(buffer)?;
+        if let Err(e) = self.check_size(kind, size as usize) {
+            buffer.seek(SeekFrom::Current(size as i64))?;
+            return Err(e);
+        }
+        if kind == 'T' {
+            let _kind = read_kind(buffer)?;
+            let seconds = read_seconds(buffer, &self.version)?;
+            self.context.update_clock(seconds);
+            return Ok(None);
+        }
+
+        let message = if self.skips_locate(buffer)? {
+            None
+        } else {
+            self.parse_message(kind, buffer)?
+        };
+        if message.is_none() {
+            buffer.seek(SeekFrom::Current(size as i64))?;
+        }
+        Ok(message)
+    }
+
+    // Checks the length of a binary message against the one its type has for the version
+    fn check_size(&self, kind: char, size: usize) -> Result<()> {
+        match self.version.message_size(kind) {
+            Some(expected) if expected != size => Err(Error::SizeMismatch {
+                kind,
+                expected: Some(expected),
+                found: size,
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    // Counts a binary message of `size` bytes as read, returning its position
+    fn advance(&mut self, size: u16) -> (u64, u64) {
+        let position = (self.offset, self.sequence);
+        self.offset += 2 + size as u64;
+        self.sequence += 1;
+        position
+    }
+
+    /// Extracts the next message from a source that cannot seek, such as standard input or a pipe.
+    ///
+    /// Binary messages are read one at a time into memory using their two-byte length prefix, so
+    /// only `Read` is required of `buffer`. Text records are read line by line as usual.
+    pub fn extract_streamed_message<T>(&mut self, buffer: &mut T) -> Result<Message>
+    where
+        T: Read,
+    {
+        if !self.buf.is_empty() {
+            return Ok(self.buf.pop_front().unwrap());
+        }
+
+        if self.version.is_text() {
+            return self.extract_text_message(buffer);
+        }
+
+        loop {
+            let size = read_size(buffer)?;
+            let (offset, sequence) = self.advance(size);
+            let mut frame = mem::take(&mut self.frame);
+            frame.resize(size as usize, 0);
+            if let Err(e) = buffer.read_exact(&mut frame) {
+                if e.kind() != ErrorKind::UnexpectedEof {
+                    return Err(Error::from(e).at(offset, sequence));
+                }
+                // Nothing follows a truncated message, so the input ends with it
+                let size = size as usize;
+                self.recover(Error::TruncatedMessage { size }.at(offset, sequence))?;
+                return Err(e.into());
+            }
+
+            // Running out of bytes within a frame means it is malformed, not that the input ended
+            let mut record = Cursor::new(frame);
+            let message = self.parse_record(&mut record);
+            let frame = record.into_inner();
+            let message = message.or_else(|e| {
+                let e = match e.kind() {
+                    ErrorKind::UnexpectedEof => Error::SizeMismatch {
+                        kind: frame.first().map_or('?', |&b| char::from(b)),
+                        expected: None,
+                        found: size as usize,
+                    },
+                    _ => e,
+                };
+                self.recover(e.at(offset, sequence)).map(|_| None)
+            });
+            self.frame = frame; // Reused for the next message
+            if let Some(m) = message? {
+                return Ok(m);
+            }
+        }
+    }
+
+    // Versions 2.0 and 3.0 are newline-terminated ASCII records, so each record is read into
+    // memory before parsing and unwanted records need no skipping.
+    fn extract_text_message<T>(&mut self, buffer: &mut T) -> Result<Message>
+    where
+        T: Read,
+    {
+        loop {
+            let offset = self.offset;
+            let mut record = read_line(buffer, &mut self.offset)?;
+            if record.is_empty() {
+                continue;
+            }
+            let sequence = self.sequence;
+            self.sequence += 1;
+
+            // Version 2.0 records lead with the timestamp: move the message type in front of it
+            // so that every version shares the same header layout.
+            if self.version == Version::V20 {
+                let header_size = self.version.header_size();
+                if record.len() < header_size {
+                    let error = Error::TruncatedMessage { size: header_size };
+                    self.recover(error.at(offset, sequence))?;
+                    continue;
+                }
+                record[..header_size].rotate_left(header_size - 1);
+            }
+
+            // Running out of bytes within a record means it is malformed, not that the file ended
+            let size = record.len();
+            let mut record = Cursor::new(record);
+            let message = self.parse_record(&mut record).or_else(|e| {
+                let e = match e.kind() {
+                    ErrorKind::UnexpectedEof => Error::SizeMismatch {
+                        kind: char::from(record.get_ref()[0]),
+                        expected: None,
+                        found: size,
+                    },
+                    _ => e,
+                };
+                self.recover(e.at(offset, sequence)).map(|_| None)
+            })?;
+            if let Some(m) = message {
+                return Ok(m);
+            }
+        }
+    }
+
+    // Returns `error` under the strict policy, or records it so that reading can go on with the
+    // next message
+    pub(crate) fn recover(&mut self, error: Error) -> Result<()> {
+        match self.policy {
+            Policy::Strict => return Err(error),
+            Policy::Warn | Policy::Skip => {}
+        }
+        self.diagnostics.push(error);
+        Ok(())
+    }
+
+    // Parses a message held in memory, returning `None` for timestamps and unwanted messages
+    fn parse_record(&mut self, record: &mut Cursor<Vec<u8>>) -> Result<Option<Message>> {
+        let kind = peek_kind(record)?;
+        self.check_size(kind, record.get_ref().len())?;
+        if kind == 'T' {
+            let _kind = read_kind(record)?;
+            let seconds = read_seconds(record, &self.version)?;
+            self.context.update_clock(seconds);
+            return Ok(None);
+        }
+        if !self.version.is_text() && self.skips_locate(record)? {
+            return Ok(None);
+        }
+        self.parse_message(kind, record)
+    }
+
+    // Whether the locate filter knows the message to be about an unwatched stock
+    fn skips_locate<T: Peek>(&self, buffer: &mut T) -> Result<bool> {
+        match &self.locates {
+            Some(locates) => Ok(locates[peek_locate(buffer)? as usize] == Some(false)),
+            None => Ok(false),
+        }
+    }
+
+    fn parse_message<T>(&mut self, kind: char, buffer: &mut T) -> Result<Option<Message>>
+    where
+        T: Read + Seek + Peek,
+    {
+        // Only system, order and trade messages are parsed for the text versions
+        let binary = !self.version.is_text();
+
+        let msg = match kind {
+            // system event message
+            'S' => self.parse_system_event(buffer)?,
+            'V' if self.version == Version::V50 => self.parse_mwcb_decline_level(buffer)?,
+            'W' if self.version == Version::V50 => self.parse_mwcb_status(buffer)?,
+            // stock related messages
+            'R' if binary => self.parse_stock_directory(buffer)?,
+            'H' if binary => self.parse_trading_action(buffer)?,
+            'Y' if binary => self.parse_reg_sho(buffer)?,
+            'L' if binary => self.parse_market_participant(buffer)?,
+            'K' if self.version == Version::V50 => self.parse_ipo_quoting_period(buffer)?,
+            'J' if self.version == Version::V50 => self.parse_luld_auction_collar(buffer)?,
+            'h' if self.version == Version::V50 => self.parse_operational_halt(buffer)?,
+            // order messages
+            'A' | 'F' => self.parse_add_order(buffer)?,
+            'E' | 'C' => self.parse_execute_order(buffer)?,
+            'X' => self.parse_cancel_order(buffer)?,
+            'D' => self.parse_delete_order(buffer)?,
+            'U' => self.parse_replace_order(buffer)?,
+            // trade messages
+            'P' => self.parse_trade(buffer)?,
+            'Q' => self.parse_cross_trade(buffer)?,
+            'B' => self.parse_broken_trade(buffer)?,
+            // net order imbalance message
+            'I' if binary => self.parse_noii(buffer)?,
+            'N' if self.version == Version::V50 => self.parse_rpii(buffer)?,
+            'O' if self.version == Version::V50 => self.parse_dlcr(buffer)?,
+            _ => None,
+        };
+
+        Ok(msg)
+    }
+
     fn parse_system_event<T>(&mut self, buffer: &mut T) -> Result<Option<Message>>
     where
         T: Read + Seek,
@@ -85,14 +493,139 @@ impl Reader {
         Ok(Some(Message::SystemEvent(data)))
     }
 
+    fn parse_mwcb_decline_level<T>(&mut self, buffer: &mut T) -> Result<Option<Message>>
+    where
+        T: Read + Seek,
+    {
+        // MWCB messages are market-wide, so we always parse them
+        let message = MWCBDeclineLevel::read(buffer, &self.version, &mut self.context)?;
+        Ok(Some(Message::MWCBDeclineLevel(message)))
+    }
+
+    fn parse_mwcb_status<T>(&mut self, buffer: &mut T) -> Result<Option<Message>>
+    where
+        T: Read + Seek,
+    {
+        // MWCB messages are market-wide, so we always parse them
+        let message = MWCBStatus::read(buffer, &self.version, &mut self.context)?;
+        Ok(Some(Message::MWCBStatus(message)))
+    }
+
+    fn parse_stock_directory<T>(&mut self, buffer: &mut T) -> Result<Option<Message>>
+    where
+        T: Read + Seek + Peek,
+    {
+        let ticker = peek_ticker(buffer, self.version.header_size(), &self.version)?;
+        let watched = self.tickers.contains(&ticker) || self.tickers.contains(&EVERY_TICKER);
+
+        // Directory messages map tickers to the locate codes of later messages
+        if let Some(locates) = self.locates.as_mut() {
+            locates[peek_locate(buffer)? as usize] = Some(watched);
+        }
+
+        if watched {
+            let message = StockDirectory::read(buffer, &self.version, &mut self.context)?;
+            Ok(Some(Message::StockDirectory(message)))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn parse_trading_action<T>(&mut self, buffer: &mut T) -> Result<Option<Message>>
+    where
+        T: Read + Seek + Peek,
+    {
+        let ticker = peek_ticker(buffer, self.version.header_size(), &self.version)?;
+
+        if self.tickers.contains(&ticker) || self.tickers.contains(&EVERY_TICKER) {
+            let message = TradingAction::read(buffer, &self.version, &mut self.context)?;
+            Ok(Some(Message::TradingAction(message)))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn parse_reg_sho<T>(&mut self, buffer: &mut T) -> Result<Option<Message>>
+    where
+        T: Read + Seek + Peek,
+    {
+        let ticker = peek_ticker(buffer, self.version.header_size(), &self.version)?;
+
+        if self.tickers.contains(&ticker) || self.tickers.contains(&EVERY_TICKER) {
+            let message = RegSHORestriction::read(buffer, &self.version, &mut self.context)?;
+            Ok(Some(Message::RegSHORestriction(message)))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn parse_market_participant<T>(&mut self, buffer: &mut T) -> Result<Option<Message>>
+    where
+        T: Read + Seek + Peek,
+    {
+        let at = self.version.header_size() + 4; // mpid
+        let ticker = peek_ticker(buffer, at, &self.version)?;
+
+        if self.tickers.contains(&ticker) || self.tickers.contains(&EVERY_TICKER) {
+            let message =
+                MarketParticipantPosition::read(buffer, &self.version, &mut self.context)?;
+            Ok(Some(Message::MarketParticipantPosition(message)))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn parse_ipo_quoting_period<T>(&mut self, buffer: &mut T) -> Result<Option<Message>>
+    where
+        T: Read + Seek + Peek,
+    {
+        let ticker = peek_ticker(buffer, self.version.header_size(), &self.version)?;
+
+        if self.tickers.contains(&ticker) || self.tickers.contains(&EVERY_TICKER) {
+            let message = IPOQuotingPeriod::read(buffer, &self.version, &mut self.context)?;
+            Ok(Some(Message::IPOQuotingPeriod(message)))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn parse_luld_auction_collar<T>(&mut self, buffer: &mut T) -> Result<Option<Message>>
+    where
+        T: Read + Seek + Peek,
+    {
+        let ticker = peek_ticker(buffer, self.version.header_size(), &self.version)?;
+
+        if self.tickers.contains(&ticker) || self.tickers.contains(&EVERY_TICKER) {
+            let message = LULDAuctionCollar::read(buffer, &self.version, &mut self.context)?;
+            Ok(Some(Message::LULDAuctionCollar(message)))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn parse_operational_halt<T>(&mut self, buffer: &mut T) -> Result<Option<Message>>
+    where
+        T: Read + Seek + Peek,
+    {
+        let ticker = peek_ticker(buffer, self.version.header_size(), &self.version)?;
+
+        if self.tickers.contains(&ticker) || self.tickers.contains(&EVERY_TICKER) {
+            let message = OperationalHalt::read(buffer, &self.version, &mut self.context)?;
+            Ok(Some(Message::OperationalHalt(message)))
+        } else {
+            Ok(None)
+        }
+    }
+
     fn parse_add_order<T>(&mut self, buffer: &mut T) -> Result<Option<Message>>
     where
         T: Read + Seek + Peek,
     {
-        let should_parse = if self.tickers.contains(EVERY_TICKER) {
+        let should_parse = if self.tickers.contains(&EVERY_TICKER) {
             true
         } else {
-            let ticker = peek_ticker(buffer, 18, &self.version)?;
+            let at = order_ticker_offset(&self.version);
+            let ticker = peek_ticker(buffer, at, &self.version)?;
             self.tickers.contains(&ticker)
         };
 
@@ -164,9 +697,10 @@ impl Reader {
     where
         T: Read + Seek + Peek,
     {
-        let ticker = peek_ticker(buffer, 18, &self.version)?;
+        let at = order_ticker_offset(&self.version);
+        let ticker = peek_ticker(buffer, at, &self.version)?;
 
-        if self.tickers.contains(&ticker) || self.tickers.contains(EVERY_TICKER) {
+        if self.tickers.contains(&ticker) || self.tickers.contains(&EVERY_TICKER) {
             let message = Trade::read(buffer, &self.version, &mut self.context)?;
             Ok(Some(Message::Trade(message)))
         } else {
@@ -178,9 +712,10 @@ impl Reader {
     where
         T: Read + Seek + Peek,
     {
-        let ticker = peek_ticker(buffer, 13, &self.version)?;
+        let at = self.version.header_size() + self.version.long_shares_size();
+        let ticker = peek_ticker(buffer, at, &self.version)?;
 
-        if self.tickers.contains(&ticker) || self.tickers.contains(EVERY_TICKER) {
+        if self.tickers.contains(&ticker) || self.tickers.contains(&EVERY_TICKER) {
             let message = CrossTrade::read(buffer, &self.version, &mut self.context)?;
             Ok(Some(Message::CrossTrade(message)))
         } else {
@@ -201,9 +736,11 @@ impl Reader {
     where
         T: Read + Seek + Peek,
     {
-        let ticker = peek_ticker(buffer, 22, &self.version)?;
+        // paired shares, imbalance shares and imbalance direction precede the ticker
+        let at = self.version.header_size() + 2 * self.version.long_shares_size() + 1;
+        let ticker = peek_ticker(buffer, at, &self.version)?;
 
-        if self.tickers.contains(&ticker) || self.tickers.contains(EVERY_TICKER) {
+        if self.tickers.contains(&ticker) || self.tickers.contains(&EVERY_TICKER) {
             let message =
                 NetOrderImbalanceIndicator::read(buffer, &self.version, &mut self.context)?;
             Ok(Some(Message::NetOrderImbalanceIndicator(message)))
@@ -211,6 +748,53 @@ impl Reader {
             Ok(None)
         }
     }
+
+    fn parse_rpii<T>(&mut self, buffer: &mut T) -> Result<Option<Message>>
+    where
+        T: Read + Seek + Peek,
+    {
+        let ticker = peek_ticker(buffer, self.version.header_size(), &self.version)?;
+
+        if self.tickers.contains(&ticker) || self.tickers.contains(&EVERY_TICKER) {
+            let message = RetailPriceImprovement::read(buffer, &self.version, &mut self.context)?;
+            Ok(Some(Message::RetailPriceImprovement(message)))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn parse_dlcr<T>(&mut self, buffer: &mut T) -> Result<Option<Message>>
+    where
+        T: Read + Seek + Peek,
+    {
+        let ticker = peek_ticker(buffer, self.version.header_size(), &self.version)?;
+
+        if self.tickers.contains(&ticker) || self.tickers.contains(&EVERY_TICKER) {
+            let message =
+                DirectListingCapitalRaise::read(buffer, &self.version, &mut self.context)?;
+            Ok(Some(Message::DirectListingCapitalRaise(message)))
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+// Add order and trade messages share the reference number, side and shares ahead of the ticker
+pub(crate) fn order_ticker_offset(version: &Version) -> usize {
+    version.header_size() + version.refno_size() + 1 + version.shares_size()
+}
+
+// Offset of the ticker of the messages that carry one
+pub(crate) fn ticker_offset(version: &Version, kind: u8) -> Option<usize> {
+    let header = version.header_size();
+    match kind {
+        b'R' | b'H' | b'Y' | b'K' | b'J' | b'h' | b'N' | b'O' => Some(header),
+        b'L' => Some(header + 4), // mpid
+        b'A' | b'F' | b'P' => Some(order_ticker_offset(version)),
+        b'Q' => Some(header + version.long_shares_size()),
+        b'I' => Some(header + 2 * version.long_shares_size() + 1),
+        _ => None,
+    }
 }
 
 #[cfg(test)]
@@ -219,7 +803,8 @@ mod tests {
 
     use super::*;
     use crate::{
-        buffer::BufFile,
+        buffer::{BufFile, Stream},
+        index::Index,
         message::{test_helpers::message_builders::*, Side},
     };
 
@@ -259,7 +844,7 @@ mod tests {
         let sinkfile = NamedTempFile::new("test_messages.bin").unwrap();
 
         // create a reader with a ticker
-        let tickers = HashSet::from(["A".to_string()]);
+        let tickers = HashSet::from([ticker("A")]);
         let mut reader = Reader::new(Version::V41, tickers);
         reader.context.update_clock(0);
 
@@ -292,7 +877,7 @@ mod tests {
         let sinkfile = NamedTempFile::new("test_messages.bin").unwrap();
 
         // create a reader with a ticker
-        let tickers = HashSet::from(["A".to_string()]);
+        let tickers = HashSet::from([ticker("A")]);
         let mut reader = Reader::new(Version::V41, tickers);
         reader.context.update_clock(0);
 
@@ -323,7 +908,7 @@ mod tests {
         let sinkfile = NamedTempFile::new("test_messages.bin").unwrap();
 
         // create a reader with a ticker
-        let tickers = HashSet::from(["A".to_string()]);
+        let tickers = HashSet::from([ticker("A")]);
         let mut reader = Reader::new(Version::V41, tickers);
         reader.context.update_clock(0);
         let order = create_order_state("A", Side::Buy, 1000, 200);
@@ -348,4 +933,609 @@ mod tests {
             assert_eq!(cancel_order.refno, 89402372340);
         }
     }
+
+    #[test]
+    // extract_message records stock directory entries for watched tickers
+    fn records_stock_directory() {
+        // create a temporary file
+        let sinkfile = NamedTempFile::new("test_messages.bin").unwrap();
+
+        // create a reader with a ticker
+        let tickers = HashSet::from([ticker("A")]);
+        let mut reader = Reader::new(Version::V41, tickers);
+        reader.context.update_clock(0);
+
+        // add messages to the file
+        let messages = vec![
+            stock_directory_v41(0, "X", 'N', 'N', 100),
+            stock_directory_v41(0, "A", 'Q', 'N', 100),
+        ];
+        let data = create_message_sequence(messages);
+        sinkfile.write_binary(&data.into_inner()).unwrap();
+        let mut buffile = BufFile::new(sinkfile.path()).unwrap();
+
+        // extract the next message
+        let message = reader.extract_message(&mut buffile).unwrap();
+
+        // check that only the watched ticker was recorded
+        assert!(matches!(message, Message::StockDirectory(_)));
+        assert_eq!(reader.directory().len(), 1);
+        assert_eq!(
+            *reader
+                .stock_directory(&ticker("A"))
+                .unwrap()
+                .market_category(),
+            'Q'
+        );
+        assert!(reader.stock_directory(&ticker("X")).is_none());
+    }
+
+    #[test]
+    // extract_message tracks the trading state of watched tickers
+    fn tracks_trading_state() {
+        // create a temporary file
+        let sinkfile = NamedTempFile::new("test_messages.bin").unwrap();
+
+        // create a reader with a ticker
+        let tickers = HashSet::from([ticker("A")]);
+        let mut reader = Reader::new(Version::V41, tickers);
+        reader.context.update_clock(0);
+
+        // add messages to the file
+        let messages = vec![
+            trading_action_v41(0, "X", 'H', ""),
+            trading_action_v41(0, "A", 'P', "LUDP"),
+        ];
+        let data = create_message_sequence(messages);
+        sinkfile.write_binary(&data.into_inner()).unwrap();
+        let mut buffile = BufFile::new(sinkfile.path()).unwrap();
+
+        // extract the next message
+        let message = reader.extract_message(&mut buffile).unwrap();
+
+        // check that only the watched ticker was updated
+        assert!(matches!(message, Message::TradingAction(_)));
+        assert_eq!(
+            reader.trading_state(&ticker("A")),
+            Some(TradingState::Paused)
+        );
+        assert_eq!(reader.trading_state(&ticker("X")), None);
+    }
+
+    #[test]
+    // extract_message tracks short sale restrictions of watched tickers
+    fn tracks_short_sale_restrictions() {
+        // create a temporary file
+        let sinkfile = NamedTempFile::new("test_messages.bin").unwrap();
+
+        // create a reader with a ticker
+        let tickers = HashSet::from([ticker("A")]);
+        let mut reader = Reader::new(Version::V41, tickers);
+        reader.context.update_clock(0);
+
+        // add messages to the file
+        let messages = vec![reg_sho_v41(0, "X", '1'), reg_sho_v41(0, "A", '2')];
+        let data = create_message_sequence(messages);
+        sinkfile.write_binary(&data.into_inner()).unwrap();
+        let mut buffile = BufFile::new(sinkfile.path()).unwrap();
+
+        // extract the next message
+        let message = reader.extract_message(&mut buffile).unwrap();
+
+        // check that only the watched ticker was updated
+        assert!(matches!(message, Message::RegSHORestriction(_)));
+        assert_eq!(reader.short_sale_restricted(&ticker("A")), Some(true));
+        assert_eq!(reader.short_sale_restricted(&ticker("X")), None);
+    }
+
+    #[test]
+    // extract_message registers market participants of watched tickers
+    fn registers_market_participants() {
+        // create a temporary file
+        let sinkfile = NamedTempFile::new("test_messages.bin").unwrap();
+
+        // create a reader with a ticker
+        let tickers = HashSet::from([ticker("A")]);
+        let mut reader = Reader::new(Version::V50, tickers);
+
+        // add messages to the file
+        let messages = vec![
+            market_participant_v50(0, "GSCO", "X", true, 'N', 'A'),
+            market_participant_v50(0, "GSCO", "A", false, 'N', 'A'),
+        ];
+        let data = create_message_sequence(messages);
+        sinkfile.write_binary(&data.into_inner()).unwrap();
+        let mut buffile = BufFile::new(sinkfile.path()).unwrap();
+
+        // extract the next message
+        let message = reader.extract_message(&mut buffile).unwrap();
+
+        // check that only the watched ticker was registered
+        assert!(matches!(message, Message::MarketParticipantPosition(_)));
+        assert_eq!(reader.market_participants().len(), 1);
+        assert!(!reader
+            .market_participant("GSCO", &ticker("A"))
+            .unwrap()
+            .primary_market_maker());
+        assert!(reader.market_participant("GSCO", &ticker("X")).is_none());
+    }
+
+    #[test]
+    // extract_message returns MWCB messages regardless of tickers
+    fn returns_mwcb_messages() {
+        // create a temporary file
+        let sinkfile = NamedTempFile::new("test_messages.bin").unwrap();
+
+        // create a reader with *no* tickers
+        let mut reader = Reader::new(Version::V50, HashSet::new());
+
+        // add messages to the file
+        let messages = vec![mwcb_decline_level_v50(0, 3, 2, 1), mwcb_status_v50(0, '1')];
+        let data = create_message_sequence(messages);
+        sinkfile.write_binary(&data.into_inner()).unwrap();
+        let mut buffile = BufFile::new(sinkfile.path()).unwrap();
+
+        // extract both messages
+        let first = reader.extract_message(&mut buffile).unwrap();
+        let second = reader.extract_message(&mut buffile).unwrap();
+
+        assert!(matches!(first, Message::MWCBDeclineLevel(_)));
+        assert!(matches!(second, Message::MWCBStatus(_)));
+    }
+
+    #[test]
+    // extract_message returns volatility messages for watched tickers only
+    fn filters_volatility_messages_by_ticker() {
+        // create a temporary file
+        let sinkfile = NamedTempFile::new("test_messages.bin").unwrap();
+
+        // create a reader with a ticker
+        let tickers = HashSet::from([ticker("A")]);
+        let mut reader = Reader::new(Version::V50, tickers);
+
+        // add messages to the file
+        let messages = vec![
+            ipo_quoting_period_v50(0, "X", 0, 'A', 0),
+            luld_auction_collar_v50(0, "X", 0, 0, 0, 0),
+            operational_halt_v50(0, "X", 'Q', 'H'),
+            ipo_quoting_period_v50(0, "A", 0, 'A', 0),
+            luld_auction_collar_v50(0, "A", 0, 0, 0, 0),
+            operational_halt_v50(0, "A", 'Q', 'H'),
+        ];
+        let data = create_message_sequence(messages);
+        sinkfile.write_binary(&data.into_inner()).unwrap();
+        let mut buffile = BufFile::new(sinkfile.path()).unwrap();
+
+        // extract the watched messages
+        let first = reader.extract_message(&mut buffile).unwrap();
+        let second = reader.extract_message(&mut buffile).unwrap();
+        let third = reader.extract_message(&mut buffile).unwrap();
+
+        assert!(matches!(first, Message::IPOQuotingPeriod(m) if m.ticker() == "A"));
+        assert!(matches!(second, Message::LULDAuctionCollar(m) if m.ticker() == "A"));
+        assert!(matches!(third, Message::OperationalHalt(m) if m.ticker() == "A"));
+    }
+
+    #[test]
+    // extract_message returns RPII and DLCR messages for watched tickers only
+    fn filters_rpii_and_dlcr_messages_by_ticker() {
+        // create a temporary file
+        let sinkfile = NamedTempFile::new("test_messages.bin").unwrap();
+
+        // create a reader with a ticker
+        let tickers = HashSet::from([ticker("A")]);
+        let mut reader = Reader::new(Version::V50, tickers);
+
+        // add messages to the file
+        let messages = vec![
+            rpii_v50(0, "X", 'B'),
+            dlcr_v50(0, "X", 'Y', 0, 0, 0, 0, 0, 0),
+            rpii_v50(0, "A", 'S'),
+            dlcr_v50(0, "A", 'Y', 0, 0, 0, 0, 0, 0),
+        ];
+        let data = create_message_sequence(messages);
+        sinkfile.write_binary(&data.into_inner()).unwrap();
+        let mut buffile = BufFile::new(sinkfile.path()).unwrap();
+
+        // extract the watched messages
+        let first = reader.extract_message(&mut buffile).unwrap();
+        let second = reader.extract_message(&mut buffile).unwrap();
+
+        assert!(matches!(first, Message::RetailPriceImprovement(m) if m.interest_flag() == &'S'));
+        assert!(matches!(second, Message::DirectListingCapitalRaise(m) if m.ticker() == "A"));
+    }
+
+    #[test]
+    // extract_message reads binary Version 4.0 files with six-character tickers
+    fn reads_version_40_messages() {
+        let sinkfile = NamedTempFile::new("test_messages.bin").unwrap();
+        let tickers = HashSet::from([ticker("A")]);
+        let mut reader = Reader::new(Version::V40, tickers);
+
+        let messages = vec![
+            timestamp_v41(1),
+            add_order_v40(0, 1, Side::Buy, 100, "X", 10000),
+            add_order_v40(5, 2, Side::Sell, 200, "A", 20000),
+        ];
+        let data = create_message_sequence(messages);
+        sinkfile.write_binary(&data.into_inner()).unwrap();
+        let mut buffile = BufFile::new(sinkfile.path()).unwrap();
+
+        let message = reader.extract_message(&mut buffile).unwrap();
+        assert!(matches!(message, Message::AddOrder(m)
+            if m.refno() == &2 && m.ticker() == "A" && m.nanoseconds() == &1_000_000_005));
+        assert!(reader.extract_message(&mut buffile).is_err());
+    }
+
+    #[test]
+    // extract_message reads newline-terminated Version 3.0 records
+    fn reads_version_30_records() {
+        let sinkfile = NamedTempFile::new("test_messages.txt").unwrap();
+        let tickers = HashSet::from([ticker("A")]);
+        let mut reader = Reader::new(Version::V30, tickers);
+
+        let records = vec![
+            timestamp_v30(34200),
+            add_order_v30(0, 1, Side::Buy, 100, "X", 10000),
+            add_order_v30(1, 2, Side::Sell, 200, "A", 20000),
+            execute_order_v30(2, 1, 100, 7),
+            execute_order_v30(3, 2, 50, 8),
+            trade_v30(4, 0, Side::Buy, 10, "X", 10000, 9),
+            trade_v30(5, 0, Side::Buy, 20, "A", 20000, 10),
+        ];
+        let data = create_record_sequence(records);
+        sinkfile.write_binary(&data.into_inner()).unwrap();
+        let mut buffile = BufFile::new(sinkfile.path()).unwrap();
+
+        let add = reader.extract_message(&mut buffile).unwrap();
+        let execute = reader.extract_message(&mut buffile).unwrap();
+        let trade = reader.extract_message(&mut buffile).unwrap();
+
+        assert!(matches!(add, Message::AddOrder(m)
+            if m.refno() == &2 && m.nanoseconds() == &34_200_001_000_000));
+        assert!(
+            matches!(execute, Message::ExecuteOrder(m) if m.refno() == &2 && m.shares() == &50)
+        );
+        assert!(matches!(trade, Message::Trade(m) if m.matchno() == &10 && m.ticker() == "A"));
+        assert_eq!(
+            reader.extract_message(&mut buffile).unwrap_err().kind(),
+            std::io::ErrorKind::UnexpectedEof
+        );
+    }
+
+    #[test]
+    // extract_message reads Version 2.0 records, which lead with a millisecond timestamp
+    fn reads_version_20_records() {
+        let sinkfile = NamedTempFile::new("test_messages.txt").unwrap();
+        let tickers = HashSet::from([ticker("A")]);
+        let mut reader = Reader::new(Version::V20, tickers);
+
+        let records = vec![
+            system_event_v20(25_200_000, 'O'),
+            add_order_v20(34_200_001, 2, Side::Sell, 200, "A", 20000),
+            delete_order_v20(34_200_002, 2),
+        ];
+        let data = create_record_sequence(records);
+        sinkfile.write_binary(&data.into_inner()).unwrap();
+        let mut buffile = BufFile::new(sinkfile.path()).unwrap();
+
+        let event = reader.extract_message(&mut buffile).unwrap();
+        let add = reader.extract_message(&mut buffile).unwrap();
+        let delete = reader.extract_message(&mut buffile).unwrap();
+
+        assert!(matches!(event, Message::SystemEvent(m) if m.nanoseconds() == &25_200_000_000_000));
+        assert!(matches!(add, Message::AddOrder(m) if m.price() == &20000 && m.shares() == &200));
+        assert!(matches!(delete, Message::DeleteOrder(m)
+            if m.refno() == &2 && m.nanoseconds() == &34_200_002_000_000));
+    }
+
+    #[test]
+    // extract_message reports truncated text records as invalid data rather than end of file
+    fn rejects_truncated_records() {
+        let sinkfile = NamedTempFile::new("test_messages.txt").unwrap();
+        let mut reader = Reader::new(Version::V30, HashSet::from([ticker("*")]));
+        reader.context.update_clock(0);
+
+        sinkfile.write_binary(b"A000000000001B   100\n").unwrap();
+        let mut buffile = BufFile::new(sinkfile.path()).unwrap();
+
+        let err = reader.extract_message(&mut buffile).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    // extract_message skips and peeks through a decompressing stream like through a file
+    fn reads_gzip_compressed_messages() {
+        use std::io::Write;
+
+        use flate2::{write::GzEncoder, Compression};
+
+        let sinkfile = NamedTempFile::new("test_messages.bin.gz").unwrap();
+        let tickers = HashSet::from([ticker("A")]);
+        let mut reader = Reader::new(Version::V50, tickers);
+
+        let messages = vec![
+            add_order_v50(0, 1, Side::Buy, 100, "B", 1000),
+            add_order_v50(0, 2, Side::Sell, 200, "A", 2000),
+            delete_order_v50(0, 1),
+            delete_order_v50(0, 2),
+        ];
+        let data = create_message_sequence(messages);
+        let mut encoder = GzEncoder::new(vec![], Compression::default());
+        encoder.write_all(&data.into_inner()).unwrap();
+        sinkfile.write_binary(&encoder.finish().unwrap()).unwrap();
+        let mut stream = Stream::open(sinkfile.path()).unwrap();
+
+        let add = reader.extract_message(&mut stream).unwrap();
+        let delete = reader.extract_message(&mut stream).unwrap();
+
+        assert!(matches!(add, Message::AddOrder(m) if m.refno() == &2 && m.ticker() == "A"));
+        assert!(matches!(delete, Message::DeleteOrder(m) if m.refno() == &2));
+        assert_eq!(
+            reader.extract_message(&mut stream).unwrap_err().kind(),
+            std::io::ErrorKind::UnexpectedEof
+        );
+    }
+
+    #[test]
+    fn seeks_to_index_entry() {
+        let tickers = HashSet::from([ticker("A")]);
+        let mut reader = Reader::new(Version::V41, tickers);
+
+        let messages = vec![
+            timestamp_v41(34200),
+            add_order_v41(0, 1, Side::Buy, 100, "A", 1000),
+            add_order_v41(1, 2, Side::Sell, 200, "A", 2000),
+            delete_order_v41(2, 1),
+            delete_order_v41(3, 2),
+        ];
+        let mut buffer = create_message_sequence(messages);
+        let index = Index::build(&mut buffer, Version::V41, 2).unwrap();
+
+        // The clock was set by a message before the entry
+        let entry = &index.entries()[1];
+        reader.seek(&mut buffer, entry).unwrap();
+        let add = reader.extract_message(&mut buffer).unwrap();
+        assert!(matches!(add, Message::AddOrder(m)
+            if m.refno() == &2 && m.nanoseconds() == &34_200_000_000_001));
+
+        // Order 1 was added before the entry
+        let delete = reader.extract_message(&mut buffer).unwrap();
+        assert!(matches!(delete, Message::DeleteOrder(m) if m.refno() == &2));
+    }
+
+    #[test]
+    // errors record the offset and sequence number of the message that raised them
+    fn reports_position_of_bad_messages() {
+        let messages = vec![
+            timestamp_v41(34200),
+            add_order_v41(0, 1, Side::Buy, 100, "A", 1000),
+            add_order_v41(1, 2, Side::Sell, 200, "A", 2000),
+        ];
+        let mut data = create_message_sequence(messages).into_inner();
+        data[7 + 32 + 15] = b'Z'; // Side of the second order
+
+        let mut reader = Reader::new(Version::V41, HashSet::from([ticker("A")]));
+        let mut buffer = Cursor::new(data.clone());
+        assert!(reader.extract_message(&mut buffer).is_ok());
+        let err = reader.extract_message(&mut buffer).unwrap_err();
+        assert!(matches!(err.cause(), Error::InvalidSide(b'Z')));
+        assert_eq!((err.offset(), err.sequence()), (Some(39), Some(2)));
+
+        let mut reader = Reader::new(Version::V41, HashSet::from([ticker("A")]));
+        let mut input = &data[7..]; // Without the timestamp
+        let err = reader.extract_streamed_message(&mut input).unwrap_err();
+        assert!(matches!(err.cause(), Error::MissingClock));
+        assert_eq!((err.offset(), err.sequence()), (Some(0), Some(0)));
+    }
+
+    #[test]
+    // messages whose length does not match their type are reported and moved past
+    fn checks_message_lengths() {
+        let mut long = add_order_v41(0, 1, Side::Buy, 100, "A", 1000).into_inner();
+        long.push(0);
+        let messages = vec![
+            timestamp_v41(34200),
+            Cursor::new(long),
+            add_order_v41(1, 2, Side::Sell, 200, "A", 2000),
+        ];
+        let data = create_message_sequence(messages).into_inner();
+        let tickers = HashSet::from([ticker("A")]);
+
+        let mut reader = Reader::new(Version::V41, tickers.clone());
+        let mut buffer = Cursor::new(data.clone());
+        let err = reader.extract_message(&mut buffer).unwrap_err();
+        assert!(matches!(
+            err.cause(),
+            Error::SizeMismatch {
+                kind: 'A',
+                expected: Some(30),
+                found: 31
+            }
+        ));
+        assert_eq!((err.offset(), err.sequence()), (Some(7), Some(1)));
+        let add = reader.extract_message(&mut buffer).unwrap();
+        assert!(matches!(add, Message::AddOrder(m) if m.refno() == &2));
+
+        let mut reader = Reader::new(Version::V41, tickers).with_policy(Policy::Skip);
+        let add = reader.extract_message(&mut Cursor::new(data)).unwrap();
+        assert!(matches!(add, Message::AddOrder(m) if m.refno() == &2));
+        assert_eq!(reader.diagnostics().len(), 1);
+        assert_eq!(reader.diagnostics()[0].offset(), Some(7));
+    }
+
+    #[test]
+    // lenient policies skip bad messages, resuming with the next frame, and record them
+    fn skips_corrupt_messages() {
+        let messages = vec![
+            timestamp_v41(34200),
+            add_order_v41(0, 1, Side::Buy, 100, "A", 1000),
+            add_order_v41(1, 2, Side::Sell, 200, "A", 2000),
+        ];
+        let mut data = create_message_sequence(messages).into_inner();
+        data[7 + 15] = b'Z'; // Side of the first order
+        data.extend([0, 30, b'A', 0, 0]); // Truncated tail
+
+        let tickers = HashSet::from([ticker("A")]);
+        let mut reader = Reader::new(Version::V41, tickers).with_policy(Policy::Skip);
+        let mut buffer = Cursor::new(data);
+        let add = reader.extract_message(&mut buffer).unwrap();
+        assert!(matches!(add, Message::AddOrder(m) if m.refno() == &2));
+        assert_eq!(
+            reader.extract_message(&mut buffer).unwrap_err().kind(),
+            std::io::ErrorKind::UnexpectedEof
+        );
+
+        let diagnostics = reader.diagnostics();
+        assert_eq!(diagnostics.len(), 2);
+        assert!(matches!(diagnostics[0].cause(), Error::InvalidSide(b'Z')));
+        assert_eq!(diagnostics[0].offset(), Some(7));
+        assert!(matches!(
+            diagnostics[1].cause(),
+            Error::TruncatedMessage { size: 30 }
+        ));
+        assert_eq!(diagnostics[1].sequence(), Some(3));
+    }
+
+    #[test]
+    // an execution of more shares than the order has left is skipped without touching the order
+    fn skips_over_executions() {
+        let messages = vec![
+            timestamp_v41(34200),
+            add_order_v41(0, 1, Side::Buy, 100, "A", 1000),
+            execute_order_v41(1, 1, 150),
+            cancel_order_v41(2, 1, 30),
+            delete_order_v41(3, 1),
+        ];
+        let data = create_message_sequence(messages).into_inner();
+        let tickers = HashSet::from([ticker("A")]);
+        let mut reader = Reader::new(Version::V41, tickers).with_policy(Policy::Skip);
+        let mut buffer = Cursor::new(data);
+
+        let add = reader.extract_message(&mut buffer).unwrap();
+        assert!(matches!(add, Message::AddOrder(_)));
+        let cancel = reader.extract_message(&mut buffer).unwrap();
+        assert!(matches!(cancel, Message::CancelOrder(m) if m.shares() == &30));
+        let delete = reader.extract_message(&mut buffer).unwrap();
+        assert!(matches!(delete, Message::DeleteOrder(m) if m.shares() == &70));
+
+        let diagnostics = reader.diagnostics();
+        assert_eq!(diagnostics.len(), 1);
+        assert!(matches!(
+            diagnostics[0].cause(),
+            Error::OrderUnderflow {
+                refno: 1,
+                shares: 150,
+                remaining: 100
+            }
+        ));
+        assert_eq!(diagnostics[0].offset(), Some(39));
+    }
+
+    #[test]
+    // lenient policies skip malformed text records
+    fn skips_malformed_records() {
+        let tickers = HashSet::from([ticker("*")]);
+        let mut reader = Reader::new(Version::V30, tickers).with_policy(Policy::Warn);
+        reader.context.update_clock(0);
+
+        let mut data = b"A000000000001B   100\n".to_vec();
+        data.extend(
+            create_record_sequence(vec![add_order_v30(1, 2, Side::Sell, 200, "A", 1)]).into_inner(),
+        );
+        let mut buffer = Cursor::new(data);
+
+        let add = reader.extract_message(&mut buffer).unwrap();
+        assert!(matches!(add, Message::AddOrder(m) if m.refno() == &2));
+        assert_eq!(reader.diagnostics().len(), 1);
+        assert_eq!(reader.diagnostics()[0].offset(), Some(0));
+    }
+
+    #[test]
+    fn parses_policies() {
+        assert_eq!("warn".parse::<Policy>().unwrap(), Policy::Warn);
+        assert_eq!("SKIP".parse::<Policy>().unwrap(), Policy::Skip);
+        assert!("lenient".parse::<Policy>().is_err());
+        assert_eq!(Policy::default().to_string(), "strict");
+    }
+
+    #[test]
+    // extract_streamed_message frames binary messages by their length prefix without seeking
+    fn reads_messages_from_plain_reader() {
+        let tickers = HashSet::from([ticker("A")]);
+        let mut reader = Reader::new(Version::V41, tickers);
+
+        let messages = vec![
+            timestamp_v41(34200),
+            add_order_v41(0, 1, Side::Buy, 100, "B", 1000),
+            add_order_v41(1, 2, Side::Sell, 200, "A", 2000),
+            delete_order_v41(2, 1),
+            delete_order_v41(3, 2),
+        ];
+        let data = create_message_sequence(messages).into_inner();
+        let mut input = data.as_slice(); // Read, but not Seek
+
+        let add = reader.extract_streamed_message(&mut input).unwrap();
+        let delete = reader.extract_streamed_message(&mut input).unwrap();
+
+        assert!(matches!(add, Message::AddOrder(m)
+            if m.refno() == &2 && m.nanoseconds() == &34_200_000_000_001));
+        assert!(matches!(delete, Message::DeleteOrder(m) if m.refno() == &2));
+        assert_eq!(
+            reader
+                .extract_streamed_message(&mut input)
+                .unwrap_err()
+                .kind(),
+            std::io::ErrorKind::UnexpectedEof
+        );
+    }
+
+    #[test]
+    // extract_streamed_message reports a frame cut short as invalid data rather than end of input
+    fn rejects_truncated_frames() {
+        let mut reader = Reader::new(Version::V50, HashSet::from([ticker("*")]));
+
+        let data = create_message_sequence(vec![system_event_v50(0, 'O')]).into_inner();
+        let mut input = &data[..data.len() - 2];
+
+        let err = reader.extract_streamed_message(&mut input).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    // with_locate_filter skips messages by locate code once the stock directory has been read
+    fn filters_by_stock_locate() {
+        let tickers = HashSet::from([ticker("A")]);
+        let mut reader = Reader::new(Version::V50, tickers).with_locate_filter(true);
+
+        let directory = |symbol| stock_directory_v50(0, symbol, 'Q', 'N', 100, 'C', "Z", 'N', 'N');
+        let messages = vec![
+            with_locate(add_order_v50(0, 1, Side::Buy, 100, "A", 1000), 1, 0), // Before directory
+            with_locate(directory("A"), 1, 0),
+            with_locate(directory("B"), 2, 0),
+            with_locate(broken_trade_v50(1, 10), 2, 0), // Skipped by locate alone
+            with_locate(broken_trade_v50(2, 11), 1, 0),
+            with_locate(add_order_v50(3, 2, Side::Sell, 200, "A", 2000), 1, 0),
+        ];
+        let mut data = create_message_sequence(messages);
+
+        let messages: Vec<Message> =
+            std::iter::from_fn(|| reader.extract_message(&mut data).ok()).collect();
+        assert_eq!(messages.len(), 4);
+        assert!(matches!(&messages[0], Message::AddOrder(m) if m.refno() == &1));
+        assert!(matches!(&messages[1], Message::StockDirectory(m) if m.ticker() == "A"));
+        assert!(matches!(&messages[2], Message::BrokenTrade(m) if m.matchno() == &11));
+        assert!(matches!(&messages[3], Message::AddOrder(m) if m.refno() == &2));
+    }
+
+    #[test]
+    // with_locate_filter leaves unfiltered and pre-5.0 readers alone
+    fn locate_filter_requires_tickers_and_version_50() {
+        let reader =
+            Reader::new(Version::V50, HashSet::from([ticker("*")])).with_locate_filter(true);
+        assert!(reader.locates.is_none());
+        let reader =
+            Reader::new(Version::V41, HashSet::from([ticker("A")])).with_locate_filter(true);
+        assert!(reader.locates.is_none());
+    }
 }
@@ -0,0 +1,140 @@
+use std::{io::ErrorKind, iter::FusedIterator};
+
+use super::Reader;
+use crate::{error::Result, message::Message};
+
+/// An iterator over the messages of a source, returned by `Reader::messages` and
+/// `Reader::streamed_messages`.
+///
+/// Messages are yielded in input order and iteration ends cleanly at the end of the input. An
+/// error ends iteration after it is yielded, so it is never yielded twice (under a lenient
+/// policy, the messages that cannot be interpreted are skipped without ending it).
+pub struct Messages<T> {
+    reader: Reader,
+    buffer: T,
+    extract: fn(&mut Reader, &mut T) -> Result<Message>,
+    done: bool,
+}
+
+impl<T> Messages<T> {
+    pub(super) fn new(
+        reader: Reader,
+        buffer: T,
+        extract: fn(&mut Reader, &mut T) -> Result<Message>,
+    ) -> Self {
+        Self {
+            reader,
+            buffer,
+            extract,
+            done: false,
+        }
+    }
+
+    /// Returns the reader, e.g. to look up the stock directory or the diagnostics collected so
+    /// far.
+    pub fn reader(&self) -> &Reader {
+        &self.reader
+    }
+
+    /// Returns the reader and the source, which is positioned after the last message read.
+    pub fn into_inner(self) -> (Reader, T) {
+        (self.reader, self.buffer)
+    }
+}
+
+impl<T> Iterator for Messages<T> {
+    type Item = Result<Message>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        match (self.extract)(&mut self.reader, &mut self.buffer) {
+            Ok(message) => Some(Ok(message)),
+            Err(e) => {
+                self.done = true;
+                (e.kind() != ErrorKind::UnexpectedEof).then_some(Err(e))
+            }
+        }
+    }
+}
+
+impl<T> FusedIterator for Messages<T> {}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashSet, io::Cursor};
+
+    use super::*;
+    use crate::{
+        error::Error,
+        message::{test_helpers::message_builders::*, Side, Version},
+        reader::Policy,
+    };
+
+    fn messages_v41() -> Vec<u8> {
+        create_message_sequence(vec![
+            timestamp_v41(34200),
+            add_order_v41(0, 1, Side::Buy, 100, "A", 1000),
+            add_order_v41(1, 2, Side::Sell, 200, "B", 2000),
+            timestamp_v41(34201),
+            delete_order_v41(2, 1),
+        ])
+        .into_inner()
+    }
+
+    #[test]
+    fn yields_messages_until_end_of_input() {
+        let reader = Reader::new(Version::V41, HashSet::from([ticker("A")]));
+        let messages = reader
+            .messages(Cursor::new(messages_v41()))
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(messages.len(), 2);
+        assert!(matches!(&messages[0], Message::AddOrder(m) if m.refno() == &1));
+        assert!(matches!(&messages[1], Message::DeleteOrder(m)
+            if m.nanoseconds() == &34_201_000_000_002));
+    }
+
+    #[test]
+    fn adapts_like_any_iterator() {
+        let tickers = HashSet::from([ticker("*")]);
+        let reader = Reader::new(Version::V41, tickers);
+        let mut data = messages_v41();
+        data.extend(messages_v41());
+
+        // Messages of the first second of the streamed input
+        let mut messages = reader.streamed_messages(data.as_slice());
+        let refnos = messages
+            .by_ref()
+            .map(Result::unwrap)
+            .take_while(|m| m.nanoseconds() < 34_201_000_000_000)
+            .filter_map(|m| match m {
+                Message::AddOrder(m) => Some(*m.refno()),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(refnos, vec![1, 2]);
+        assert!(messages.reader().directory().is_empty());
+    }
+
+    #[test]
+    fn ends_after_an_error() {
+        let mut data = messages_v41();
+        data[7 + 15] = b'Z'; // Side of the first order
+
+        let tickers = HashSet::from([ticker("A")]);
+        let mut messages =
+            Reader::new(Version::V41, tickers.clone()).messages(Cursor::new(data.clone()));
+        let err = messages.next().unwrap().unwrap_err();
+        assert!(matches!(err.cause(), Error::InvalidSide(b'Z')));
+        assert!(messages.next().is_none());
+
+        let reader = Reader::new(Version::V41, tickers).with_policy(Policy::Skip);
+        let mut messages = reader.messages(Cursor::new(data.clone()));
+        assert!(messages.next().is_none()); // The delete is about the skipped order
+        let (reader, buffer) = messages.into_inner();
+        assert_eq!(reader.diagnostics().len(), 1);
+        assert_eq!(buffer.position(), data.len() as u64);
+    }
+}
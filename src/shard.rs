@@ -0,0 +1,241 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    constants::EVERY_TICKER,
+    error::{Error, Result},
+    message::{frame_field, frame_u32, frame_u64, Ticker, Version},
+    reader::ticker_offset,
+};
+
+mod shards;
+
+pub use shards::{Frames, Sequential, Shard, Shards};
+
+/// Where a message should be parsed.
+#[derive(Debug, PartialEq)]
+pub enum Route {
+    /// Parse the message in a single shard.
+    Shard(usize),
+    /// Parse the message in every shard (e.g. Version 4.x timestamps).
+    All,
+}
+
+/// Assigns the binary messages of a file to shards so that each shard can be parsed by its own
+/// `Reader`.
+///
+/// All messages about a stock are sent to the same shard: Version 5.0 messages by their stock
+/// locate code, and earlier versions by their ticker or, for order updates, by the shard of the
+/// order they refer to. Messages that concern no stock in particular (e.g. system events) are
+/// sent to the first shard.
+pub struct Router {
+    version: Version,
+    shards: usize,
+    tickers: HashSet<Ticker>,
+    orders: Orders<usize>, // Shard of each watched order
+}
+
+impl Router {
+    pub fn new(version: Version, shards: usize, tickers: HashSet<Ticker>) -> Result<Self> {
+        if version.is_text() {
+            return Err(Error::Unframed(version));
+        }
+        if shards == 0 {
+            return Err(Error::InvalidField {
+                field: "shard count",
+                value: shards.to_string(),
+            });
+        }
+        Ok(Self {
+            version,
+            shards,
+            tickers,
+            orders: Orders::new(),
+        })
+    }
+
+    pub fn shards(&self) -> usize {
+        self.shards
+    }
+
+    /// Routes a message, given without its length prefix.
+    ///
+    /// Frames must be routed in file order, since order updates are routed by the messages that
+    /// added the orders.
+    pub fn route(&mut self, frame: &[u8]) -> Result<Route> {
+        let kind = frame_field(frame, 0, 1)?[0];
+        if self.version == Version::V50 {
+            let locate = frame_field(frame, 1, 2)?;
+            let locate = u16::from_be_bytes([locate[0], locate[1]]);
+            return Ok(Route::Shard(locate as usize % self.shards));
+        }
+
+        if kind == b'T' {
+            return Ok(Route::All);
+        }
+        if let Some(at) = ticker_offset(&self.version, kind) {
+            let ticker = self.ticker(frame, at)?;
+            let shard = self.shard_of(&ticker);
+            if matches!(kind, b'A' | b'F') && self.is_watched(&ticker) {
+                self.orders.add(frame, &self.version, shard)?;
+            }
+            return Ok(Route::Shard(shard));
+        }
+        let shard = self.orders.update(frame, &self.version)?;
+        Ok(Route::Shard(shard.unwrap_or(0)))
+    }
+
+    fn ticker(&self, frame: &[u8], at: usize) -> Result<Ticker> {
+        Ok(Ticker::from_bytes(frame_field(
+            frame,
+            at,
+            self.version.ticker_size(),
+        )?)?)
+    }
+
+    // Spreads tickers evenly, including short tickers that only differ in their first bytes
+    fn shard_of(&self, ticker: &Ticker) -> usize {
+        let hash = u64::from_be_bytes(*ticker.as_bytes()).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+        ((hash >> 32) % self.shards as u64) as usize
+    }
+
+    fn is_watched(&self, ticker: &Ticker) -> bool {
+        self.tickers.contains(ticker) || self.tickers.contains(&EVERY_TICKER)
+    }
+}
+
+/// Follows orders through the messages that update them, keeping a value (e.g. a shard) for each.
+///
+/// Orders are forgotten once deleted, replaced or executed or cancelled in full.
+pub(crate) struct Orders<V> {
+    orders: HashMap<u64, (V, u32)>, // Value and remaining shares of each order
+}
+
+impl<V: Copy> Orders<V> {
+    pub(crate) fn new() -> Self {
+        Self {
+            orders: HashMap::new(),
+        }
+    }
+
+    // Records the order added by an add order message
+    pub(crate) fn add(&mut self, frame: &[u8], version: &Version, value: V) -> Result<()> {
+        let header = version.header_size();
+        let refno = frame_u64(frame, header)?;
+        let shares = frame_u32(frame, header + version.refno_size() + 1)?;
+        self.orders.insert(refno, (value, shares));
+        Ok(())
+    }
+
+    // Applies an order update message, returning the value of its order if known. Returns `None`
+    // for other messages.
+    pub(crate) fn update(&mut self, frame: &[u8], version: &Version) -> Result<Option<V>> {
+        let header = version.header_size();
+        let refno_size = version.refno_size();
+        let value = match frame_field(frame, 0, 1)?[0] {
+            b'E' | b'C' | b'X' => {
+                let refno = frame_u64(frame, header)?;
+                let shares = frame_u32(frame, header + refno_size)?;
+                self.reduce(refno, shares)
+            }
+            b'D' => {
+                let refno = frame_u64(frame, header)?;
+                self.orders.remove(&refno).map(|(value, _)| value)
+            }
+            b'U' => {
+                let old_refno = frame_u64(frame, header)?;
+                let new_refno = frame_u64(frame, header + refno_size)?;
+                let shares = frame_u32(frame, header + 2 * refno_size)?;
+                let value = self.orders.remove(&old_refno).map(|(value, _)| value);
+                if let Some(value) = value {
+                    self.orders.insert(new_refno, (value, shares));
+                }
+                value
+            }
+            _ => None,
+        };
+        Ok(value)
+    }
+
+    fn reduce(&mut self, refno: u64, shares: u32) -> Option<V> {
+        let (value, remaining) = self.orders.get_mut(&refno)?;
+        let value = *value;
+        *remaining = remaining.saturating_sub(shares);
+        if *remaining == 0 {
+            self.orders.remove(&refno);
+        }
+        Some(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::message::{test_helpers::message_builders::*, Side};
+
+    fn router(version: Version, shards: usize) -> Router {
+        Router::new(version, shards, HashSet::from([EVERY_TICKER])).unwrap()
+    }
+
+    // Finds a ticker that is routed to `shard`
+    fn ticker_in(router: &Router, shard: usize) -> String {
+        (b'A'..=b'Z')
+            .map(|c| (c as char).to_string())
+            .find(|symbol| router.shard_of(&ticker(symbol)) == shard)
+            .unwrap()
+    }
+
+    #[test]
+    fn routes_by_stock_locate() {
+        let mut router = router(Version::V50, 4);
+        let add = with_locate(add_order_v50(0, 1, Side::Buy, 100, "AAPL", 1), 6, 0);
+        assert_eq!(router.route(add.get_ref()).unwrap(), Route::Shard(2));
+        let delete = with_locate(delete_order_v50(0, 1), 6, 0);
+        assert_eq!(router.route(delete.get_ref()).unwrap(), Route::Shard(2));
+    }
+
+    #[test]
+    fn order_updates_follow_their_order() {
+        let mut router = router(Version::V41, 3);
+        let symbol = ticker_in(&router, 2);
+        let add = add_order_v41(0, 1, Side::Buy, 100, &symbol, 1);
+        assert_eq!(router.route(add.get_ref()).unwrap(), Route::Shard(2));
+
+        let execute = execute_order_v41(0, 1, 40);
+        assert_eq!(router.route(execute.get_ref()).unwrap(), Route::Shard(2));
+        let replace = replace_order_v41(0, 1, 2, 60, 1);
+        assert_eq!(router.route(replace.get_ref()).unwrap(), Route::Shard(2));
+        let cancel = cancel_order_v41(0, 2, 60);
+        assert_eq!(router.route(cancel.get_ref()).unwrap(), Route::Shard(2));
+
+        // Fully cancelled orders are forgotten
+        let delete = delete_order_v41(0, 2);
+        assert_eq!(router.route(delete.get_ref()).unwrap(), Route::Shard(0));
+        assert!(router.orders.orders.is_empty());
+    }
+
+    #[test]
+    fn ignores_unwatched_orders() {
+        let mut router = Router::new(Version::V41, 3, HashSet::from([ticker("MSFT")])).unwrap();
+        let symbol = ticker_in(&router, 1);
+        let add = add_order_v41(0, 1, Side::Buy, 100, &symbol, 1);
+        assert_eq!(router.route(add.get_ref()).unwrap(), Route::Shard(1));
+        assert!(router.orders.orders.is_empty());
+    }
+
+    #[test]
+    fn broadcasts_timestamps() {
+        let mut router = router(Version::V41, 2);
+        let timestamp = timestamp_v41(34200);
+        assert_eq!(router.route(timestamp.get_ref()).unwrap(), Route::All);
+        let event = system_event_v41(0, 'O');
+        assert_eq!(router.route(event.get_ref()).unwrap(), Route::Shard(0));
+    }
+
+    #[test]
+    fn rejects_text_versions_and_short_frames() {
+        assert!(Router::new(Version::V30, 2, HashSet::new()).is_err());
+        assert!(Router::new(Version::V41, 0, HashSet::new()).is_err());
+        assert!(router(Version::V41, 2).route(b"A\x00").is_err());
+        assert!(router(Version::V50, 2).route(b"").is_err());
+    }
+}
@@ -0,0 +1,449 @@
+use std::{
+    collections::HashSet,
+    io::{ErrorKind, Read, Seek},
+    mem,
+    sync::mpsc::{self, Receiver, SyncSender},
+    thread,
+    time::{Duration, Instant},
+};
+
+use getset::Getters;
+
+use super::{Route, Router};
+use crate::{
+    error::{Error, Result},
+    index::{IndexEntry, Selection},
+    message::{read_frame, Ticker, Version},
+    pipeline::{Output, Pipeline, Processor, Sink},
+    reader::{Policy, Reader},
+};
+
+// Number of frames routed before each worker is sent its share of them
+const ROUND_SIZE: u64 = 1 << 14;
+
+/// A source of length-prefixed binary messages that knows where each one starts in its file.
+pub trait Frames {
+    /// Reads the next message into `frame`, without its length prefix. Returns `false` at the end
+    /// of the input.
+    fn read_frame(&mut self, frame: &mut Vec<u8>) -> Result<bool>;
+
+    /// Returns the byte offset in the file of the message read last (or being read, if that
+    /// failed), or `None` if it is not part of the file (e.g. a 'T' message added by a
+    /// `Selection`).
+    fn offset(&self) -> Option<u64>;
+}
+
+/// The messages of a file (or of standard input) read one after the other.
+pub struct Sequential<R> {
+    reader: R,
+    offset: u64, // Offset of the message read last
+    next: u64,   // Offset of the next message
+}
+
+impl<R: Read> Sequential<R> {
+    /// Reads the messages of `reader`, the first of which starts at byte `offset` of the file.
+    pub fn new(reader: R, offset: u64) -> Self {
+        Self {
+            reader,
+            offset,
+            next: offset,
+        }
+    }
+}
+
+impl<R: Read> Frames for Sequential<R> {
+    fn read_frame(&mut self, frame: &mut Vec<u8>) -> Result<bool> {
+        self.offset = self.next;
+        let read = read_frame(&mut self.reader, frame)?;
+        self.next += 2 + frame.len() as u64;
+        Ok(read)
+    }
+
+    fn offset(&self) -> Option<u64> {
+        Some(self.offset)
+    }
+}
+
+impl<B: Read + Seek> Frames for Selection<B> {
+    fn read_frame(&mut self, frame: &mut Vec<u8>) -> Result<bool> {
+        read_frame(self, frame)
+    }
+
+    fn offset(&self) -> Option<u64> {
+        (!self.in_added_clock()).then(|| self.position())
+    }
+}
+
+/// Parses the binary messages of a file on several threads.
+///
+/// The calling thread routes messages to workers by stock (see `Router`), each with its own
+/// reader and pipeline, and a collector writes the rows they produce back in input order, so that
+/// the output is identical to that of a single pipeline. Every worker is sent a (possibly empty)
+/// batch of messages each round, which lets the collector merge one round at a time.
+pub struct Shards {
+    version: Version,
+    threads: usize,
+    tickers: HashSet<Ticker>,
+    policy: Policy,
+    resume: Option<IndexEntry>,
+}
+
+/// A worker of `Shards` once every message has been parsed: its reader and pipeline hold what it
+/// has seen (e.g. their diagnostics).
+#[derive(Getters)]
+#[getset(get = "pub")]
+pub struct Shard<P> {
+    reader: Reader,
+    pipeline: Pipeline<P>,
+    /// Time spent parsing messages.
+    parsing: Duration,
+}
+
+impl Shards {
+    /// Splits the messages of `tickers` across `threads` workers.
+    pub fn new(version: Version, threads: usize, tickers: HashSet<Ticker>) -> Self {
+        Self {
+            version,
+            threads,
+            tickers,
+            policy: Policy::Strict,
+            resume: None,
+        }
+    }
+
+    /// Sets how messages that cannot be routed (e.g. a truncated last message) are handled. Those
+    /// that cannot be parsed are handled by the policy of the workers' readers.
+    pub fn with_policy(mut self, policy: Policy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Starts from the message of an index entry (see `Reader::resume_at`), at which the input
+    /// must already be positioned.
+    pub fn with_resume(mut self, entry: IndexEntry) -> Self {
+        self.resume = Some(entry);
+        self
+    }
+
+    /// Parses every message of `frames` and writes the rows produced into `sink`, until the end of
+    /// the input or the first error. Each worker parses with the reader and pipeline returned by
+    /// `worker`, which are handed back once done.
+    pub fn run<F, P, S, W>(&self, frames: &mut F, sink: &mut S, worker: W) -> Result<Vec<Shard<P>>>
+    where
+        F: Frames,
+        P: Processor + Send,
+        S: Sink + Send,
+        W: Fn() -> (Reader, Pipeline<P>) + Sync,
+    {
+        let mut router = Router::new(self.version, self.threads, self.tickers.clone())?;
+        let worker = &worker;
+
+        thread::scope(|scope| {
+            let mut senders = Vec::with_capacity(self.threads);
+            let mut receivers = Vec::with_capacity(self.threads);
+            let mut workers = Vec::with_capacity(self.threads);
+            for _ in 0..self.threads {
+                let (batch_tx, batch_rx) = mpsc::sync_channel::<Batch>(2);
+                let (rows_tx, rows_rx) = mpsc::sync_channel::<Result<Rows>>(2);
+                workers.push(scope.spawn(move || {
+                    let (reader, pipeline) = worker();
+                    self.work(reader, pipeline, batch_rx, rows_tx)
+                }));
+                senders.push(batch_tx);
+                receivers.push(rows_rx);
+            }
+            let collector = scope.spawn(move || collect(receivers, sink));
+
+            // Route frames until the input ends or a worker stops early
+            let mut batches: Vec<Batch> = (0..self.threads).map(|_| Batch::default()).collect();
+            let mut frame = Vec::new();
+            let mut skipped = None;
+            let mut sequence = self.resume.map_or(0, |entry| *entry.message());
+            let mut offset = self.resume.map_or(0, |entry| *entry.offset());
+            let mut routed = 0;
+            let result = loop {
+                match frames.read_frame(&mut frame) {
+                    Ok(true) => {}
+                    Ok(false) => break Ok(()),
+                    // A truncated message ends the input, and is skipped like any other under a
+                    // lenient policy
+                    Err(e) => {
+                        let e = e.at(frames.offset().unwrap_or(offset), sequence);
+                        let truncated = matches!(e.cause(), Error::TruncatedMessage { .. });
+                        if !truncated || self.policy == Policy::Strict {
+                            break Err(e);
+                        }
+                        skipped = Some(e);
+                        break Ok(());
+                    }
+                }
+
+                // 'T' messages added to the input are parsed along with the message they precede
+                let added = frames.offset().is_none();
+                offset = frames.offset().unwrap_or(offset);
+                match router.route(&frame) {
+                    Ok(Route::Shard(shard)) => batches[shard].push(sequence, offset, &frame),
+                    Ok(Route::All) => {
+                        for batch in &mut batches {
+                            batch.push(sequence, offset, &frame);
+                        }
+                    }
+                    // Frames too short to route are left for a reader to report or skip
+                    Err(_) if self.policy != Policy::Strict => {
+                        batches[0].push(sequence, offset, &frame)
+                    }
+                    Err(e) => break Err(e.at(offset, sequence)),
+                }
+                if !added {
+                    sequence += 1;
+                }
+
+                routed += 1;
+                if routed % ROUND_SIZE == 0 && !send_round(&senders, &mut batches) {
+                    break Ok(());
+                }
+            };
+            send_round(&senders, &mut batches);
+            drop(senders);
+
+            let mut shards: Vec<Shard<P>> = workers
+                .into_iter()
+                .map(|worker| worker.join().unwrap())
+                .collect();
+            result.and(collector.join().unwrap())?;
+            if let Some(e) = skipped {
+                shards[0].reader.recover(e)?;
+            }
+            Ok(shards)
+        })
+    }
+
+    // Parses the batches of one shard until the router is done
+    fn work<P: Processor>(
+        &self,
+        mut reader: Reader,
+        mut pipeline: Pipeline<P>,
+        batches: Receiver<Batch>,
+        rows: SyncSender<Result<Rows>>,
+    ) -> Shard<P> {
+        if let Some(entry) = &self.resume {
+            reader.resume_at(entry);
+        }
+        let mut parsing = Duration::new(0, 0);
+        for batch in batches {
+            let result = parse(&batch, &mut reader, &mut pipeline, &mut parsing);
+            let failed = result.is_err();
+            if rows.send(result).is_err() || failed {
+                break;
+            }
+        }
+        Shard {
+            reader,
+            pipeline,
+            parsing,
+        }
+    }
+}
+
+// Frames sent to a worker, length-prefixed, along with their positions in the input
+#[derive(Default)]
+struct Batch {
+    frames: Vec<u8>,
+    sequences: Vec<u64>,
+    offsets: Vec<u64>, // Where each frame starts in the file, for error messages
+}
+
+impl Batch {
+    fn push(&mut self, sequence: u64, offset: u64, frame: &[u8]) {
+        self.frames
+            .extend_from_slice(&(frame.len() as u16).to_be_bytes());
+        self.frames.extend_from_slice(frame);
+        self.sequences.push(sequence);
+        self.offsets.push(offset);
+    }
+}
+
+// The rows produced for each frame of a batch that produced any, by position in the input
+type Rows = Vec<(u64, Vec<Output>)>;
+
+// Parses the frames of a batch, returning the rows produced
+fn parse<P: Processor>(
+    batch: &Batch,
+    reader: &mut Reader,
+    pipeline: &mut Pipeline<P>,
+    parsing: &mut Duration,
+) -> Result<Rows> {
+    let mut rows = Vec::new();
+    let mut frames = batch.frames.as_slice();
+    for (&sequence, &offset) in batch.sequences.iter().zip(&batch.offsets) {
+        let size = 2 + u16::from_be_bytes([frames[0], frames[1]]) as usize;
+        let (mut frame, rest) = frames.split_at(size);
+        frames = rest;
+        reader.set_position(offset, sequence);
+
+        // A frame can hold more than one message (e.g. replace orders)
+        let mut outputs = Vec::new();
+        loop {
+            let parse_start = Instant::now();
+            let message = match reader.extract_streamed_message(&mut frame) {
+                Ok(message) => message,
+                Err(e) if e.kind() == ErrorKind::UnexpectedEof => break,
+                Err(e) => return Err(e),
+            };
+            *parsing += parse_start.elapsed();
+            outputs.extend(pipeline.process(&message, reader));
+        }
+        if !outputs.is_empty() {
+            rows.push((sequence, outputs));
+        }
+    }
+    Ok(rows)
+}
+
+// Sends every worker its batch for the round. Returns `false` if a worker has stopped.
+fn send_round(senders: &[SyncSender<Batch>], batches: &mut [Batch]) -> bool {
+    senders
+        .iter()
+        .zip(batches.iter_mut())
+        .all(|(sender, batch)| sender.send(mem::take(batch)).is_ok())
+}
+
+// Writes the rows of each round in input order
+fn collect<S: Sink>(receivers: Vec<Receiver<Result<Rows>>>, sink: &mut S) -> Result<()> {
+    let mut round = Vec::new();
+    loop {
+        for receiver in &receivers {
+            match receiver.recv() {
+                Ok(rows) => round.extend(rows?),
+                Err(_) => return Ok(()), // Every worker is done
+            }
+        }
+
+        round.sort_unstable_by_key(|(sequence, _)| *sequence);
+        for (_, outputs) in round.drain(..) {
+            for output in outputs {
+                sink.write(output).map_err(Error::Sink)?;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::{
+        index::TickerIndex,
+        message::{test_helpers::message_builders::*, Side},
+        pipeline::{self, OrderBooks},
+    };
+
+    // Orders of "A" and "B" added, executed, cancelled, replaced and deleted, interleaved with
+    // those of "C"
+    fn messages_v41() -> Vec<u8> {
+        create_message_sequence(vec![
+            timestamp_v41(34200),
+            add_order_v41(0, 1, Side::Buy, 100, "A", 1000),
+            add_order_v41(1, 2, Side::Sell, 200, "B", 2000),
+            add_order_v41(2, 3, Side::Buy, 300, "C", 1500),
+            execute_order_v41(3, 1, 40),
+            cancel_order_v41(4, 2, 50),
+            execute_order_v41(5, 3, 100),
+            timestamp_v41(34201),
+            replace_order_v41(6, 1, 4, 80, 1010),
+            add_order_v41(7, 5, Side::Sell, 10, "A", 1020),
+            cancel_order_v41(8, 3, 10),
+            delete_order_v41(9, 2),
+            replace_order_v41(10, 3, 6, 50, 1490),
+            execute_order_v41(11, 4, 30),
+            delete_order_v41(12, 4),
+            delete_order_v41(13, 6),
+            cancel_order_v41(14, 5, 5),
+        ])
+        .into_inner()
+    }
+
+    fn worker(tickers: &HashSet<Ticker>) -> (Reader, Pipeline<(OrderBooks, pipeline::Rows)>) {
+        let reader = Reader::new(Version::V41, tickers.clone());
+        let processor = (
+            OrderBooks::new(tickers, "2024-01-02", 3),
+            pipeline::Rows::new("2024-01-02"),
+        );
+        (reader, Pipeline::new(processor))
+    }
+
+    #[test]
+    fn writes_the_same_rows_on_several_threads() {
+        let tickers = HashSet::from([ticker("A"), ticker("B")]);
+        let (reader, mut pipeline) = worker(&tickers);
+        let mut expected: Vec<Output> = Vec::new();
+        let mut messages = reader.messages(Cursor::new(messages_v41()));
+        pipeline.run(&mut messages, &mut expected).unwrap();
+        assert!(expected.iter().any(|o| matches!(o, Output::Snapshot(_))));
+        assert!(!format!("{:?}", expected).contains("Ticker(\"C\")"));
+
+        for threads in [2, 3] {
+            let shards = Shards::new(Version::V41, threads, tickers.clone());
+            let mut frames = Sequential::new(Cursor::new(messages_v41()), 0);
+            let mut rows: Vec<Output> = Vec::new();
+            let done = shards
+                .run(&mut frames, &mut rows, || worker(&tickers))
+                .unwrap();
+            assert_eq!(done.len(), threads);
+            assert_eq!(format!("{:?}", rows), format!("{:?}", expected));
+        }
+    }
+
+    #[test]
+    // errors in a selection are located in the file, without counting the 'T' messages added
+    fn locates_errors_in_selections() {
+        let mut data = create_message_sequence(vec![
+            timestamp_v41(34200),
+            add_order_v41(0, 1, Side::Buy, 100, "A", 1000),
+            add_order_v41(1, 2, Side::Sell, 200, "B", 2000),
+            timestamp_v41(34201),
+            add_order_v41(2, 3, Side::Sell, 300, "B", 2000),
+        ])
+        .into_inner();
+        data[78 + 15] = b'Z'; // Side of the last order
+
+        let tickers = HashSet::from([ticker("B")]);
+        let index = TickerIndex::build(&mut Cursor::new(&data), Version::V41).unwrap();
+        let mut selection = index.select(Cursor::new(&data), &tickers);
+        let shards = Shards::new(Version::V41, 2, tickers.clone());
+        let err = shards
+            .run(&mut selection, &mut Vec::new(), || worker(&tickers))
+            .err()
+            .unwrap();
+        assert!(matches!(err.cause(), Error::InvalidSide(b'Z')));
+        assert_eq!((err.offset(), err.sequence()), (Some(78), Some(1)));
+    }
+
+    #[test]
+    // a truncated last message is recorded by the first shard under a lenient policy
+    fn skips_a_truncated_last_message() {
+        let mut data = messages_v41();
+        data.extend([0, 30, b'A', 0, 0]);
+        let tickers = HashSet::from([ticker("A")]);
+
+        let shards = Shards::new(Version::V41, 2, tickers.clone());
+        let mut frames = Sequential::new(Cursor::new(&data), 0);
+        let err = shards
+            .run(&mut frames, &mut Vec::new(), || worker(&tickers))
+            .err()
+            .unwrap();
+        assert!(matches!(err.cause(), Error::TruncatedMessage { size: 30 }));
+
+        let shards = shards.with_policy(Policy::Skip);
+        let mut frames = Sequential::new(Cursor::new(&data), 0);
+        let worker = || {
+            let (reader, pipeline) = worker(&tickers);
+            (reader.with_policy(Policy::Skip), pipeline)
+        };
+        let done = shards.run(&mut frames, &mut Vec::new(), worker).unwrap();
+        let diagnostics = done[0].reader().diagnostics();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].offset(), Some(data.len() as u64 - 5));
+    }
+}
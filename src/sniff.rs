@@ -0,0 +1,275 @@
+//! Infers the ITCH version and trading date of a file when its name does not follow the
+//! `SMMDDYY-vNN` convention.
+//!
+//! The version is detected from the first messages of a file: text versions are recognized by
+//! their newline-terminated records, while binary versions are told apart by the width of their
+//! timestamps (Version 5.0 system events are 12 bytes long against 6 bytes for Version 4.x), the
+//! presence of 'T' (seconds) messages, and the width of their tickers (Version 4.0 tickers are two
+//! bytes shorter than Version 4.1 tickers).
+
+use std::{
+    io::{ErrorKind, Read, Result},
+    path::Path,
+};
+
+use crate::message::Version;
+
+// Number of bytes inspected by `sniff_version`
+const SNIFF_SIZE: usize = 64 * 1024;
+
+/// Reads the first bytes of `reader` and detects the ITCH version of its messages.
+///
+/// Returns `None` if the version cannot be determined from the inspected bytes.
+pub fn sniff_version<R: Read>(reader: &mut R) -> Result<Option<Version>> {
+    let mut data = vec![0; SNIFF_SIZE];
+    let mut filled = 0;
+    while filled < data.len() {
+        match reader.read(&mut data[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    data.truncate(filled);
+
+    Ok(detect_version(&data))
+}
+
+/// Detects the ITCH version of the messages at the start of `data`.
+pub fn detect_version(data: &[u8]) -> Option<Version> {
+    if let Some(line) = first_line(data) {
+        return detect_text_version(line);
+    }
+
+    // Binary messages are prefixed by their (two byte) length
+    let mut pos = 0;
+    let mut has_seconds = false;
+    while pos + 3 <= data.len() {
+        let size = u16::from_be_bytes([data[pos], data[pos + 1]]) as usize;
+        if size == 0 || pos + 2 + size > data.len() {
+            break;
+        }
+
+        let kind = char::from(data[pos + 2]);
+        match (kind, size) {
+            ('T', 5) => has_seconds = true,
+            ('S', 12) if !has_seconds => return Some(Version::V50),
+            // Messages with tickers settle the difference between Versions 4.0 and 4.1
+            ('R', 18) | ('A', 28) | ('F', 32) | ('P', 36) if has_seconds => {
+                return Some(Version::V40)
+            }
+            ('R', 20) | ('A', 30) | ('F', 34) | ('P', 38) if has_seconds => {
+                return Some(Version::V41)
+            }
+            ('R', 39) | ('A', 36) | ('F', 40) | ('P', 44) if !has_seconds => {
+                return Some(Version::V50)
+            }
+            _ => {}
+        }
+
+        pos += 2 + size;
+    }
+
+    None
+}
+
+// Returns the first record if `data` starts with a newline-terminated line of printable ASCII
+fn first_line(data: &[u8]) -> Option<&[u8]> {
+    let end = data.iter().take(256).position(|&b| b == b'\n')?;
+    let line = data[..end].strip_suffix(b"\r").unwrap_or(&data[..end]);
+    if !line.is_empty() && line.iter().all(|b| (0x20..0x7f).contains(b)) {
+        Some(line)
+    } else {
+        None
+    }
+}
+
+// Version 2.0 records lead with an eight digit timestamp, Version 3.0 records with their type
+fn detect_text_version(line: &[u8]) -> Option<Version> {
+    if line.len() > 8 && line[..8].iter().all(u8::is_ascii_digit) && line[8].is_ascii_alphabetic() {
+        Some(Version::V20)
+    } else if line[0].is_ascii_alphabetic() {
+        Some(Version::V30)
+    } else {
+        None
+    }
+}
+
+/// Infers the trading date (`YYYY-MM-DD`) from the name of a file.
+///
+/// Recognizes Nasdaq names (`S022717-v50`, i.e. `MMDDYY`) as well as eight digit dates in either
+/// `MMDDYYYY` (`01302019.NASDAQ_ITCH50`) or `YYYYMMDD` order.
+pub fn date_from_filename<P: AsRef<Path>>(path: P) -> Option<String> {
+    let filename = path.as_ref().file_name()?.to_str()?;
+
+    for digits in filename
+        .split(|c: char| !c.is_ascii_digit())
+        .filter(|s| !s.is_empty())
+    {
+        let date = match digits.len() {
+            6 => format_date(
+                &format!("20{}", &digits[4..6]),
+                &digits[0..2],
+                &digits[2..4],
+            ),
+            8 if is_year(&digits[0..4]) => format_date(&digits[0..4], &digits[4..6], &digits[6..8]),
+            8 => format_date(&digits[4..8], &digits[0..2], &digits[2..4]),
+            _ => None,
+        };
+        if date.is_some() {
+            return date;
+        }
+    }
+
+    None
+}
+
+/// Infers the ITCH version from the name of a file (e.g. `S022717-v50` or `NASDAQ_ITCH50`).
+pub fn version_from_filename<P: AsRef<Path>>(path: P) -> Option<Version> {
+    let filename = path.as_ref().file_name()?.to_str()?.to_lowercase();
+
+    ["-v", "itch"].iter().find_map(|prefix| {
+        let start = filename.find(prefix)? + prefix.len();
+        filename.get(start..start + 2)?.parse().ok()
+    })
+}
+
+fn is_year(s: &str) -> bool {
+    s.starts_with("19") || s.starts_with("20")
+}
+
+fn format_date(yyyy: &str, mm: &str, dd: &str) -> Option<String> {
+    let valid = is_date(yyyy.parse().ok()?, mm.parse().ok()?, dd.parse().ok()?);
+    valid.then(|| format!("{}-{}-{}", yyyy, mm, dd))
+}
+
+/// Returns whether `day` exists in `month` (1 to 12) of `year`, leap days included.
+pub fn is_date(year: u32, month: u32, day: u32) -> bool {
+    let leap = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+    let days = match month {
+        2 if leap => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        1..=12 => 31,
+        _ => return false,
+    };
+    (1..=days).contains(&day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::message::{test_helpers::message_builders::*, Side};
+
+    #[test]
+    fn detects_version_50() {
+        let data = create_message_sequence(vec![
+            system_event_v50(0, 'O'),
+            add_order_v50(0, 1, Side::Buy, 100, "A", 1),
+        ]);
+        assert_eq!(detect_version(data.get_ref()), Some(Version::V50));
+    }
+
+    #[test]
+    fn detects_version_41() {
+        let data = create_message_sequence(vec![
+            timestamp_v41(0),
+            system_event_v41(0, 'O'),
+            stock_directory_v41(0, "A", 'Q', 'N', 100),
+        ]);
+        assert_eq!(detect_version(data.get_ref()), Some(Version::V41));
+    }
+
+    #[test]
+    fn detects_version_40() {
+        let data = create_message_sequence(vec![
+            timestamp_v41(0),
+            system_event_v41(0, 'O'),
+            add_order_v40(0, 1, Side::Buy, 100, "A", 1),
+        ]);
+        assert_eq!(detect_version(data.get_ref()), Some(Version::V40));
+    }
+
+    #[test]
+    fn detects_text_versions() {
+        let data = create_record_sequence(vec![
+            timestamp_v30(34200),
+            add_order_v30(0, 1, Side::Buy, 100, "A", 1),
+        ]);
+        assert_eq!(detect_version(data.get_ref()), Some(Version::V30));
+
+        let data = create_record_sequence(vec![system_event_v20(0, 'O')]);
+        assert_eq!(detect_version(data.get_ref()), Some(Version::V20));
+    }
+
+    #[test]
+    fn undecided_without_distinguishing_messages() {
+        let data = create_message_sequence(vec![timestamp_v41(0), system_event_v41(0, 'O')]);
+        assert_eq!(detect_version(data.get_ref()), None);
+        assert_eq!(detect_version(&[]), None);
+    }
+
+    #[test]
+    fn sniffs_from_reader() {
+        let data = create_message_sequence(vec![system_event_v50(0, 'O')]);
+        let mut reader = data.get_ref().as_slice();
+        assert_eq!(sniff_version(&mut reader).unwrap(), Some(Version::V50));
+    }
+
+    #[test]
+    fn infers_date_from_nasdaq_filename() {
+        assert_eq!(
+            date_from_filename("data/S022717-v50.txt"),
+            Some("2017-02-27".to_string())
+        );
+    }
+
+    #[test]
+    fn infers_date_from_vendor_filename() {
+        assert_eq!(
+            date_from_filename("01302019.NASDAQ_ITCH50.gz"),
+            Some("2019-01-30".to_string())
+        );
+        assert_eq!(
+            date_from_filename("itch_20190130.bin"),
+            Some("2019-01-30".to_string())
+        );
+    }
+
+    #[test]
+    fn no_date_in_filename() {
+        assert_eq!(date_from_filename("data/itch.bin"), None);
+        assert_eq!(date_from_filename("S132717-v50.txt"), None); // Invalid month
+        assert_eq!(date_from_filename("S023117-v50.txt"), None); // February 31st
+        assert_eq!(date_from_filename("S022917-v50.txt"), None); // Not a leap year
+        assert_eq!(
+            date_from_filename("S022916-v50.txt"),
+            Some("2016-02-29".to_string())
+        );
+    }
+
+    #[test]
+    fn checks_calendar_dates() {
+        assert!(is_date(2000, 2, 29));
+        assert!(!is_date(1900, 2, 29));
+        assert!(is_date(2019, 12, 31));
+        assert!(!is_date(2019, 4, 31));
+        assert!(!is_date(2019, 13, 1));
+        assert!(!is_date(2019, 1, 0));
+    }
+
+    #[test]
+    fn infers_version_from_filename() {
+        assert_eq!(
+            version_from_filename("data/S022717-v50.txt"),
+            Some(Version::V50)
+        );
+        assert_eq!(
+            version_from_filename("01302019.NASDAQ_ITCH50.gz"),
+            Some(Version::V50)
+        );
+        assert_eq!(version_from_filename("S022717-v60.txt"), None);
+        assert_eq!(version_from_filename("01302019.bin"), None);
+    }
+}
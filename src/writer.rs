@@ -5,15 +5,59 @@ use std::error::Error;
 pub use csv::CSV;
 
 use crate::{
-    message::{NOIIMessage, OrderMessage, TradeMessage},
+    message::{
+        DLCRMessage, IPOQuotingMessage, LULDCollarMessage, Locate, MWCBMessage,
+        MarketParticipantMessage, NOIIMessage, OperationalHaltMessage, OrderMessage, RPIIMessage,
+        TradeMessage,
+    },
     orderbook::OrderBookSnapshot,
 };
 
 pub trait Flush {
-    fn flush_order_messages(&self, order_messages: &[OrderMessage]) -> Result<(), Box<dyn Error>>;
-    fn flush_snapshots(&self, snapshots: &[OrderBookSnapshot]) -> Result<(), Box<dyn Error>>;
-    fn flush_trade_messages(&self, trade_messages: &[TradeMessage]) -> Result<(), Box<dyn Error>>;
-    fn flush_noii_messages(&self, noii_messages: &[NOIIMessage]) -> Result<(), Box<dyn Error>>;
+    fn flush_order_messages(
+        &self,
+        order_messages: &[OrderMessage],
+    ) -> Result<(), Box<dyn Error + Send + Sync>>;
+    fn flush_snapshots(
+        &self,
+        snapshots: &[OrderBookSnapshot],
+    ) -> Result<(), Box<dyn Error + Send + Sync>>;
+    fn flush_trade_messages(
+        &self,
+        trade_messages: &[TradeMessage],
+    ) -> Result<(), Box<dyn Error + Send + Sync>>;
+    fn flush_noii_messages(
+        &self,
+        noii_messages: &[NOIIMessage],
+    ) -> Result<(), Box<dyn Error + Send + Sync>>;
+    fn flush_market_participant_messages(
+        &self,
+        market_participant_messages: &[MarketParticipantMessage],
+    ) -> Result<(), Box<dyn Error + Send + Sync>>;
+    fn flush_mwcb_messages(
+        &self,
+        mwcb_messages: &[MWCBMessage],
+    ) -> Result<(), Box<dyn Error + Send + Sync>>;
+    fn flush_ipo_quoting_messages(
+        &self,
+        ipo_quoting_messages: &[IPOQuotingMessage],
+    ) -> Result<(), Box<dyn Error + Send + Sync>>;
+    fn flush_luld_collar_messages(
+        &self,
+        luld_collar_messages: &[LULDCollarMessage],
+    ) -> Result<(), Box<dyn Error + Send + Sync>>;
+    fn flush_operational_halt_messages(
+        &self,
+        operational_halt_messages: &[OperationalHaltMessage],
+    ) -> Result<(), Box<dyn Error + Send + Sync>>;
+    fn flush_rpii_messages(
+        &self,
+        rpii_messages: &[RPIIMessage],
+    ) -> Result<(), Box<dyn Error + Send + Sync>>;
+    fn flush_dlcr_messages(
+        &self,
+        dlcr_messages: &[DLCRMessage],
+    ) -> Result<(), Box<dyn Error + Send + Sync>>;
 }
 
 pub struct Writer<T: Flush> {
@@ -22,7 +66,15 @@ pub struct Writer<T: Flush> {
     snapshots: Vec<OrderBookSnapshot>,
     trade_messages: Vec<TradeMessage>,
     noii_messages: Vec<NOIIMessage>,
+    market_participant_messages: Vec<MarketParticipantMessage>,
+    mwcb_messages: Vec<MWCBMessage>,
+    ipo_quoting_messages: Vec<IPOQuotingMessage>,
+    luld_collar_messages: Vec<LULDCollarMessage>,
+    operational_halt_messages: Vec<OperationalHaltMessage>,
+    rpii_messages: Vec<RPIIMessage>,
+    dlcr_messages: Vec<DLCRMessage>,
     buffer_size: usize,
+    include_locate: bool, // Write stock locate and tracking number columns (Version 5.0)
 }
 
 impl<T: Flush> Writer<T> {
@@ -33,14 +85,37 @@ impl<T: Flush> Writer<T> {
             snapshots: vec![],
             trade_messages: vec![],
             noii_messages: vec![],
+            market_participant_messages: vec![],
+            mwcb_messages: vec![],
+            ipo_quoting_messages: vec![],
+            luld_collar_messages: vec![],
+            operational_halt_messages: vec![],
+            rpii_messages: vec![],
+            dlcr_messages: vec![],
             buffer_size,
+            include_locate: false,
         }
     }
 
+    /// Sets whether the Version 5.0 stock locate and tracking number are written (they are
+    /// dropped by default).
+    pub fn with_locate(mut self, include_locate: bool) -> Self {
+        self.include_locate = include_locate;
+        self
+    }
+
+    fn strip_locate<M: Locate>(&self, mut message: M) -> M {
+        if !self.include_locate {
+            message.clear_locate();
+        }
+        message
+    }
+
     pub fn write_order_message(
         &mut self,
         order_message: OrderMessage,
-    ) -> Result<(), Box<dyn Error>> {
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let order_message = self.strip_locate(order_message);
         self.order_messages.push(order_message);
 
         if self.order_messages.len() >= self.buffer_size {
@@ -51,7 +126,10 @@ impl<T: Flush> Writer<T> {
         Ok(())
     }
 
-    pub fn write_snapshot(&mut self, snapshot: OrderBookSnapshot) -> Result<(), Box<dyn Error>> {
+    pub fn write_snapshot(
+        &mut self,
+        snapshot: OrderBookSnapshot,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
         self.snapshots.push(snapshot);
 
         if self.snapshots.len() >= self.buffer_size {
@@ -65,7 +143,8 @@ impl<T: Flush> Writer<T> {
     pub fn write_trade_message(
         &mut self,
         trade_message: TradeMessage,
-    ) -> Result<(), Box<dyn Error>> {
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let trade_message = self.strip_locate(trade_message);
         self.trade_messages.push(trade_message);
 
         if self.trade_messages.len() >= self.buffer_size {
@@ -76,7 +155,11 @@ impl<T: Flush> Writer<T> {
         Ok(())
     }
 
-    pub fn write_noii_message(&mut self, noii_message: NOIIMessage) -> Result<(), Box<dyn Error>> {
+    pub fn write_noii_message(
+        &mut self,
+        noii_message: NOIIMessage,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let noii_message = self.strip_locate(noii_message);
         self.noii_messages.push(noii_message);
 
         if self.noii_messages.len() >= self.buffer_size {
@@ -86,6 +169,117 @@ impl<T: Flush> Writer<T> {
 
         Ok(())
     }
+
+    pub fn write_market_participant_message(
+        &mut self,
+        market_participant_message: MarketParticipantMessage,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let market_participant_message = self.strip_locate(market_participant_message);
+        self.market_participant_messages
+            .push(market_participant_message);
+
+        if self.market_participant_messages.len() >= self.buffer_size {
+            self.backend
+                .flush_market_participant_messages(&self.market_participant_messages)?;
+            self.market_participant_messages.clear();
+        }
+
+        Ok(())
+    }
+
+    pub fn write_mwcb_message(
+        &mut self,
+        mwcb_message: MWCBMessage,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let mwcb_message = self.strip_locate(mwcb_message);
+        self.mwcb_messages.push(mwcb_message);
+
+        if self.mwcb_messages.len() >= self.buffer_size {
+            self.backend.flush_mwcb_messages(&self.mwcb_messages)?;
+            self.mwcb_messages.clear();
+        }
+
+        Ok(())
+    }
+
+    pub fn write_ipo_quoting_message(
+        &mut self,
+        ipo_quoting_message: IPOQuotingMessage,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let ipo_quoting_message = self.strip_locate(ipo_quoting_message);
+        self.ipo_quoting_messages.push(ipo_quoting_message);
+
+        if self.ipo_quoting_messages.len() >= self.buffer_size {
+            self.backend
+                .flush_ipo_quoting_messages(&self.ipo_quoting_messages)?;
+            self.ipo_quoting_messages.clear();
+        }
+
+        Ok(())
+    }
+
+    pub fn write_luld_collar_message(
+        &mut self,
+        luld_collar_message: LULDCollarMessage,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let luld_collar_message = self.strip_locate(luld_collar_message);
+        self.luld_collar_messages.push(luld_collar_message);
+
+        if self.luld_collar_messages.len() >= self.buffer_size {
+            self.backend
+                .flush_luld_collar_messages(&self.luld_collar_messages)?;
+            self.luld_collar_messages.clear();
+        }
+
+        Ok(())
+    }
+
+    pub fn write_operational_halt_message(
+        &mut self,
+        operational_halt_message: OperationalHaltMessage,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let operational_halt_message = self.strip_locate(operational_halt_message);
+        self.operational_halt_messages
+            .push(operational_halt_message);
+
+        if self.operational_halt_messages.len() >= self.buffer_size {
+            self.backend
+                .flush_operational_halt_messages(&self.operational_halt_messages)?;
+            self.operational_halt_messages.clear();
+        }
+
+        Ok(())
+    }
+
+    pub fn write_rpii_message(
+        &mut self,
+        rpii_message: RPIIMessage,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let rpii_message = self.strip_locate(rpii_message);
+        self.rpii_messages.push(rpii_message);
+
+        if self.rpii_messages.len() >= self.buffer_size {
+            self.backend.flush_rpii_messages(&self.rpii_messages)?;
+            self.rpii_messages.clear();
+        }
+
+        Ok(())
+    }
+
+    pub fn write_dlcr_message(
+        &mut self,
+        dlcr_message: DLCRMessage,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let dlcr_message = self.strip_locate(dlcr_message);
+        self.dlcr_messages.push(dlcr_message);
+
+        if self.dlcr_messages.len() >= self.buffer_size {
+            self.backend.flush_dlcr_messages(&self.dlcr_messages)?;
+            self.dlcr_messages.clear();
+        }
+
+        Ok(())
+    }
 }
 
 impl<T: Flush> Drop for Writer<T> {
@@ -117,5 +311,69 @@ impl<T: Flush> Drop for Writer<T> {
                 Ok(_) => self.noii_messages.clear(),
             };
         }
+
+        if !self.market_participant_messages.is_empty() {
+            match self
+                .backend
+                .flush_market_participant_messages(&self.market_participant_messages)
+            {
+                Err(e) => eprintln!(
+                    "Failed to flush residual market participant messages: {}",
+                    e
+                ),
+                Ok(_) => self.market_participant_messages.clear(),
+            };
+        }
+
+        if !self.mwcb_messages.is_empty() {
+            match self.backend.flush_mwcb_messages(&self.mwcb_messages) {
+                Err(e) => eprintln!("Failed to flush residual mwcb messages: {}", e),
+                Ok(_) => self.mwcb_messages.clear(),
+            };
+        }
+
+        if !self.ipo_quoting_messages.is_empty() {
+            match self
+                .backend
+                .flush_ipo_quoting_messages(&self.ipo_quoting_messages)
+            {
+                Err(e) => eprintln!("Failed to flush residual ipo quoting messages: {}", e),
+                Ok(_) => self.ipo_quoting_messages.clear(),
+            };
+        }
+
+        if !self.luld_collar_messages.is_empty() {
+            match self
+                .backend
+                .flush_luld_collar_messages(&self.luld_collar_messages)
+            {
+                Err(e) => eprintln!("Failed to flush residual luld collar messages: {}", e),
+                Ok(_) => self.luld_collar_messages.clear(),
+            };
+        }
+
+        if !self.operational_halt_messages.is_empty() {
+            match self
+                .backend
+                .flush_operational_halt_messages(&self.operational_halt_messages)
+            {
+                Err(e) => eprintln!("Failed to flush residual operational halt messages: {}", e),
+                Ok(_) => self.operational_halt_messages.clear(),
+            };
+        }
+
+        if !self.rpii_messages.is_empty() {
+            match self.backend.flush_rpii_messages(&self.rpii_messages) {
+                Err(e) => eprintln!("Failed to flush residual rpii messages: {}", e),
+                Ok(_) => self.rpii_messages.clear(),
+            };
+        }
+
+        if !self.dlcr_messages.is_empty() {
+            match self.backend.flush_dlcr_messages(&self.dlcr_messages) {
+                Err(e) => eprintln!("Failed to flush residual dlcr messages: {}", e),
+                Ok(_) => self.dlcr_messages.clear(),
+            };
+        }
     }
 }
@@ -1,19 +1,28 @@
 use std::{
+    collections::HashMap,
     error::Error,
-    fs::{create_dir, create_dir_all, OpenOptions},
+    fs::{create_dir, create_dir_all, File, OpenOptions},
+    io::{BufRead, BufReader, Write},
     path::{Path, PathBuf},
+    sync::Mutex,
 };
 
 use csv::WriterBuilder;
+use serde::Serialize;
 
 use super::Flush;
 use crate::{
-    message::{NOIIMessage, OrderMessage, TradeMessage},
+    message::{
+        DLCRMessage, IPOQuotingMessage, LULDCollarMessage, MWCBMessage, MarketParticipantMessage,
+        NOIIMessage, OperationalHaltMessage, OrderMessage, RPIIMessage, TradeMessage,
+    },
     orderbook::OrderBookSnapshot,
 };
 
 pub struct CSV {
     output_dir: PathBuf,
+    // Headers of the files written to since the backend was created
+    opened: Mutex<HashMap<PathBuf, Vec<u8>>>,
 }
 
 impl CSV {
@@ -23,161 +32,335 @@ impl CSV {
             create_dir_all(&path)?;
         }
 
-        Ok(Self { output_dir: path })
+        Ok(Self {
+            output_dir: path,
+            opened: Mutex::new(HashMap::new()),
+        })
     }
-}
 
-impl Flush for CSV {
-    fn flush_order_messages(&self, order_messages: &[OrderMessage]) -> Result<(), Box<dyn Error>> {
-        let dirpath = self.output_dir.join("orders");
-        if !dirpath.exists() {
-            create_dir(&dirpath)?;
+    // Open `filepath` for appending, writing `headers` if the file does not exist yet, or checking
+    // that they match those of the file otherwise. A file's headers are only read from disk the
+    // first time it is opened, and compared with the cached ones on later flushes
+    fn open(&self, filepath: &Path, headers: &[u8]) -> Result<File, Box<dyn Error + Send + Sync>> {
+        let mut opened = self.opened.lock().unwrap_or_else(|e| e.into_inner());
+        let first = !opened.contains_key(filepath);
+        let file_exists = first && filepath.exists();
+        let existing = match opened.get(filepath) {
+            Some(existing) => Some(existing.clone()),
+            None if file_exists => Some(read_headers(filepath)?),
+            None => None,
+        };
+        if let Some(existing) = existing {
+            check_headers(filepath, &existing, headers)?;
         }
 
-        if order_messages.is_empty() {
-            return Ok(());
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(filepath)?;
+        if first && !file_exists {
+            file.write_all(headers)?;
+        }
+        if first {
+            opened.insert(filepath.to_path_buf(), headers.to_vec());
+        }
+
+        Ok(file)
+    }
+
+    // Append serialized records to `<output_dir>/<table>/<date>.csv`
+    fn append<S: Serialize>(
+        &self,
+        table: &str,
+        date: &str,
+        records: &[S],
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let dirpath = self.output_dir.join(table);
+        if !dirpath.exists() {
+            create_dir(&dirpath)?;
         }
 
-        let date = order_messages[0].date().clone(); // Assume same date across all messages
         let filename = format!("{}.csv", date);
         let filepath = dirpath.join(filename);
-        let file_exists = filepath.exists();
+        let headers = match records.first() {
+            Some(record) => serialized_headers(record)?,
+            None => return Ok(()),
+        };
+        let file = self.open(&filepath, &headers)?;
 
-        let file = OpenOptions::new()
-            .create(true)
-            .append(true)
-            .open(filepath)?;
+        let mut writer = WriterBuilder::new().has_headers(false).from_writer(file);
 
-        let mut writer = WriterBuilder::new()
-            .has_headers(!file_exists)
-            .from_writer(file);
-
-        for message in order_messages {
-            writer.serialize(message)?;
+        for record in records {
+            writer.serialize(record)?;
         }
 
         writer.flush()?;
 
         Ok(())
     }
+}
 
-    fn flush_snapshots(&self, snapshots: &[OrderBookSnapshot]) -> Result<(), Box<dyn Error>> {
+impl Flush for CSV {
+    fn flush_order_messages(
+        &self,
+        order_messages: &[OrderMessage],
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        match order_messages.first() {
+            // Assume same date across all messages
+            Some(message) => self.append("orders", message.date(), order_messages),
+            None => Ok(()),
+        }
+    }
+
+    fn flush_snapshots(
+        &self,
+        snapshots: &[OrderBookSnapshot],
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
         let dirpath = self.output_dir.join("books");
         if !dirpath.exists() {
             create_dir(&dirpath)?;
         }
 
+        if snapshots.is_empty() {
+            return Ok(());
+        }
+
         let date = snapshots[0].date.clone(); // Assume same date across all messages
         let filename = format!("{}.csv", date);
         let filepath = dirpath.join(filename);
-        let file_exists = filepath.exists();
-
-        let file = OpenOptions::new()
-            .create(true)
-            .append(true)
-            .open(filepath)?;
-
-        let mut writer = WriterBuilder::new()
-            .has_headers(false) // We'll write headers manually
-            .from_writer(file);
 
-        if !snapshots.is_empty() {
-            // Write headers if file is new
-            if !file_exists {
-                let levels_count = snapshots[0].data.len() / 4; // levels per side
-                let mut headers = vec!["ticker".to_string(), "timestamp".to_string()];
+        let levels_count = snapshots[0].data.len() / 4; // levels per side
+        let mut headers = vec![
+            "ticker".to_string(),
+            "timestamp".to_string(),
+            "state".to_string(),
+        ];
 
-                for i in 1..=levels_count {
-                    headers.push(format!("bid_price_{}", i));
-                    headers.push(format!("bid_size_{}", i));
-                }
+        for i in 1..=levels_count {
+            headers.push(format!("bid_price_{}", i));
+            headers.push(format!("bid_size_{}", i));
+        }
 
-                for i in 1..=levels_count {
-                    headers.push(format!("ask_price_{}", i));
-                    headers.push(format!("ask_size_{}", i));
-                }
+        for i in 1..=levels_count {
+            headers.push(format!("ask_price_{}", i));
+            headers.push(format!("ask_size_{}", i));
+        }
 
-                writer.write_record(&headers)?;
-            }
+        let file = self.open(&filepath, (headers.join(",") + "\n").as_bytes())?;
+        let mut writer = WriterBuilder::new()
+            .has_headers(false) // Headers are written when the file is created
+            .from_writer(file);
 
-            // Write data rows
-            for snapshot in snapshots {
-                let mut record = vec![snapshot.ticker.clone(), snapshot.timestamp.to_string()];
-                for val in &snapshot.data {
-                    record.push(val.to_string());
-                }
-                writer.write_record(&record)?;
+        // Write data rows
+        for snapshot in snapshots {
+            let mut record = vec![
+                snapshot.ticker.to_string(),
+                snapshot.timestamp.to_string(),
+                snapshot.state.to_string(),
+            ];
+            for val in &snapshot.data {
+                record.push(val.to_string());
             }
-
-            writer.flush()?;
+            writer.write_record(&record)?;
         }
 
+        writer.flush()?;
+
         Ok(())
     }
 
-    fn flush_trade_messages(&self, trade_messages: &[TradeMessage]) -> Result<(), Box<dyn Error>> {
-        let dirpath = self.output_dir.join("trades");
-        if !dirpath.exists() {
-            create_dir(&dirpath)?;
+    fn flush_trade_messages(
+        &self,
+        trade_messages: &[TradeMessage],
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        match trade_messages.first() {
+            // Assume same date across all messages
+            Some(message) => self.append("trades", message.date(), trade_messages),
+            None => Ok(()),
         }
+    }
 
-        if trade_messages.is_empty() {
-            return Ok(());
+    fn flush_noii_messages(
+        &self,
+        noii_messages: &[NOIIMessage],
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        match noii_messages.first() {
+            // Assume same date across all messages
+            Some(message) => self.append("noii", message.date(), noii_messages),
+            None => Ok(()),
         }
+    }
 
-        let date = trade_messages[0].date().clone(); // Assume same date across all messages
-        let filename = format!("{}.csv", date);
-        let filepath = dirpath.join(filename);
-        let file_exists = filepath.exists();
-
-        let file = OpenOptions::new()
-            .create(true)
-            .append(true)
-            .open(filepath)?;
+    fn flush_market_participant_messages(
+        &self,
+        market_participant_messages: &[MarketParticipantMessage],
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        match market_participant_messages.first() {
+            // Assume same date across all messages
+            Some(message) => {
+                self.append("participants", message.date(), market_participant_messages)
+            }
+            None => Ok(()),
+        }
+    }
 
-        let mut writer = WriterBuilder::new()
-            .has_headers(!file_exists)
-            .from_writer(file);
+    fn flush_mwcb_messages(
+        &self,
+        mwcb_messages: &[MWCBMessage],
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        match mwcb_messages.first() {
+            // Assume same date across all messages
+            Some(message) => self.append("mwcb", message.date(), mwcb_messages),
+            None => Ok(()),
+        }
+    }
 
-        for message in trade_messages {
-            writer.serialize(message)?;
+    fn flush_ipo_quoting_messages(
+        &self,
+        ipo_quoting_messages: &[IPOQuotingMessage],
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        match ipo_quoting_messages.first() {
+            // Assume same date across all messages
+            Some(message) => self.append("ipo", message.date(), ipo_quoting_messages),
+            None => Ok(()),
         }
+    }
 
-        writer.flush()?;
+    fn flush_luld_collar_messages(
+        &self,
+        luld_collar_messages: &[LULDCollarMessage],
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        match luld_collar_messages.first() {
+            // Assume same date across all messages
+            Some(message) => self.append("luld", message.date(), luld_collar_messages),
+            None => Ok(()),
+        }
+    }
 
-        Ok(())
+    fn flush_operational_halt_messages(
+        &self,
+        operational_halt_messages: &[OperationalHaltMessage],
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        match operational_halt_messages.first() {
+            // Assume same date across all messages
+            Some(message) => self.append("halts", message.date(), operational_halt_messages),
+            None => Ok(()),
+        }
     }
 
-    fn flush_noii_messages(&self, noii_messages: &[NOIIMessage]) -> Result<(), Box<dyn Error>> {
-        let dirpath = self.output_dir.join("noii");
-        if !dirpath.exists() {
-            create_dir(&dirpath)?;
+    fn flush_rpii_messages(
+        &self,
+        rpii_messages: &[RPIIMessage],
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        match rpii_messages.first() {
+            // Assume same date across all messages
+            Some(message) => self.append("rpii", message.date(), rpii_messages),
+            None => Ok(()),
         }
+    }
 
-        if noii_messages.is_empty() {
-            return Ok(());
+    fn flush_dlcr_messages(
+        &self,
+        dlcr_messages: &[DLCRMessage],
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        match dlcr_messages.first() {
+            // Assume same date across all messages
+            Some(message) => self.append("dlcr", message.date(), dlcr_messages),
+            None => Ok(()),
         }
+    }
+}
 
-        let date = noii_messages[0].date().clone(); // Assume same date across all messages
-        let filename = format!("{}.csv", date);
-        let filepath = dirpath.join(filename);
-        let file_exists = filepath.exists();
+// Returns the header line written for records like `record`
+fn serialized_headers<S: Serialize>(record: &S) -> Result<Vec<u8>, Box<dyn Error + Send + Sync>> {
+    let mut writer = WriterBuilder::new().from_writer(vec![]);
+    writer.serialize(record)?;
+    let data = writer.into_inner().map_err(|e| e.into_error())?;
+    let end = data
+        .iter()
+        .position(|&b| b == b'\n')
+        .map_or(data.len(), |i| i + 1);
+    Ok(data[..end].to_vec())
+}
 
-        let file = OpenOptions::new()
-            .create(true)
-            .append(true)
-            .open(filepath)?;
+// Returns the first line of `filepath`
+fn read_headers(filepath: &Path) -> std::io::Result<Vec<u8>> {
+    let mut existing = vec![];
+    BufReader::new(File::open(filepath)?).read_until(b'\n', &mut existing)?;
+    Ok(existing)
+}
 
-        let mut writer = WriterBuilder::new()
-            .has_headers(!file_exists)
-            .from_writer(file);
+// Rows are only appended to a file with the same columns (e.g. written with the same `--locate`
+// setting and order book depth), so that they line up with its headers
+fn check_headers(
+    filepath: &Path,
+    existing: &[u8],
+    headers: &[u8],
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let existing = String::from_utf8_lossy(existing);
+    let headers = String::from_utf8_lossy(headers);
+    if existing.trim_end() != headers.trim_end() {
+        return Err(format!(
+            "Cannot append to {}: its columns ({}) differ from those of the rows to write ({})",
+            filepath.display(),
+            existing.trim_end(),
+            headers.trim_end()
+        )
+        .into());
+    }
+    Ok(())
+}
 
-        for message in noii_messages {
-            writer.serialize(message)?;
+#[cfg(test)]
+mod tests {
+    use assert_fs::TempDir;
+
+    use super::*;
+    use crate::message::{
+        test_helpers::message_builders::*, AddOrder, Context, IntoOrderMessage, Locate,
+        ReadMessage, Side, Version,
+    };
+
+    fn order_message(locate: bool) -> OrderMessage {
+        let mut data = with_locate(add_order_v50(1, 1, Side::Buy, 10, "AAPL", 100), 7, 0);
+        let add = AddOrder::read(&mut data, &Version::V50, &mut Context::new()).unwrap();
+        let mut message = add.into_order_message("2024-01-02".into());
+        if !locate {
+            message.clear_locate();
         }
+        message
+    }
 
-        writer.flush()?;
+    #[test]
+    fn refuses_to_append_rows_with_other_columns() {
+        let dir = TempDir::new().unwrap();
+        let csv = CSV::new(dir.path()).unwrap();
+        csv.flush_order_messages(&[order_message(false)]).unwrap();
+        csv.flush_order_messages(&[order_message(false)]).unwrap();
+        assert!(csv.flush_order_messages(&[order_message(true)]).is_err());
+
+        let written = std::fs::read_to_string(dir.path().join("orders/2024-01-02.csv")).unwrap();
+        assert_eq!(written.lines().count(), 3);
+        assert!(!written.contains("stock_locate"));
+    }
 
-        Ok(())
+    #[test]
+    fn reads_headers_of_existing_files_once() {
+        let dir = TempDir::new().unwrap();
+        CSV::new(dir.path())
+            .unwrap()
+            .flush_order_messages(&[order_message(false)])
+            .unwrap();
+
+        let csv = CSV::new(dir.path()).unwrap();
+        assert!(csv.flush_order_messages(&[order_message(true)]).is_err());
+        csv.flush_order_messages(&[order_message(false)]).unwrap();
+
+        // Later flushes compare with the cached headers instead of reading the file again
+        let path = dir.path().join("orders/2024-01-02.csv");
+        std::fs::write(&path, "other,columns\n").unwrap();
+        csv.flush_order_messages(&[order_message(false)]).unwrap();
+        assert!(csv.flush_order_messages(&[order_message(true)]).is_err());
+        assert_eq!(std::fs::read_to_string(&path).unwrap().lines().count(), 2);
     }
 }